@@ -0,0 +1,401 @@
+use std::cmp;
+use std::collections::{BTreeMap, HashMap};
+
+use syn::Ident;
+
+use check::{App, Kind};
+
+use syntax::error::*;
+
+pub type Ownerships = HashMap<Ident, Ownership>;
+
+pub enum Ownership {
+    /// Owned or co-owned by tasks that run at the same priority; `read_only`
+    /// if none of them writes to the resource
+    Owned { priority: u8, read_only: bool },
+    /// Shared by tasks that run at different priorities.
+    ///
+    /// `ceiling` is the maximum value across all the task priorities;
+    /// `read_ceiling` is the maximum value across the priorities of the tasks
+    /// that write to the resource (0 if there are none).
+    Shared { ceiling: u8, read_ceiling: u8 },
+    /// Multi-unit resource. Its ceiling is dynamic: `ceilings[n]` is the
+    /// ceiling while `n` units are available, the maximum priority across the
+    /// tasks that may hold more than `n` units at once
+    Units { ceilings: Vec<u8> },
+}
+
+impl Ownership {
+    /// Ceiling of the resource when it's claimed for writing
+    ///
+    /// For multi-unit resources this is the highest of its dynamic ceilings,
+    /// the one reached when no unit is available.
+    pub fn ceiling(&self) -> u8 {
+        match *self {
+            Ownership::Owned { priority, .. } => priority,
+            Ownership::Shared { ceiling, .. } => ceiling,
+            Ownership::Units { ref ceilings } => ceilings[0],
+        }
+    }
+
+    /// Ceiling of the resource when it's claimed for reading; readers don't
+    /// block each other, only writers
+    pub fn read_ceiling(&self) -> u8 {
+        match *self {
+            Ownership::Owned { priority, .. } => priority,
+            Ownership::Shared { read_ceiling, .. } => read_ceiling,
+            Ownership::Units { ref ceilings } => ceilings[0],
+        }
+    }
+
+    /// The dynamic ceilings of a multi-unit resource
+    pub fn unit_ceilings(&self) -> Option<&[u8]> {
+        match *self {
+            Ownership::Units { ref ceilings } => Some(ceilings),
+            _ => None,
+        }
+    }
+
+    pub fn is_owned(&self) -> bool {
+        match *self {
+            Ownership::Owned { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+/// Computes the ceilings of the resources
+///
+/// Ceilings are computed from the priorities of the tasks, not from their
+/// preemption thresholds: a task that runs above its priority (`threshold`) is
+/// handed a `Threshold` token at that level so its claims on resources whose
+/// ceiling doesn't exceed its threshold compile down to plain accesses.
+///
+/// The non-maskable exceptions (`NMI` and `HARD_FAULT`) are never part of a
+/// ceiling: no critical section can mask them so they can't access resources
+/// nor queues (see `check::task`). The highest ceiling is thus `1 <<
+/// NVIC_PRIO_BITS`, which `claim` implements by disabling interrupts.
+pub fn app(app: &App) -> Ownerships {
+    let mut ownerships = HashMap::new();
+
+    for resource in &app.idle.resources {
+        if app.idle.read_only.contains(resource) {
+            read(&mut ownerships, resource, 0);
+        } else {
+            access(&mut ownerships, resource, 0);
+        }
+    }
+
+    for task in app.tasks.values() {
+        for resource in &task.resources {
+            if task.read_only.contains(resource) {
+                read(&mut ownerships, resource, task.priority);
+            } else {
+                access(&mut ownerships, resource, task.priority);
+            }
+        }
+    }
+
+    // the ceilings of the multi-unit resources depend on how many units each
+    // context holds at once
+    let holders = app.tasks
+        .values()
+        .map(|task| (task.priority, &task.units))
+        .chain(Some((0, &app.idle.units)))
+        .collect::<Vec<_>>();
+
+    for (resource, &len) in &app.units {
+        let ceilings = (0..len + 1)
+            .map(|available| {
+                holders
+                    .iter()
+                    .filter(|&&(_, units)| units.get(resource).cloned().unwrap_or(0) > available)
+                    .map(|&(priority, _)| priority)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        ownerships.insert(resource.clone(), Ownership::Units { ceilings });
+    }
+
+    for access in queues(app) {
+        ::analyze::access(&mut ownerships, &access.queue, access.priority);
+    }
+
+    ownerships
+}
+
+/// An access to one of the queues of the runtime
+pub struct QueueAccess {
+    /// The queue; see `fq`, `rq` and `tq`
+    pub queue: Ident,
+    /// The context that accesses the queue: a task, `idle` or `SYS_TICK`, the
+    /// handler of the timer queue. The dispatchers access the queues on behalf
+    /// of the software tasks they run.
+    pub context: Ident,
+    /// Priority the context runs at
+    pub priority: u8,
+}
+
+/// Lists the accesses to the queues of the runtime; each one is a critical
+/// section on the queue
+pub fn queues(app: &App) -> Vec<QueueAccess> {
+    let mut accesses = vec![];
+    {
+        let mut push = |queue: Ident, context: &Ident, priority: u8| {
+            accesses.push(QueueAccess {
+                queue,
+                context: context.clone(),
+                priority,
+            })
+        };
+
+        let idle = Ident::from("idle");
+
+        // the queues of the software tasks are accessed by their spawners
+        // (`init` runs with interrupts disabled so it doesn't count) and by the
+        // dispatchers
+        let spawners = app.tasks
+            .iter()
+            .map(|(name, task)| (name, task.priority, &task.spawn))
+            .chain(Some((&idle, 0, &app.idle.spawn)));
+
+        for (spawner, priority, spawn) in spawners {
+            for name in spawn {
+                push(fq(name), spawner, priority);
+                push(rq(app.tasks[name].priority), spawner, priority);
+            }
+        }
+
+        for (name, task) in &app.tasks {
+            if task.kind.is_software() {
+                push(fq(name), name, task.priority);
+                push(rq(task.priority), name, task.priority);
+            }
+        }
+
+        // the timer queue is accessed by the schedulers, which also take
+        // message slots from the free queues, and by the `SYS_TICK` handler,
+        // which moves the tasks whose time has come into the ready queues
+        let schedulers = app.tasks
+            .iter()
+            .map(|(name, task)| (name, task.priority, &task.schedule))
+            .chain(Some((&idle, 0, &app.idle.schedule)));
+
+        for (scheduler, priority, schedule) in schedulers {
+            for name in schedule {
+                push(fq(name), scheduler, priority);
+                push(tq(), scheduler, priority);
+            }
+        }
+
+        if let Some(timer_queue) = timer_queue(app) {
+            let handler = Ident::from("SYS_TICK");
+            push(tq(), &handler, timer_queue.priority);
+
+            for name in &timer_queue.tasks {
+                let task = &app.tasks[name];
+
+                // the handler takes the message slots of the periodic tasks
+                if task.period.is_some() {
+                    push(fq(name), &handler, timer_queue.priority);
+                }
+
+                push(rq(task.priority), &handler, timer_queue.priority);
+            }
+        }
+    }
+
+    accesses
+}
+
+/// Records an access to `resource` from a context that runs at `priority`
+fn access(ownerships: &mut Ownerships, resource: &Ident, priority: u8) {
+    record(ownerships, resource, priority, true)
+}
+
+/// Records a read-only access to `resource` from a context that runs at
+/// `priority`
+fn read(ownerships: &mut Ownerships, resource: &Ident, priority: u8) {
+    record(ownerships, resource, priority, false)
+}
+
+fn record(ownerships: &mut Ownerships, resource: &Ident, priority: u8, write: bool) {
+    let writer = if write { priority } else { 0 };
+
+    if let Some(ownership) = ownerships.get_mut(resource) {
+        match *ownership {
+            Ownership::Owned {
+                priority: owner,
+                read_only,
+            } => {
+                if owner == priority {
+                    *ownership = Ownership::Owned {
+                        priority,
+                        read_only: read_only && !write,
+                    };
+                } else {
+                    *ownership = Ownership::Shared {
+                        ceiling: cmp::max(owner, priority),
+                        read_ceiling: cmp::max(if read_only { 0 } else { owner }, writer),
+                    };
+                }
+            }
+            Ownership::Shared {
+                ceiling,
+                read_ceiling,
+            } => {
+                *ownership = Ownership::Shared {
+                    ceiling: cmp::max(ceiling, priority),
+                    read_ceiling: cmp::max(read_ceiling, writer),
+                };
+            }
+            Ownership::Units { .. } => unreachable!(),
+        }
+
+        return;
+    }
+
+    ownerships.insert(
+        resource.clone(),
+        Ownership::Owned {
+            priority,
+            read_only: !write,
+        },
+    );
+}
+
+/// Name under which the ceiling of the free queue (the free message slots) of
+/// the software task `task` is recorded in the `Ownerships`
+///
+/// It's not a valid identifier so it can't collide with a resource.
+pub fn fq(task: &Ident) -> Ident {
+    Ident::from(format!("FQ::{}", task))
+}
+
+/// Name under which the ceiling of the ready queue of the software tasks that
+/// run at `priority` is recorded in the `Ownerships`
+pub fn rq(priority: u8) -> Ident {
+    Ident::from(format!("RQ::{}", priority))
+}
+
+/// Name under which the ceiling of the timer queue is recorded in the
+/// `Ownerships`
+pub fn tq() -> Ident {
+    Ident::from("TQ::SYS_TICK")
+}
+
+/// The timer queue that releases the scheduled and the periodic software tasks
+pub struct TimerQueue {
+    /// Priority of the `SYS_TICK` handler; the highest priority among `tasks`
+    pub priority: u8,
+    /// The software tasks that can be scheduled, and the periodic tasks, sorted
+    /// by name
+    pub tasks: Vec<Ident>,
+    /// Capacity of the queue: the sum of the capacities of the scheduled
+    /// tasks plus one entry per periodic task
+    pub capacity: usize,
+}
+
+/// Returns the timer queue of the application, if any context uses `schedule`
+/// or any task is periodic
+pub fn timer_queue(app: &App) -> Option<TimerQueue> {
+    let mut tasks = app.tasks
+        .values()
+        .flat_map(|task| &task.schedule)
+        .chain(&app.init.schedule)
+        .chain(&app.idle.schedule)
+        .chain(
+            app.tasks
+                .iter()
+                .filter(|&(_, task)| task.period.is_some())
+                .map(|(name, _)| name),
+        )
+        .collect::<Vec<_>>();
+
+    if tasks.is_empty() {
+        return None;
+    }
+
+    tasks.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+    tasks.dedup();
+
+    let priority = tasks
+        .iter()
+        .map(|name| app.tasks[*name].priority)
+        .max()
+        .unwrap_or(1);
+    // a periodic task always has a single entry in the queue: its next release
+    let capacity = tasks
+        .iter()
+        .map(|name| {
+            if app.tasks[*name].period.is_some() {
+                1
+            } else {
+                ::analyze::capacity(app, name)
+            }
+        })
+        .sum();
+
+    Some(TimerQueue {
+        priority,
+        tasks: tasks.into_iter().cloned().collect(),
+        capacity,
+    })
+}
+
+fn capacity(app: &App, name: &Ident) -> usize {
+    match app.tasks[name].kind {
+        Kind::Software { capacity, .. } => usize::from(capacity),
+        _ => unreachable!(),
+    }
+}
+
+pub type Dispatchers = BTreeMap<u8, Dispatcher>;
+
+/// Interrupt that runs the software tasks of a priority level
+pub struct Dispatcher {
+    pub interrupt: Ident,
+    /// The software tasks that run at this priority level, sorted by name
+    pub tasks: Vec<Ident>,
+    /// Capacity of the ready queue; the sum of the capacities of `tasks`
+    pub capacity: usize,
+}
+
+/// Binds each priority level used by software tasks to one of the
+/// `free_interrupts`, in order, starting from the lowest priority
+pub fn dispatchers(app: &App) -> Result<Dispatchers> {
+    let mut levels: BTreeMap<u8, Vec<&Ident>> = BTreeMap::new();
+    for (name, task) in &app.tasks {
+        if task.kind.is_software() {
+            levels.entry(task.priority).or_default().push(name);
+        }
+    }
+
+    ensure!(
+        levels.len() <= app.free_interrupts.len(),
+        "software tasks run at {} different priorities but only {} `free_interrupts` were given",
+        levels.len(),
+        app.free_interrupts.len()
+    );
+
+    Ok(levels
+        .into_iter()
+        .zip(&app.free_interrupts)
+        .map(|((priority, mut tasks), interrupt)| {
+            tasks.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+            let capacity = tasks.iter().map(|name| ::analyze::capacity(app, name)).sum();
+
+            (
+                priority,
+                Dispatcher {
+                    interrupt: interrupt.clone(),
+                    tasks: tasks.into_iter().cloned().collect(),
+                    capacity,
+                },
+            )
+        })
+        .collect())
+}
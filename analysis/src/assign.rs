@@ -0,0 +1,232 @@
+//! Automatic priority assignment (`priority: auto`)
+//!
+//! At most `1 << nvic_prio_bits` priority levels are used, the number of
+//! levels of the device; tasks share a level when there are not enough levels.
+//!
+//! If every task declares its `wcet` priorities are assigned using a variant of
+//! Audsley's algorithm: starting from the lowest priority level, each level is
+//! given to the first task (longest deadline first) that meets its deadline,
+//! according to the response time analysis (see `rta`), assuming that all the
+//! tasks that are still unassigned have higher priority. While there are more
+//! unassigned tasks than levels left more tasks are given the same level, as
+//! long as all the tasks at that level meet their deadlines. The assignment
+//! fails if no task meets its deadline at some level; another assignment may
+//! still exist.
+//!
+//! Otherwise priorities are assigned in deadline monotonic order: the shorter
+//! the relative deadline the higher the priority; tasks with the same deadline
+//! share a priority level, and so do tasks with neighbouring deadlines if there
+//! are more distinct deadlines than levels.
+use std::cmp;
+use std::collections::BTreeSet;
+
+use syn::Ident;
+
+use analyze;
+use blocking::CriticalSections;
+use check::App;
+use diag::Diagnostics;
+use rta::{self, Wcets};
+
+/// Assigns the `auto` priorities; `measured` are critical section lengths
+/// measured on the target (see `rta::sections`)
+///
+/// If no assignment is found the errors are recorded in `diag`, at the
+/// priorities of the tasks that couldn't be assigned one.
+pub fn app(app: &mut App, measured: &CriticalSections, diag: &mut Diagnostics) {
+    if !app.auto_priority {
+        return;
+    }
+
+    let bits = match app.nvic_prio_bits {
+        Some(bits) => bits,
+        None => {
+            diag.error(
+                &["nvic_prio_bits"],
+                "`priority: auto` needs the number of priority bits, `nvic_prio_bits`".to_owned(),
+            );
+            return;
+        }
+    };
+    // highest priority level of the device
+    let max = cmp::min(1u32 << bits, u32::from(::std::u8::MAX)) as u8;
+
+    let wcets = app.tasks
+        .iter()
+        .filter_map(|(name, task)| task.wcet.map(|wcet| (name.clone(), wcet)))
+        .collect::<Wcets>();
+
+    if wcets.len() == app.tasks.len() {
+        if !audsley(app, &wcets, measured, max, diag) {
+            return;
+        }
+    } else {
+        deadline_monotonic(app, max);
+    }
+
+    // `threshold` can't be used along `priority: auto`
+    for task in app.tasks.values_mut() {
+        if !task.kind.is_non_maskable() {
+            task.threshold = task.priority;
+        }
+    }
+}
+
+fn deadline_monotonic(app: &mut App, max: u8) {
+    // the non-maskable exceptions keep their fixed priority
+    let deadlines = app.tasks
+        .values()
+        .filter(|task| !task.kind.is_non_maskable())
+        .map(|task| task.deadline)
+        .collect::<BTreeSet<_>>();
+    let deadlines_len = deadlines.len();
+    let levels = cmp::min(deadlines_len, usize::from(max));
+
+    for task in app.tasks.values_mut() {
+        if task.kind.is_non_maskable() {
+            continue;
+        }
+
+        let rank = deadlines.iter().position(|&d| d == task.deadline).unwrap();
+        // neighbouring deadlines share a level if there are not enough levels
+        task.priority = (levels - rank * levels / deadlines_len) as u8;
+    }
+}
+
+/// Returns `false` if no assignment was found
+fn audsley(
+    app: &mut App,
+    wcets: &Wcets,
+    measured: &CriticalSections,
+    max: u8,
+    diag: &mut Diagnostics,
+) -> bool {
+    let sections = rta::sections(app, measured);
+
+    // candidates for the lowest priority level come first
+    let mut unassigned = app.tasks
+        .iter()
+        .filter(|&(_, task)| !task.kind.is_non_maskable())
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+    unassigned.sort_by(|a, b| {
+        app.tasks[b]
+            .deadline
+            .cmp(&app.tasks[a].deadline)
+            .then_with(|| a.as_ref().cmp(b.as_ref()))
+    });
+
+    let mut level = 1;
+    while !unassigned.is_empty() {
+        let mut assigned = vec![];
+
+        loop {
+            // share the level only if there are not enough levels left
+            if !assigned.is_empty() && unassigned.len() <= usize::from(max - level) {
+                break;
+            }
+
+            let mut position = None;
+            for (i, name) in unassigned.iter().enumerate() {
+                let mut candidates = assigned.clone();
+                candidates.push(name.clone());
+
+                place(app, &candidates, &unassigned, level, max);
+                match schedulable(app, wcets, &sections, &candidates, level, diag) {
+                    Some(true) => {
+                        position = Some(i);
+                        break;
+                    }
+                    Some(false) => {}
+                    None => return false,
+                }
+            }
+
+            match position {
+                Some(i) => assigned.push(unassigned.remove(i)),
+                None => break,
+            }
+        }
+
+        if assigned.is_empty() {
+            unassigned.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+            for name in &unassigned {
+                diag.error(
+                    &["tasks", name.as_ref(), "priority"],
+                    format!(
+                        "no priority assignment was found that makes the task set schedulable: \
+                         task `{}`, like the other unassigned tasks, doesn't meet its deadline \
+                         at priority {}",
+                        name, level
+                    ),
+                );
+            }
+
+            return false;
+        }
+
+        for name in &assigned {
+            app.tasks.get_mut(name).unwrap().priority = level;
+        }
+
+        if !unassigned.is_empty() {
+            if level == max {
+                unassigned.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+                for name in &unassigned {
+                    diag.error(
+                        &["tasks", name.as_ref(), "priority"],
+                        format!(
+                            "no priority assignment was found that makes the task set \
+                             schedulable with {} priority levels: task `{}` has no level left",
+                            max, name
+                        ),
+                    );
+                }
+
+                return false;
+            }
+
+            level += 1;
+        }
+    }
+
+    true
+}
+
+/// Places the `candidates` at priority `level` and the rest of the
+/// `unassigned` tasks at the next level
+fn place(app: &mut App, candidates: &[Ident], unassigned: &[Ident], level: u8, max: u8) {
+    for name in unassigned.iter().chain(candidates) {
+        let priority = if candidates.contains(name) {
+            level
+        } else {
+            cmp::min(level + 1, max)
+        };
+
+        let task = app.tasks.get_mut(name).unwrap();
+        task.priority = priority;
+        task.threshold = priority;
+    }
+}
+
+/// Whether the `candidates`, placed at priority `level`, meet their deadlines
+/// along the tasks already assigned a lower priority; `None` if the analysis
+/// failed
+fn schedulable(
+    app: &App,
+    wcets: &Wcets,
+    sections: &CriticalSections,
+    candidates: &[Ident],
+    level: u8,
+    diag: &mut Diagnostics,
+) -> Option<bool> {
+    let ownerships = analyze::app(app);
+    let responses = rta::app(app, &ownerships, wcets, sections, diag)?;
+
+    Some(app.tasks.iter().all(|(name, task)| {
+        task.kind.is_non_maskable() || (task.priority >= level && !candidates.contains(name))
+            || responses[name].is_schedulable()
+    }))
+}
@@ -0,0 +1,246 @@
+//! Blocking time analysis under the Stack Resource Policy
+//!
+//! Under SRP a task can be blocked at most once, for the duration of a single
+//! critical section of a lower priority task (or `idle`) on a resource whose
+//! ceiling is greater or equal to the priority of the blocked task:
+//!
+//! ``` text
+//! B_i = max { cs(j, r) | P_j < P_i, r in resources(j), ceiling(j, r) >= P_i }
+//! ```
+//!
+//! where `ceiling(j, r)` is the read ceiling of `r` if `j` only reads `r` and
+//! its (write) ceiling otherwise.
+//!
+//! A lower priority task whose preemption threshold is greater or equal to the
+//! priority of the blocked task behaves like a critical section that spans
+//! its whole execution, so its worst case execution time is also a candidate:
+//!
+//! ``` text
+//! B_i = max(B_i, max { C_j | P_j < P_i <= TH_j })
+//! ```
+//!
+//! The runtime enqueues and dequeues the messages of the software tasks and
+//! the timer queue entries in critical sections of its own, on behalf of the
+//! spawners, the schedulers, the dispatchers and the `SYS_TICK` handler. These
+//! are accounted for like critical sections on the queues, whose length is the
+//! `queue` overhead declared in the `app!` macro.
+use std::collections::HashMap;
+
+use syn::Ident;
+
+use analyze::{self, Ownerships};
+use check::App;
+use rta::Wcets;
+
+use syntax::error::*;
+
+/// Length, in clock cycles, of the longest critical section on each resource,
+/// per task
+///
+/// The critical sections of `idle` are stored under the `idle` key.
+pub type CriticalSections = HashMap<Ident, HashMap<Ident, u32>>;
+
+pub type Blockings = HashMap<Ident, Blocking>;
+
+/// Worst case blocking of a task
+pub struct Blocking {
+    /// Worst case blocking time (`B`)
+    pub time: u32,
+    /// The critical section responsible for `time`; `None` if the task can't
+    /// be blocked
+    pub blocker: Option<Blocker>,
+}
+
+/// A critical section of a lower priority task
+pub struct Blocker {
+    /// Task that holds the resource (`idle` for the idle loop)
+    pub task: Ident,
+    /// `None` if the task blocks by running above its priority (`threshold`)
+    pub resource: Option<Ident>,
+}
+
+/// Critical sections declared in the `app!` macro
+pub fn declared(app: &App) -> CriticalSections {
+    let mut sections = HashMap::new();
+
+    if !app.idle.critical_sections.is_empty() {
+        sections.insert(Ident::from("idle"), app.idle.critical_sections.clone());
+    }
+
+    for (name, task) in &app.tasks {
+        if !task.critical_sections.is_empty() {
+            sections.insert(name.clone(), task.critical_sections.clone());
+        }
+    }
+
+    sections
+}
+
+/// Parses critical section lengths measured on the target
+///
+/// Each line of the input has the form `$TASK $RESOURCE $CYCLES`. Empty lines
+/// and lines that start with `#` are ignored. If a critical section appears
+/// more than once the longest measurement is kept.
+pub fn measured(input: &str) -> Result<CriticalSections> {
+    let mut sections: CriticalSections = HashMap::new();
+
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        ensure!(
+            fields.len() == 3,
+            "line {}: expected `$TASK $RESOURCE $CYCLES`, found `{}`",
+            i + 1,
+            line
+        );
+
+        let cycles = fields[2]
+            .parse::<u32>()
+            .map_err(|_| format!("line {}: `{}` is not a valid cycle count", i + 1, fields[2]))?;
+
+        let length = sections
+            .entry(Ident::from(fields[0]))
+            .or_default()
+            .entry(Ident::from(fields[1]))
+            .or_insert(0);
+
+        if cycles > *length {
+            *length = cycles;
+        }
+    }
+
+    Ok(sections)
+}
+
+/// Length of the longest critical section of `task` on `resource`
+///
+/// If the length is not known it's bounded by the worst case execution time of
+/// `task`, if present in `wcets`.
+pub fn length(
+    sections: &CriticalSections,
+    wcets: &Wcets,
+    task: &Ident,
+    resource: &Ident,
+) -> Option<u32> {
+    sections
+        .get(task)
+        .and_then(|s| s.get(resource))
+        .or_else(|| wcets.get(task))
+        .cloned()
+}
+
+/// Computes the blocking bound of each task
+///
+/// Critical sections whose length is not known are bounded by the worst case
+/// execution time of the task that contains them, if present in `wcets`. The
+/// critical sections of `idle` are only accounted for if their length is
+/// known. Lower priority tasks that run above their priority (`threshold`) are
+/// accounted for if their worst case execution time is in `wcets`. The critical
+/// sections on the queues of the runtime are as long as the declared `queue`
+/// overhead; if it's missing they are treated like the critical sections on
+/// resources.
+pub fn app(
+    app: &App,
+    ownerships: &Ownerships,
+    sections: &CriticalSections,
+    wcets: &Wcets,
+) -> Blockings {
+    let idle = Ident::from("idle");
+
+    // (task, priority, resources, read-only resources)
+    let mut holders = vec![(&idle, 0, &app.idle.resources, &app.idle.read_only)];
+    for (name, task) in &app.tasks {
+        holders.push((name, task.priority, &task.resources, &task.read_only));
+    }
+
+    let queues = analyze::queues(app);
+
+    let mut blockings = HashMap::new();
+    for (name, task) in &app.tasks {
+        let mut blocking = Blocking {
+            time: 0,
+            blocker: None,
+        };
+
+        for &(holder, priority, resources, read_only) in &holders {
+            if priority >= task.priority {
+                continue;
+            }
+
+            for resource in resources {
+                let ceiling = if read_only.contains(resource) {
+                    ownerships[resource].read_ceiling()
+                } else {
+                    ownerships[resource].ceiling()
+                };
+
+                if ceiling < task.priority {
+                    continue;
+                }
+
+                if let Some(length) = length(sections, wcets, holder, resource) {
+                    if blocking.blocker.is_none() || length > blocking.time {
+                        blocking = Blocking {
+                            time: length,
+                            blocker: Some(Blocker {
+                                task: holder.clone(),
+                                resource: Some(resource.clone()),
+                            }),
+                        };
+                    }
+                }
+            }
+        }
+
+        for access in &queues {
+            if access.priority >= task.priority
+                || ownerships[&access.queue].ceiling() < task.priority
+            {
+                continue;
+            }
+
+            let length = app.overheads
+                .queue
+                .or_else(|| length(sections, wcets, &access.context, &access.queue));
+
+            if let Some(length) = length {
+                if blocking.blocker.is_none() || length > blocking.time {
+                    blocking = Blocking {
+                        time: length,
+                        blocker: Some(Blocker {
+                            task: access.context.clone(),
+                            resource: Some(access.queue.clone()),
+                        }),
+                    };
+                }
+            }
+        }
+
+        for (holder, other) in &app.tasks {
+            if other.priority >= task.priority || other.threshold < task.priority {
+                continue;
+            }
+
+            if let Some(&wcet) = wcets.get(holder) {
+                if blocking.blocker.is_none() || wcet > blocking.time {
+                    blocking = Blocking {
+                        time: wcet,
+                        blocker: Some(Blocker {
+                            task: holder.clone(),
+                            resource: None,
+                        }),
+                    };
+                }
+            }
+        }
+
+        blockings.insert(name.clone(), blocking);
+    }
+
+    blockings
+}
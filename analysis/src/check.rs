@@ -0,0 +1,858 @@
+use std::collections::HashMap;
+
+use syn::{ConstExpr, Ident, IntTy, Lit, Path, Ty};
+use syntax::check::{self, Idle, Init};
+use syntax::{self, CriticalSections, Interrupts, Overheads, Priority, Resources, Spawn, Statics,
+             Units};
+
+use diag::Diagnostics;
+
+/// Like `ensure!` but, instead of returning, records the error in the
+/// `Diagnostics` at the token of the `app!` input found at `$at`
+macro_rules! expect {
+    ($diag:expr, $at:expr, $cond:expr, $($msg:tt)+) => {
+        if !$cond {
+            $diag.error($at, format!($($msg)+));
+        }
+    };
+}
+
+pub struct App {
+    pub device: Path,
+    pub free_interrupts: Interrupts,
+    pub idle: Idle,
+    pub init: Init,
+    pub instrument: Option<Path>,
+    /// Number of priority bits of the device; required by `priority: auto`
+    pub nvic_prio_bits: Option<u8>,
+    /// Execution times of the runtime
+    pub overheads: Overheads,
+    pub resources: Statics,
+    pub tasks: Tasks,
+    /// The task priorities are `auto` and must be assigned by `assign::app`
+    pub auto_priority: bool,
+    /// Number of units of each multi-unit resource
+    pub units: Units,
+}
+
+impl App {
+    /// Whether any context uses `schedule` or any task is periodic, in which
+    /// case the timer queue and its `SYS_TICK` handler must be generated
+    pub fn uses_timer_queue(&self) -> bool {
+        !self.init.schedule.is_empty() || !self.idle.schedule.is_empty()
+            || self.tasks
+                .values()
+                .any(|task| !task.schedule.is_empty() || task.period.is_some())
+    }
+}
+
+pub type Tasks = HashMap<Ident, Task>;
+
+#[allow(non_camel_case_types)]
+pub enum Exception {
+    BUS_FAULT,
+    DEBUG_MONITOR,
+    HARD_FAULT,
+    MEM_MANAGE,
+    NMI,
+    PENDSV,
+    SVCALL,
+    SYS_TICK,
+    USAGE_FAULT,
+}
+
+impl Exception {
+    pub fn from(s: &str) -> Option<Self> {
+        Some(match s {
+            "BUS_FAULT" => Exception::BUS_FAULT,
+            "DEBUG_MONITOR" => Exception::DEBUG_MONITOR,
+            "HARD_FAULT" => Exception::HARD_FAULT,
+            "MEM_MANAGE" => Exception::MEM_MANAGE,
+            "NMI" => Exception::NMI,
+            "PENDSV" => Exception::PENDSV,
+            "SVCALL" => Exception::SVCALL,
+            "SYS_TICK" => Exception::SYS_TICK,
+            "USAGE_FAULT" => Exception::USAGE_FAULT,
+            _ => return None,
+        })
+    }
+
+    pub fn nr(&self) -> usize {
+        match *self {
+            Exception::NMI => 2,
+            Exception::HARD_FAULT => 3,
+            Exception::MEM_MANAGE => 4,
+            Exception::BUS_FAULT => 5,
+            Exception::USAGE_FAULT => 6,
+            Exception::SVCALL => 11,
+            Exception::DEBUG_MONITOR => 12,
+            Exception::PENDSV => 14,
+            Exception::SYS_TICK => 15,
+        }
+    }
+
+    /// Symbol `cortex-m-rt` looks up to fill the entry of the exception in the
+    /// vector table
+    pub fn symbol(&self) -> &'static str {
+        match *self {
+            Exception::BUS_FAULT => "BusFault",
+            Exception::DEBUG_MONITOR => "DebugMonitor",
+            Exception::HARD_FAULT => "UserHardFault",
+            Exception::MEM_MANAGE => "MemoryManagement",
+            Exception::NMI => "NonMaskableInt",
+            Exception::PENDSV => "PendSV",
+            Exception::SVCALL => "SVCall",
+            Exception::SYS_TICK => "SysTick",
+            Exception::USAGE_FAULT => "UsageFault",
+        }
+    }
+
+    /// `NMI` and `HARD_FAULT` have fixed priorities above any `BASEPRI` value
+    /// and are not masked by `interrupt::disable` so they can't take part in
+    /// critical sections
+    pub fn is_non_maskable(&self) -> bool {
+        self.fixed_priority().is_some()
+    }
+
+    /// Logical priority of the non-maskable exceptions: above every
+    /// configurable priority, with `NMI` able to preempt `HARD_FAULT`
+    pub fn fixed_priority(&self) -> Option<u8> {
+        match *self {
+            Exception::HARD_FAULT => Some(::std::u8::MAX - 1),
+            Exception::NMI => Some(::std::u8::MAX),
+            _ => None,
+        }
+    }
+
+    /// Bit of the fault exceptions in the SHCSR; they escalate to `HARD_FAULT`
+    /// unless enabled
+    pub fn shcsr_enable(&self) -> Option<u8> {
+        match *self {
+            Exception::MEM_MANAGE => Some(16),
+            Exception::BUS_FAULT => Some(17),
+            Exception::USAGE_FAULT => Some(18),
+            _ => None,
+        }
+    }
+}
+
+pub enum Kind {
+    Exception(Exception),
+    Interrupt { enabled: bool },
+    /// Task dispatched from one of the `free_interrupts`; `input` is `None` if
+    /// the task takes no message
+    Software { capacity: u8, input: Option<Ty> },
+}
+
+impl Kind {
+    pub fn is_software(&self) -> bool {
+        match *self {
+            Kind::Software { .. } => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_non_maskable(&self) -> bool {
+        match *self {
+            Kind::Exception(ref e) => e.is_non_maskable(),
+            _ => false,
+        }
+    }
+}
+
+pub struct Task {
+    pub kind: Kind,
+    pub path: Path,
+    pub priority: u8,
+    /// Preemption threshold: the priority the task runs at once it has
+    /// started; equal to `priority` unless declared
+    pub threshold: u8,
+    pub interarrival: u32,
+    /// Release period of a periodic task; equal to `interarrival`
+    pub period: Option<u32>,
+    /// First release of a periodic task, relative to the end of `init`
+    pub offset: u32,
+    /// Relative deadline; defaults to `interarrival`
+    pub deadline: u32,
+    pub wcet: Option<u32>,
+    pub resources: Resources,
+    /// Resources, among `resources`, that the task only reads
+    pub read_only: Resources,
+    /// Task-local `static` variables; not part of the ownership analysis
+    pub local: Statics,
+    pub schedule: Spawn,
+    pub spawn: Spawn,
+    pub critical_sections: CriticalSections,
+    /// Stack usage in bytes
+    pub stack: Option<u32>,
+    /// Maximum number of units of each multi-unit resource the task holds at
+    /// once
+    pub units: Units,
+}
+
+/// Checks the application; returns `None` if errors were found, in which case
+/// they have been recorded in `diag`
+pub fn app(app: check::App, diag: &mut Diagnostics) -> Option<App> {
+    let auto = app.tasks
+        .values()
+        .filter(|task| task.priority == Some(Priority::Auto))
+        .count();
+
+    if auto != 0 {
+        for (name, task) in &app.tasks {
+            // the non-maskable exceptions have no priority to assign
+            let maskable = !Exception::from(name.as_ref())
+                .map(|e| e.is_non_maskable())
+                .unwrap_or(false);
+
+            expect!(
+                diag,
+                &["tasks", name.as_ref(), "priority"],
+                !maskable || task.priority == Some(Priority::Auto),
+                "`priority: auto` must be used by either all the tasks or none of them"
+            );
+        }
+
+        // the assignment can't use more priority levels than the device has
+        if app.nvic_prio_bits.is_none() {
+            let mut names = app.tasks
+                .iter()
+                .filter(|&(_, task)| task.priority == Some(Priority::Auto))
+                .map(|(name, _)| name.as_ref())
+                .collect::<Vec<_>>();
+            names.sort();
+
+            diag.error(
+                &["tasks", names[0], "priority"],
+                "`priority: auto` needs the number of priority bits of the device, \
+                 `nvic_prio_bits: $BITS`"
+                    .to_owned(),
+            );
+        }
+    }
+
+    if let Some(bits) = app.nvic_prio_bits {
+        expect!(
+            diag,
+            &["nvic_prio_bits"],
+            (1..=8).contains(&bits),
+            "`nvic_prio_bits` must be between 1 and 8"
+        );
+    }
+
+    // check all the tasks before giving up
+    let mut tasks = HashMap::new();
+    for (name, task) in app.tasks {
+        if let Some(task) = ::check::task(name.as_ref(), task, diag) {
+            tasks.insert(name, task);
+        }
+    }
+
+    if !diag.is_empty() {
+        return None;
+    }
+
+    let mut app = App {
+        device: app.device,
+        free_interrupts: app.free_interrupts,
+        idle: app.idle,
+        init: app.init,
+        instrument: app.instrument,
+        nvic_prio_bits: app.nvic_prio_bits,
+        overheads: app.overheads,
+        resources: app.resources,
+        tasks,
+        auto_priority: auto != 0,
+        units: HashMap::new(),
+    };
+
+    ::check::resources(&app, diag);
+
+    if !diag.is_empty() {
+        return None;
+    }
+
+    ::check::units(&mut app, diag);
+    ::check::spawn(&app, diag);
+    ::check::schedule(&app, diag);
+
+    if !diag.is_empty() {
+        return None;
+    }
+
+    Some(app)
+}
+
+fn resources(app: &App, diag: &mut Diagnostics) {
+    for name in &app.init.resources {
+        if let Some(resource) = app.resources.get(name) {
+            expect!(
+                diag,
+                &["resources", name.as_ref()],
+                resource.expr.is_some(),
+                "resource `{}`, allocated to `init`, must have an initial value",
+                name
+            );
+        } else {
+            diag.error(
+                &["init", "resources", name.as_ref()],
+                format!("resource `{}`, allocated to `init`, must be a data resource", name),
+            );
+        }
+
+        expect!(
+            diag,
+            &["idle", "resources", name.as_ref()],
+            !app.idle.resources.contains(name),
+            "resources assigned to `init` can't be shared with `idle`"
+        );
+
+        for (tname, task) in &app.tasks {
+            expect!(
+                diag,
+                &["tasks", tname.as_ref(), "resources", name.as_ref()],
+                !task.resources.contains(name),
+                "resources assigned to `init` can't be shared with tasks"
+            );
+        }
+    }
+
+    for resource in app.resources.keys() {
+        if app.init.resources.contains(resource) {
+            continue;
+        }
+
+        if app.idle.resources.contains(resource) {
+            continue;
+        }
+
+        if app.tasks
+            .values()
+            .any(|task| task.resources.contains(resource))
+        {
+            continue;
+        }
+
+        diag.error(
+            &["resources", resource.as_ref()],
+            format!("resource `{}` is unused", resource),
+        );
+    }
+
+    for (name, task) in &app.tasks {
+        for resource in &task.resources {
+            expect!(
+                diag,
+                &["tasks", name.as_ref(), "resources", resource.as_ref()],
+                app.resources.contains_key(resource),
+                "task {} contains an undeclared resource with name {}",
+                name,
+                resource
+            );
+        }
+
+        for resource in task.critical_sections.keys() {
+            expect!(
+                diag,
+                &["tasks", name.as_ref(), "critical_sections", resource.as_ref()],
+                task.resources.contains(resource),
+                "task {} declares a critical section on resource {} which is not in its \
+                 `resources` list",
+                name,
+                resource
+            );
+        }
+    }
+
+    for resource in app.idle.critical_sections.keys() {
+        expect!(
+            diag,
+            &["idle", "critical_sections", resource.as_ref()],
+            app.idle.resources.contains(resource),
+            "`idle` declares a critical section on resource {} which is not in its `resources` \
+             list",
+            resource
+        );
+    }
+}
+
+/// Collects the multi-unit resources, the ones that appear in some `units`
+/// list, and makes every other context that accesses them hold one unit at a
+/// time
+fn units(app: &mut App, diag: &mut Diagnostics) {
+    let mut pools: Units = HashMap::new();
+
+    {
+        let contexts = app.tasks
+            .iter()
+            .map(|(name, task)| (name.as_ref(), &task.resources, &task.units))
+            .chain(Some(("idle", &app.idle.resources, &app.idle.units)))
+            .collect::<Vec<_>>();
+
+        let mut resources = contexts
+            .iter()
+            .flat_map(|&(_, _, units)| units.keys())
+            .collect::<Vec<_>>();
+        resources.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+        resources.dedup();
+
+        for resource in resources {
+            let at = ["resources", resource.as_ref()];
+            match app.resources
+                .get(resource)
+                .and_then(|resource| ::check::array_len(&resource.ty))
+            {
+                Some(len) if len != 0 && len <= 32 => {
+                    pools.insert(resource.clone(), len as u8);
+                }
+                Some(_) => diag.error(
+                    &at,
+                    format!("multi-unit resource `{}` must have between 1 and 32 units", resource),
+                ),
+                None => diag.error(
+                    &at,
+                    format!(
+                        "multi-unit resource `{}` must have an array type with a literal length, \
+                         like `[T; 4]`",
+                        resource
+                    ),
+                ),
+            }
+        }
+
+        for &(context, resources, units) in &contexts {
+            for (resource, &n) in units {
+                let at = ::check::at(context, "units", resource.as_ref());
+
+                expect!(
+                    diag,
+                    &at,
+                    resources.contains(resource),
+                    "`{}` declares the units of resource `{}` which is not in its `resources` list",
+                    context,
+                    resource
+                );
+
+                expect!(
+                    diag,
+                    &at,
+                    n != 0,
+                    "`{}` must hold at least one unit of `{}`",
+                    context,
+                    resource
+                );
+
+                if let Some(&len) = pools.get(resource) {
+                    expect!(
+                        diag,
+                        &at,
+                        n <= len,
+                        "`{}` holds up to {} units of `{}` but it only has {}",
+                        context,
+                        n,
+                        resource,
+                        len
+                    );
+                }
+            }
+        }
+    }
+
+    for resource in pools.keys() {
+        expect!(
+            diag,
+            &["init", "resources", resource.as_ref()],
+            !app.init.resources.contains(resource),
+            "multi-unit resource `{}` can't be assigned to `init`",
+            resource
+        );
+
+        let readers = app.tasks
+            .iter()
+            .map(|(name, task)| (name.as_ref(), &task.read_only))
+            .chain(Some(("idle", &app.idle.read_only)));
+
+        for (context, read_only) in readers {
+            expect!(
+                diag,
+                &::check::at(context, "resources", resource.as_ref()),
+                !read_only.contains(resource),
+                "multi-unit resource `{}` can't be accessed read-only",
+                resource
+            );
+        }
+    }
+
+    for task in app.tasks.values_mut() {
+        for resource in &task.resources {
+            if pools.contains_key(resource) {
+                task.units.entry(resource.clone()).or_insert(1);
+            }
+        }
+    }
+
+    for resource in &app.idle.resources {
+        if pools.contains_key(resource) {
+            app.idle.units.entry(resource.clone()).or_insert(1);
+        }
+    }
+
+    app.units = pools;
+}
+
+/// Returns the length of `[T; N]` if `N` is an integer literal
+fn array_len(ty: &Ty) -> Option<u64> {
+    match *ty {
+        Ty::Array(_, ConstExpr::Lit(Lit::Int(n, IntTy::Unsuffixed)))
+        | Ty::Array(_, ConstExpr::Lit(Lit::Int(n, IntTy::Usize))) => Some(n),
+        _ => None,
+    }
+}
+
+/// Location of the `item` listed under the key `key` of `context`: `init`,
+/// `idle` or a task
+fn at<'a>(context: &'a str, key: &'a str, item: &'a str) -> Vec<&'a str> {
+    match context {
+        "init" | "idle" => vec![context, key, item],
+        _ => vec!["tasks", context, key, item],
+    }
+}
+
+fn spawn(app: &App, diag: &mut Diagnostics) {
+    let spawners = app.tasks
+        .iter()
+        .map(|(name, task)| (name.as_ref(), &task.spawn))
+        .chain(Some(("init", &app.init.spawn)))
+        .chain(Some(("idle", &app.idle.spawn)));
+
+    for (spawner, spawn) in spawners {
+        for name in spawn {
+            let at = ::check::at(spawner, "spawn", name.as_ref());
+
+            match app.tasks.get(name) {
+                Some(task) if task.kind.is_software() => {
+                    expect!(
+                        diag,
+                        &at,
+                        task.period.is_none(),
+                        "`{}` can't spawn `{}` because it's a periodic task",
+                        spawner,
+                        name
+                    );
+                }
+                _ => diag.error(
+                    &at,
+                    format!(
+                        "`{}` can't spawn `{}` because it's not a software task",
+                        spawner, name
+                    ),
+                ),
+            }
+        }
+    }
+
+    if app.free_interrupts.is_empty() {
+        let mut software = app.tasks
+            .iter()
+            .filter(|&(_, task)| task.kind.is_software())
+            .map(|(name, _)| name.as_ref())
+            .collect::<Vec<_>>();
+        software.sort();
+
+        if let Some(name) = software.first() {
+            diag.error(
+                &["tasks", name],
+                "software tasks need at least one interrupt in `free_interrupts` to be dispatched"
+                    .to_owned(),
+            );
+        }
+    }
+
+    for interrupt in &app.free_interrupts {
+        let at = ["free_interrupts", interrupt.as_ref()];
+
+        expect!(
+            diag,
+            &at,
+            Exception::from(interrupt.as_ref()).is_none(),
+            "`{}` is an exception; only interrupts can be used as `free_interrupts`",
+            interrupt
+        );
+
+        expect!(
+            diag,
+            &at,
+            !app.tasks.contains_key(interrupt),
+            "`{}` is bound to a task; it can't be used as a free interrupt",
+            interrupt
+        );
+    }
+}
+
+fn schedule(app: &App, diag: &mut Diagnostics) {
+    let schedulers = app.tasks
+        .iter()
+        .map(|(name, task)| (name.as_ref(), &task.schedule))
+        .chain(Some(("init", &app.init.schedule)))
+        .chain(Some(("idle", &app.idle.schedule)));
+
+    for (scheduler, schedule) in schedulers {
+        for name in schedule {
+            let at = ::check::at(scheduler, "schedule", name.as_ref());
+
+            match app.tasks.get(name) {
+                Some(task) if task.kind.is_software() => {
+                    expect!(
+                        diag,
+                        &at,
+                        task.period.is_none(),
+                        "`{}` can't schedule `{}` because it's a periodic task",
+                        scheduler,
+                        name
+                    );
+                }
+                _ => diag.error(
+                    &at,
+                    format!(
+                        "`{}` can't schedule `{}` because it's not a software task",
+                        scheduler, name
+                    ),
+                ),
+            }
+        }
+    }
+
+    if app.uses_timer_queue() {
+        expect!(
+            diag,
+            &["tasks", "SYS_TICK"],
+            !app.tasks.contains_key(&Ident::from("SYS_TICK")),
+            "`SYS_TICK` drives the timer queue used by `schedule` and periodic tasks; it can't be \
+             bound to a task"
+        );
+    }
+}
+
+fn task(name: &str, task: syntax::check::Task, diag: &mut Diagnostics) -> Option<Task> {
+    let errors = diag.len();
+    // `input` is moved into the `kind`
+    let has_input = task.input.is_some();
+
+    let kind = if task.capacity.is_some() || task.input.is_some() || task.period.is_some() {
+        expect!(
+            diag,
+            &["tasks", name],
+            Exception::from(name).is_none(),
+            "`capacity`, `input` and `period` are not valid for exceptions"
+        );
+
+        expect!(
+            diag,
+            &["tasks", name, "enabled"],
+            task.enabled.is_none(),
+            "`enabled` field is not valid for software tasks"
+        );
+
+        let capacity = task.capacity.unwrap_or(1);
+        expect!(
+            diag,
+            &["tasks", name, "capacity"],
+            capacity != 0,
+            "`capacity` must be greater than 0"
+        );
+
+        Kind::Software {
+            capacity,
+            input: task.input,
+        }
+    } else {
+        match Exception::from(name) {
+            Some(e) => {
+                expect!(
+                    diag,
+                    &["tasks", name, "enabled"],
+                    task.enabled.is_none(),
+                    "`enabled` field is not valid for exceptions"
+                );
+
+                Kind::Exception(e)
+            }
+            None => {
+                expect!(
+                    diag,
+                    &["tasks", name, "enabled"],
+                    task.enabled != Some(true),
+                    "`enabled: true` is the default value; this line can be omitted"
+                );
+
+                Kind::Interrupt {
+                    enabled: task.enabled.unwrap_or(true),
+                }
+            }
+        }
+    };
+
+    if let Some(period) = task.period {
+        expect!(
+            diag,
+            &["tasks", name, "period"],
+            period != 0,
+            "`period` must be greater than 0"
+        );
+
+        expect!(
+            diag,
+            &["tasks", name, "input"],
+            !has_input,
+            "periodic tasks are released by the timer queue; they can't take an `input`"
+        );
+
+        expect!(
+            diag,
+            &["tasks", name, "interarrival"],
+            task.interarrival.is_none(),
+            "`interarrival` is implied by `period`; this line can be omitted"
+        );
+    } else {
+        expect!(
+            diag,
+            &["tasks", name, "offset"],
+            task.offset.is_none(),
+            "`offset` must be specified along `period`"
+        );
+    }
+
+    // the interarrival time of a periodic task is its period
+    let interarrival = task.period.or(task.interarrival);
+
+    expect!(
+        diag,
+        &["tasks", name, if task.wcet.is_some() { "wcet" } else { "deadline" }],
+        interarrival.is_some() || (task.wcet.is_none() && task.deadline.is_none()),
+        "`interarrival` or `period` must be specified along `wcet` and `deadline`"
+    );
+
+    expect!(
+        diag,
+        &["tasks", name, "priority"],
+        interarrival.is_some() || task.priority != Some(Priority::Auto),
+        "`interarrival` or `period` must be specified along `priority: auto`"
+    );
+
+    if kind.is_non_maskable() {
+        expect!(
+            diag,
+            &["tasks", name, if task.priority.is_some() { "priority" } else { "threshold" }],
+            task.priority.is_none() && task.threshold.is_none(),
+            "`priority` and `threshold` are not valid for non-maskable exceptions; they run above \
+             every other task"
+        );
+
+        for key in &["resources", "spawn", "schedule"] {
+            let empty = match *key {
+                "resources" => task.resources.is_empty(),
+                "spawn" => task.spawn.is_empty(),
+                _ => task.schedule.is_empty(),
+            };
+
+            expect!(
+                diag,
+                &["tasks", name, *key],
+                empty,
+                "non-maskable exceptions can't be masked by critical sections; they can't access \
+                 `resources` nor `spawn` or `schedule` tasks"
+            );
+        }
+    }
+
+    let fixed = match kind {
+        Kind::Exception(ref e) => e.fixed_priority(),
+        _ => None,
+    };
+
+    let priority = match task.priority {
+        None => fixed.unwrap_or(1),
+        Some(Priority::Value(priority)) => priority,
+        // placeholder; the actual value is computed by `assign::app`
+        Some(Priority::Auto) => 0,
+    };
+
+    if let Some(threshold) = task.threshold {
+        expect!(
+            diag,
+            &["tasks", name, "threshold"],
+            task.priority != Some(Priority::Auto),
+            "`threshold` can't be used along `priority: auto`"
+        );
+
+        expect!(
+            diag,
+            &["tasks", name, "threshold"],
+            threshold >= priority || task.priority == Some(Priority::Auto),
+            "`threshold` ({}) can't be lower than `priority` ({})",
+            threshold,
+            priority
+        );
+    }
+
+    for (lname, local) in &task.local {
+        expect!(
+            diag,
+            &["tasks", name, "local", lname.as_ref()],
+            local.expr.is_some(),
+            "task-local static `{}` must have an initial value",
+            lname
+        );
+
+        expect!(
+            diag,
+            &["tasks", name, "local", lname.as_ref()],
+            !task.resources.contains(lname),
+            "task-local static `{}` has the same name as one of the resources of the task",
+            lname
+        );
+    }
+
+    let interarrival = interarrival.unwrap_or(1);
+    let deadline = task.deadline.unwrap_or(interarrival);
+
+    expect!(
+        diag,
+        &["tasks", name, "deadline"],
+        deadline <= interarrival,
+        "`deadline` ({}) can't be greater than `interarrival` ({})",
+        deadline,
+        interarrival
+    );
+
+    expect!(
+        diag,
+        &["tasks", name],
+        task.path.is_some(),
+        "`path` field is missing"
+    );
+
+    if diag.len() != errors {
+        return None;
+    }
+
+    Some(Task {
+        kind,
+        path: task.path?,
+        priority,
+        threshold: task.threshold.unwrap_or(priority),
+        interarrival,
+        period: task.period,
+        offset: task.offset.unwrap_or(0),
+        deadline,
+        wcet: task.wcet,
+        resources: task.resources,
+        read_only: task.read_only,
+        local: task.local,
+        schedule: task.schedule,
+        spawn: task.spawn,
+        critical_sections: task.critical_sections,
+        stack: task.stack,
+        units: task.units,
+    })
+}
@@ -0,0 +1,78 @@
+//! Errors located in the `app!` input
+//!
+//! The input is parsed from its string representation so the syntax tree has
+//! no spans. Errors are instead located by a path of keys and names, like
+//! `["tasks", "EXTI0", "enabled"]`, that the `app!` macro looks up in the
+//! original token stream to report the errors on the offending tokens.
+use std::fmt;
+
+use error_chain;
+
+use syntax::error::*;
+
+/// Errors found in the `app!` input
+#[derive(Default)]
+pub struct Diagnostics {
+    errors: Vec<(Vec<String>, String)>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Diagnostics { errors: vec![] }
+    }
+
+    /// Records an error at `at`; an empty path points at the whole macro call
+    pub fn error(&mut self, at: &[&str], message: String) {
+        self.errors
+            .push((at.iter().map(|s| s.to_string()).collect(), message));
+    }
+
+    /// Records the error of `result`, if any, at `at`
+    pub fn check<T>(&mut self, at: &[&str], result: Result<T>) -> Option<T> {
+        match result {
+            Ok(x) => Some(x),
+            Err(e) => {
+                self.error(at, format!("{}", error_chain::ChainedError::display(&e)));
+                None
+            }
+        }
+    }
+
+    /// Moves each error to the location `f` maps its location to
+    pub fn relocate<F>(&mut self, f: F)
+    where
+        F: Fn(&[String]) -> Vec<String>,
+    {
+        for &mut (ref mut at, _) in &mut self.errors {
+            *at = f(at);
+        }
+    }
+
+    /// The errors and their locations, in the order they were found
+    pub fn errors(&self) -> &[(Vec<String>, String)] {
+        &self.errors
+    }
+
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// One error per line, prefixed with its location, e.g. `tasks.EXTI0.enabled: ..`
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (at, message) in &self.errors {
+            if at.is_empty() {
+                writeln!(f, "{}", message)?;
+            } else {
+                writeln!(f, "{}: {}", at.join("."), message)?;
+            }
+        }
+
+        Ok(())
+    }
+}
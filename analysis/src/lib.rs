@@ -0,0 +1,83 @@
+//! Checks and analyses of Real Time For the Masses (RTFM) applications
+//!
+//! This is the front end of the `app!` macro of the `cortex-m-rtfm` crate as a
+//! regular library, so host tools can work on the same view of an application
+//! the macro has:
+//!
+//! ``` ignore
+//! let app = rtfm_analysis::parse_app(input)?;
+//! let ownerships = rtfm_analysis::ownerships(&app);
+//!
+//! for (resource, ownership) in &ownerships {
+//!     println!("{}: ceiling = {}", resource, ownership.ceiling());
+//! }
+//! ```
+//!
+//! `input` is the contents of the `app!` macro. The lower level passes, which
+//! the macro runs one by one, are available in the modules of this crate.
+
+#[macro_use]
+extern crate error_chain;
+extern crate rtfm_syntax as syntax;
+extern crate syn;
+
+pub mod analyze;
+pub mod assign;
+pub mod blocking;
+pub mod check;
+pub mod diag;
+pub mod rta;
+
+pub use analyze::Ownerships;
+pub use check::App;
+pub use diag::Diagnostics;
+pub use syntax::error::{Error, ErrorKind, Result, ResultExt};
+
+/// Parses and checks the `app!` input `input`
+///
+/// The errors in the description of the application, like a resource shared
+/// with `init` or a key that can't be parsed, are recorded in `diagnostics`,
+/// in which case `None` is returned. The `auto` priorities are not assigned
+/// yet; see `assign::app`.
+pub fn check_app(input: &str, diagnostics: &mut Diagnostics) -> Result<Option<App>> {
+    let app = match syntax::App::parse(input) {
+        Ok(app) => app,
+        Err(e) => {
+            let (keys, message) = e.locate();
+            let at = keys.iter().map(|key| &**key).collect::<Vec<_>>();
+            diagnostics.error(&at, message);
+
+            return Ok(None);
+        }
+    };
+    let app = syntax::check::app(app).chain_err(|| "checking the AST")?;
+
+    Ok(::check::app(app, diagnostics))
+}
+
+/// Parses and checks the `app!` input `input`, and assigns the `auto`
+/// priorities using the critical section lengths declared in `input`
+pub fn parse_app(input: &str) -> Result<App> {
+    let mut diagnostics = Diagnostics::new();
+    let mut app = match ::check_app(input, &mut diagnostics)? {
+        Some(app) => app,
+        None => bail!("found {} error(s) in the application:\n{}", diagnostics.len(), diagnostics),
+    };
+
+    ::assign::app(&mut app, &Default::default(), &mut diagnostics);
+    ensure!(
+        diagnostics.is_empty(),
+        "found {} error(s) assigning priorities:\n{}",
+        diagnostics.len(),
+        diagnostics
+    );
+
+    Ok(app)
+}
+
+/// Computes the ownership, and thus the ceiling, of each resource of `app`
+///
+/// The priorities of `app` must have been assigned; see `parse_app`.
+pub fn ownerships(app: &App) -> Ownerships {
+    ::analyze::app(app)
+}
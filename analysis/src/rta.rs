@@ -0,0 +1,354 @@
+//! Response time analysis of the task set
+//!
+//! Implements the classic fixed priority response time test extended with the
+//! single blocking term of the Stack Resource Policy:
+//!
+//! ``` text
+//! R_i = C_i + B_i + sum_{j in hp(i)} ceil(R_i / T_j) * C_j
+//! ```
+//!
+//! where `hp(i)` are the *other* tasks whose priority is greater or equal to
+//! the priority of task `i`. Tasks that share a priority level can't preempt
+//! each other, but one of them may be serviced before the other so they are
+//! (conservatively) counted as interference. The relative deadline of each task
+//! is its `deadline`, which defaults to its interarrival time.
+//!
+//! Preemption thresholds only enter the analysis through the blocking term;
+//! the tasks in `hp(i)` whose priority doesn't exceed the threshold of task
+//! `i` can't preempt it once it has started but they are (conservatively)
+//! counted as interference over the whole response time.
+//!
+//! The execution time of the runtime is accounted for using the `overheads`
+//! declared in `app!`: the `dispatch` overhead is added to the execution time
+//! of each software task, as its dispatcher runs once per message, and each
+//! release of a task from the timer queue interferes with the tasks that the
+//! `SYS_TICK` handler can preempt:
+//!
+//! ``` text
+//! R_i = C_i + B_i + sum_{j in hp(i)} ceil(R_i / T_j) * C_j
+//!     + sum_{j in tq(i)} ceil(R_i / T_j) * release
+//! ```
+//!
+//! where `tq(i)` are the *other* tasks in the timer queue if the `SYS_TICK`
+//! handler runs at a priority greater or equal to the priority of task `i`,
+//! and no task otherwise.
+use std::collections::HashMap;
+
+use syn::Ident;
+
+use analyze::{self, Ownerships};
+use blocking::{self, Blocker, CriticalSections};
+use check::App;
+use diag::Diagnostics;
+
+/// Worst case execution time, in clock cycles, of each task
+pub type Wcets = HashMap<Ident, u32>;
+
+pub type Responses = HashMap<Ident, Response>;
+
+pub struct Response {
+    /// Worst case execution time (`C`), including the `dispatch` overhead if
+    /// the task is a software task
+    pub wcet: u32,
+    /// Worst case blocking time (`B`)
+    pub blocking: u32,
+    /// The critical section responsible for the blocking time
+    pub blocker: Option<Blocker>,
+    /// Worst case interference from the tasks in `hp(i)` and from the releases
+    /// of the tasks in `tq(i)` (`I`)
+    ///
+    /// If the task is not schedulable this is the interference at the point
+    /// the iteration exceeded the deadline.
+    pub interference: u32,
+    /// Relative deadline (`D`)
+    pub deadline: u32,
+}
+
+impl Response {
+    /// Worst case response time, `R = C + B + I`
+    pub fn time(&self) -> u32 {
+        self.wcet
+            .saturating_add(self.blocking)
+            .saturating_add(self.interference)
+    }
+
+    pub fn is_schedulable(&self) -> bool {
+        self.time() <= self.deadline
+    }
+}
+
+/// Computes the response time of each task of `app`
+///
+/// `wcets` must have an entry for each task of `app`, and only for those.
+/// Returns `None` if the analysis can't be done, in which case the errors have
+/// been recorded in `diag` at the offending tasks.
+pub fn app(
+    app: &App,
+    ownerships: &Ownerships,
+    wcets: &Wcets,
+    sections: &CriticalSections,
+    diag: &mut Diagnostics,
+) -> Option<Responses> {
+    let errors = diag.len();
+    for (name, task) in &app.tasks {
+        if !wcets.contains_key(name) {
+            diag.error(
+                &["tasks", name.as_ref()],
+                format!("task `{}` has no worst case execution time", name),
+            );
+        }
+
+        if task.interarrival == 0 {
+            diag.error(
+                &["tasks", name.as_ref(), "interarrival"],
+                format!("task `{}` has an interarrival time of 0", name),
+            );
+        }
+    }
+
+    let costs = costs(app, wcets, diag);
+    let release = release(app, diag);
+    let (costs, release) = match (costs, release) {
+        (Some(costs), Some(release)) if diag.len() == errors => (costs, release),
+        _ => return None,
+    };
+    let timer_queue = analyze::timer_queue(app);
+
+    let mut blockings = blocking::app(app, ownerships, sections, wcets);
+    let mut responses = HashMap::new();
+
+    for (name, task) in &app.tasks {
+        let wcet = costs[name];
+        let blocking = blockings.remove(name).expect("BUG: task without blocking");
+        let (blocker, blocking) = (blocking.blocker, blocking.time);
+        let deadline = task.deadline;
+
+        let mut hp = app.tasks
+            .iter()
+            .filter(|&(other, t)| other != name && t.priority >= task.priority)
+            .map(|(other, t)| (costs[other], t.interarrival))
+            .collect::<Vec<_>>();
+
+        if let Some(ref timer_queue) = timer_queue {
+            if timer_queue.priority >= task.priority {
+                hp.extend(
+                    timer_queue
+                        .tasks
+                        .iter()
+                        .filter(|&other| other != name)
+                        .map(|other| (release, app.tasks[other].interarrival)),
+                );
+            }
+        }
+
+        let interference = interference(wcet, blocking, deadline, &hp);
+
+        responses.insert(
+            name.clone(),
+            Response {
+                wcet,
+                blocking,
+                blocker,
+                interference,
+                deadline,
+            },
+        );
+    }
+
+    Some(responses)
+}
+
+/// Execution time of each task in `wcets` plus, if it's a software task, the
+/// `dispatch` overhead
+///
+/// Fails if `wcets` names a task that's not in `app`, or if the application
+/// has software tasks but doesn't declare the `dispatch` overhead; the errors
+/// are recorded in `diag`.
+pub fn costs(app: &App, wcets: &Wcets, diag: &mut Diagnostics) -> Option<Wcets> {
+    let mut costs = HashMap::new();
+
+    let errors = diag.len();
+    for (name, &wcet) in wcets {
+        let task = match app.tasks.get(name) {
+            Some(task) => task,
+            None => {
+                diag.error(
+                    &["tasks", name.as_ref()],
+                    format!("`{}` has a worst case execution time but it's not a task", name),
+                );
+                continue;
+            }
+        };
+
+        let cost = if task.kind.is_software() {
+            match app.overheads.dispatch {
+                Some(dispatch) => wcet.saturating_add(dispatch),
+                None => {
+                    diag.error(
+                        &["tasks", name.as_ref(), "wcet"],
+                        format!(
+                            "the `dispatch` overhead must be declared to analyze the software \
+                             task `{}`",
+                            name
+                        ),
+                    );
+                    continue;
+                }
+            }
+        } else {
+            wcet
+        };
+
+        costs.insert(name.clone(), cost);
+    }
+
+    if diag.len() == errors {
+        Some(costs)
+    } else {
+        None
+    }
+}
+
+/// The `release` overhead; 0 if the application has no timer queue
+///
+/// Fails if the application has a timer queue but doesn't declare the
+/// `release` overhead; the error is recorded in `diag`.
+pub fn release(app: &App, diag: &mut Diagnostics) -> Option<u32> {
+    if analyze::timer_queue(app).is_none() {
+        return Some(0);
+    }
+
+    if app.overheads.release.is_none() {
+        diag.error(
+            &["overheads"],
+            "the `release` overhead must be declared to analyze the timer queue".to_owned(),
+        );
+    }
+
+    app.overheads.release
+}
+
+/// Computes the interference `I` that the tasks `hp`, given as `(C, T)` pairs,
+/// cause on a task with execution time `wcet` and blocking time `blocking`
+///
+/// The fixed point iteration stops as soon as the response time exceeds the
+/// `deadline`.
+pub fn interference(wcet: u32, blocking: u32, deadline: u32, hp: &[(u32, u32)]) -> u32 {
+    let mut interference = 0;
+    loop {
+        let response = wcet.saturating_add(blocking).saturating_add(interference);
+
+        if response > deadline {
+            return interference;
+        }
+
+        let next = hp.iter().fold(0u32, |acc, &(c, t)| {
+            // number of releases in the window, `ceil(response / t)`
+            let releases = if response == 0 { 0 } else { (response - 1) / t + 1 };
+            acc.saturating_add(releases.saturating_mul(c))
+        });
+
+        if next == interference {
+            return interference;
+        }
+
+        interference = next;
+    }
+}
+
+/// Critical section lengths: those declared in `app!` plus, for the critical
+/// sections not declared there, the `measured` ones (see `blocking::measured`)
+pub fn sections(app: &App, measured: &CriticalSections) -> CriticalSections {
+    let mut sections = measured.clone();
+
+    for (task, declared) in blocking::declared(app) {
+        sections
+            .entry(task)
+            .or_default()
+            .extend(declared);
+    }
+
+    sections
+}
+
+/// Checks that the task set is schedulable using the WCETs declared in `app!`
+///
+/// `wcet` is all-or-nothing: the check is skipped if no task declares its
+/// `wcet`, and if some do each task that doesn't is an error. The errors, like
+/// a task that misses its deadline, are recorded in `diag` at the offending
+/// tasks. See `sections` for the source of the critical section lengths.
+pub fn check(
+    app: &App,
+    ownerships: &Ownerships,
+    measured: &CriticalSections,
+    diag: &mut Diagnostics,
+) {
+    let wcets = app.tasks
+        .iter()
+        .filter_map(|(name, task)| task.wcet.map(|wcet| (name.clone(), wcet)))
+        .collect::<Wcets>();
+
+    if wcets.is_empty() {
+        return;
+    }
+
+    if wcets.len() != app.tasks.len() {
+        let mut names = app.tasks
+            .keys()
+            .filter(|name| !wcets.contains_key(name))
+            .collect::<Vec<_>>();
+        names.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+        for name in names {
+            diag.error(
+                &["tasks", name.as_ref()],
+                format!(
+                    "task `{}` must declare its `wcet`: the schedulability analysis needs the \
+                     `wcet` of either all the tasks or none of them",
+                    name
+                ),
+            );
+        }
+
+        return;
+    }
+
+    let sections = sections(app, measured);
+    let responses = match ::rta::app(app, ownerships, &wcets, &sections, diag) {
+        Some(responses) => responses,
+        None => return,
+    };
+
+    let mut names = responses.keys().collect::<Vec<_>>();
+    names.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+    for name in names {
+        let response = &responses[name];
+
+        if !response.is_schedulable() {
+            let blocker = match response.blocker {
+                Some(Blocker {
+                    ref task,
+                    resource: Some(ref resource),
+                }) => format!(" (`{}` holding `{}`)", task, resource),
+                Some(Blocker { ref task, .. }) => format!(" (`{}` running at its threshold)", task),
+                None => String::new(),
+            };
+
+            diag.error(
+                &["tasks", name.as_ref()],
+                format!(
+                    "the task set is not schedulable: task `{}` misses its deadline: \
+                     R = C + B + I = {} + {}{} + {} = {} > D = {}",
+                    name,
+                    response.wcet,
+                    response.blocking,
+                    blocker,
+                    response.interference,
+                    response.time(),
+                    response.deadline
+                ),
+            );
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Priority assignment (`priority: auto`) with fewer levels than tasks
+extern crate rtfm_analysis as analysis;
+extern crate syn;
+
+use analysis::Diagnostics;
+use syn::Ident;
+
+fn priorities(input: &str) -> Vec<(String, u8)> {
+    let app = analysis::parse_app(input).unwrap();
+
+    let mut priorities = app.tasks
+        .iter()
+        .map(|(name, task)| (name.as_ref().to_owned(), task.priority))
+        .collect::<Vec<_>>();
+    priorities.sort();
+    priorities
+}
+
+fn owned(priorities: &[(&str, u8)]) -> Vec<(String, u8)> {
+    priorities
+        .iter()
+        .map(|&(name, priority)| (name.to_owned(), priority))
+        .collect()
+}
+
+#[test]
+fn deadline_monotonic() {
+    // three deadlines but only two levels
+    let priorities = priorities(
+        "
+        device: stm32f103xx,
+
+        nvic_prio_bits: 1,
+
+        tasks: {
+            EXTI0: { path: exti0, priority: auto, interarrival: 1000 },
+            EXTI1: { path: exti1, priority: auto, interarrival: 500 },
+            EXTI2: { path: exti2, priority: auto, interarrival: 100 },
+        },
+        ",
+    );
+
+    assert_eq!(priorities, owned(&[("EXTI0", 1), ("EXTI1", 2), ("EXTI2", 2)]));
+}
+
+#[test]
+fn audsley() {
+    // `EXTI0` and `EXTI1` meet their deadlines at the lowest level
+    let priorities = priorities(
+        "
+        device: stm32f103xx,
+
+        nvic_prio_bits: 1,
+
+        tasks: {
+            EXTI0: { path: exti0, priority: auto, interarrival: 1000, wcet: 10 },
+            EXTI1: { path: exti1, priority: auto, interarrival: 500, wcet: 10 },
+            EXTI2: { path: exti2, priority: auto, interarrival: 100, deadline: 15, wcet: 10 },
+        },
+        ",
+    );
+
+    assert_eq!(priorities, owned(&[("EXTI0", 1), ("EXTI1", 1), ("EXTI2", 2)]));
+}
+
+#[test]
+fn one_task_per_level() {
+    // there are enough levels, the levels are not shared
+    let app = analysis::parse_app(
+        "
+        device: stm32f103xx,
+
+        nvic_prio_bits: 2,
+
+        tasks: {
+            EXTI0: { path: exti0, priority: auto, interarrival: 1000, wcet: 10 },
+            EXTI1: { path: exti1, priority: auto, interarrival: 500, wcet: 10 },
+            EXTI2: { path: exti2, priority: auto, interarrival: 100, wcet: 10 },
+        },
+        ",
+    ).unwrap();
+
+    assert_eq!(app.tasks[&Ident::from("EXTI0")].priority, 1);
+    assert_eq!(app.tasks[&Ident::from("EXTI1")].priority, 2);
+    assert_eq!(app.tasks[&Ident::from("EXTI2")].priority, 3);
+}
+
+#[test]
+fn too_few_levels() {
+    // `EXTI1` and `EXTI2` need a level each above `EXTI0`
+    let input = "
+        device: stm32f103xx,
+
+        nvic_prio_bits: $BITS,
+
+        tasks: {
+            EXTI0: { path: exti0, priority: auto, interarrival: 1000, wcet: 10 },
+            EXTI1: { path: exti1, priority: auto, interarrival: 1000, deadline: 25, wcet: 10 },
+            EXTI2: { path: exti2, priority: auto, interarrival: 1000, deadline: 15, wcet: 10 },
+        },
+    ";
+
+    assert!(analysis::parse_app(&input.replace("$BITS", "2")).is_ok());
+
+    let mut app = analysis::check_app(&input.replace("$BITS", "1"), &mut Diagnostics::new())
+        .unwrap()
+        .unwrap();
+    let mut diagnostics = Diagnostics::new();
+    analysis::assign::app(&mut app, &Default::default(), &mut diagnostics);
+
+    // reported at the tasks left without a level
+    let errors = diagnostics.errors();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, ["tasks", "EXTI2", "priority"]);
+    assert!(errors[0].1.contains("with 2 priority levels"));
+}
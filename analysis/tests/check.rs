@@ -0,0 +1,175 @@
+//! Location of the errors found while checking the `app!` input
+extern crate rtfm_analysis as analysis;
+
+use analysis::Diagnostics;
+
+fn errors(input: &str) -> Vec<(Vec<String>, String)> {
+    let mut diagnostics = Diagnostics::new();
+    let app = analysis::check_app(input, &mut diagnostics).unwrap();
+    assert!(app.is_none());
+
+    let mut errors = diagnostics.errors().to_vec();
+    errors.sort();
+    errors
+}
+
+fn at(path: &[&str]) -> Vec<String> {
+    path.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn spawn_and_schedule() {
+    let errors = errors(
+        "
+        device: stm32f103xx,
+
+        free_interrupts: [EXTI2],
+
+        tasks: {
+            EXTI0: {
+                path: exti0,
+                spawn: [EXTI1],
+            },
+
+            EXTI1: {
+                path: exti1,
+                schedule: [periodic],
+            },
+
+            periodic: {
+                path: periodic,
+                period: 1000,
+            },
+        },
+        ",
+    );
+
+    // both errors are reported, each at the offending name
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].0, at(&["tasks", "EXTI0", "spawn", "EXTI1"]));
+    assert!(errors[0].1.contains("not a software task"));
+    assert_eq!(errors[1].0, at(&["tasks", "EXTI1", "schedule", "periodic"]));
+    assert!(errors[1].1.contains("periodic task"));
+}
+
+#[test]
+fn units() {
+    let errors = errors(
+        "
+        device: stm32f103xx,
+
+        resources: {
+            static COUNTER: u32 = 0;
+            static POOL: [u8; 2] = [0; 2];
+        },
+
+        idle: {
+            resources: [COUNTER, POOL],
+            units: { COUNTER: 2, POOL: 3 },
+        },
+        ",
+    );
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].0, at(&["idle", "units", "POOL"]));
+    assert!(errors[0].1.contains("only has 2"));
+    assert_eq!(errors[1].0, at(&["resources", "COUNTER"]));
+    assert!(errors[1].1.contains("array type"));
+}
+
+#[test]
+fn sys_tick() {
+    let errors = errors(
+        "
+        device: stm32f103xx,
+
+        free_interrupts: [EXTI1],
+
+        tasks: {
+            SYS_TICK: {
+                path: sys_tick,
+            },
+
+            EXTI0: {
+                path: exti0,
+                schedule: [foo],
+            },
+
+            foo: {
+                path: foo,
+                capacity: 1,
+            },
+        },
+        ",
+    );
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, at(&["tasks", "SYS_TICK"]));
+}
+
+#[test]
+fn nvic_prio_bits() {
+    let errors = errors(
+        "
+        device: stm32f103xx,
+
+        tasks: {
+            EXTI1: { path: exti1, priority: auto, interarrival: 100 },
+            EXTI0: { path: exti0, priority: auto, interarrival: 100 },
+        },
+        ",
+    );
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, at(&["tasks", "EXTI0", "priority"]));
+    assert!(errors[0].1.contains("nvic_prio_bits"));
+
+    let errors = ::errors(
+        "
+        device: stm32f103xx,
+
+        nvic_prio_bits: 9,
+
+        tasks: {
+            EXTI0: { path: exti0, priority: auto, interarrival: 100 },
+        },
+        ",
+    );
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, at(&["nvic_prio_bits"]));
+}
+
+#[test]
+fn parse() {
+    let errors = errors(
+        "
+        device: stm32f103xx,
+
+        tasks: {
+            EXTI0: {
+                path: exti0,
+                priority: high,
+            },
+        },
+        ",
+    );
+
+    // located at the key whose value couldn't be parsed
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, at(&["tasks", "EXTI0", "priority"]));
+
+    let errors = ::errors(
+        "
+        device: stm32f103xx,
+
+        idle: {
+            resources: [A, A],
+        },
+        ",
+    );
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, at(&["idle", "resources"]));
+    assert!(errors[0].1.contains("listed more than once"));
+}
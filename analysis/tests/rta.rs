@@ -0,0 +1,313 @@
+//! Response time analysis of a small task set, computed by hand
+extern crate rtfm_analysis as analysis;
+extern crate syn;
+
+use analysis::{blocking, rta, Diagnostics};
+use syn::Ident;
+
+// ceiling(A) = 2, ceiling(B) = 3
+const APP: &str = "
+    device: stm32f103xx,
+
+    resources: {
+        static A: u32 = 0;
+        static B: u32 = 0;
+    },
+
+    tasks: {
+        EXTI0: {
+            path: exti0,
+            priority: 1,
+            interarrival: 1000,
+            wcet: 40,
+            resources: [A],
+            critical_sections: { A: 10 },
+        },
+
+        EXTI1: {
+            path: exti1,
+            priority: 2,
+            interarrival: 200,
+            wcet: 30,
+            resources: [A, B],
+            critical_sections: { A: 5, B: 7 },
+        },
+
+        EXTI2: {
+            path: exti2,
+            priority: 3,
+            interarrival: 100,
+            deadline: 25,
+            wcet: 20,
+            resources: [B],
+        },
+    },
+";
+
+fn wcets(app: &analysis::App) -> rta::Wcets {
+    app.tasks
+        .iter()
+        .map(|(name, task)| (name.clone(), task.wcet.unwrap()))
+        .collect()
+}
+
+#[test]
+fn interference() {
+    // R = 10 -> I = 5 + 3 = 8, R = 18 -> I = 5 + 6 = 11, R = 21 -> I = 10 + 6 = 16,
+    // R = 26 -> I = 16
+    assert_eq!(rta::interference(10, 0, 100, &[(5, 20), (3, 15)]), 16);
+
+    // the iteration stops once R = 21 exceeds the deadline
+    assert_eq!(rta::interference(10, 0, 20, &[(5, 20), (3, 15)]), 11);
+
+    // the blocking time extends the window
+    assert_eq!(rta::interference(10, 3, 100, &[(5, 20)]), 5);
+    assert_eq!(rta::interference(10, 11, 100, &[(5, 20)]), 10);
+
+    assert_eq!(rta::interference(10, 0, 100, &[]), 0);
+}
+
+#[test]
+fn blocking() {
+    let app = analysis::parse_app(APP).unwrap();
+    let ownerships = analysis::ownerships(&app);
+    let sections = blocking::declared(&app);
+    let blockings = blocking::app(&app, &ownerships, &sections, &wcets(&app));
+
+    // nothing runs below `EXTI0` but `idle`, which holds no resource
+    let exti0 = &blockings[&Ident::from("EXTI0")];
+    assert_eq!(exti0.time, 0);
+    assert!(exti0.blocker.is_none());
+
+    // `EXTI0` holds `A` (ceiling 2) for up to 10 cycles
+    let exti1 = &blockings[&Ident::from("EXTI1")];
+    assert_eq!(exti1.time, 10);
+    let blocker = exti1.blocker.as_ref().unwrap();
+    assert_eq!(blocker.task, Ident::from("EXTI0"));
+    assert_eq!(blocker.resource, Some(Ident::from("A")));
+
+    // `EXTI1` holds `B` (ceiling 3) for up to 7 cycles; `A` can't block it
+    let exti2 = &blockings[&Ident::from("EXTI2")];
+    assert_eq!(exti2.time, 7);
+    let blocker = exti2.blocker.as_ref().unwrap();
+    assert_eq!(blocker.task, Ident::from("EXTI1"));
+    assert_eq!(blocker.resource, Some(Ident::from("B")));
+}
+
+#[test]
+fn unknown_lengths_are_bounded_by_the_wcet() {
+    let app = analysis::parse_app(APP).unwrap();
+    let ownerships = analysis::ownerships(&app);
+    let blockings = blocking::app(&app, &ownerships, &Default::default(), &wcets(&app));
+
+    assert_eq!(blockings[&Ident::from("EXTI1")].time, 40);
+    assert_eq!(blockings[&Ident::from("EXTI2")].time, 30);
+}
+
+#[test]
+fn responses() {
+    let app = analysis::parse_app(APP).unwrap();
+    let ownerships = analysis::ownerships(&app);
+    let sections = blocking::declared(&app);
+    let mut diagnostics = Diagnostics::new();
+    let responses = rta::app(&app, &ownerships, &wcets(&app), &sections, &mut diagnostics)
+        .unwrap();
+
+    // R = C + B + I = 40 + 0 + (30 + 20) = 90
+    let exti0 = &responses[&Ident::from("EXTI0")];
+    assert_eq!((exti0.blocking, exti0.interference, exti0.time()), (0, 50, 90));
+    assert!(exti0.is_schedulable());
+
+    // R = 30 + 10 + 20 = 60
+    let exti1 = &responses[&Ident::from("EXTI1")];
+    assert_eq!((exti1.blocking, exti1.interference, exti1.time()), (10, 20, 60));
+    assert!(exti1.is_schedulable());
+
+    // R = 20 + 7 + 0 = 27 > D = 25
+    let exti2 = &responses[&Ident::from("EXTI2")];
+    assert_eq!((exti2.blocking, exti2.interference, exti2.time()), (7, 0, 27));
+    assert!(!exti2.is_schedulable());
+}
+
+#[test]
+fn queues() {
+    // `EXTI0` enqueues `foo` in critical sections on `FQ::foo` and `RQ::2`, whose
+    // ceiling is the priority of the dispatcher of `foo`
+    let app = analysis::parse_app(
+        "
+        device: stm32f103xx,
+
+        overheads: { queue: 12 },
+
+        free_interrupts: [EXTI1],
+
+        tasks: {
+            EXTI0: {
+                path: exti0,
+                priority: 1,
+                spawn: [foo],
+            },
+
+            foo: {
+                path: foo,
+                priority: 2,
+                capacity: 1,
+            },
+        },
+        ",
+    ).unwrap();
+    let ownerships = analysis::ownerships(&app);
+    let blockings = blocking::app(&app, &ownerships, &Default::default(), &Default::default());
+
+    assert_eq!(blockings[&Ident::from("EXTI0")].time, 0);
+
+    let foo = &blockings[&Ident::from("foo")];
+    assert_eq!(foo.time, 12);
+    let blocker = foo.blocker.as_ref().unwrap();
+    assert_eq!(blocker.task, Ident::from("EXTI0"));
+    assert!(blocker.resource == Some(Ident::from("FQ::foo"))
+        || blocker.resource == Some(Ident::from("RQ::2")));
+}
+
+// `foo` and `tick` are dispatched from `EXTI1` and `EXTI2`; the `SYS_TICK`
+// handler runs at the priority of `tick`
+const SOFTWARE: &str = "
+    device: stm32f103xx,
+
+    overheads: { dispatch: 5, queue: 3, release: 8 },
+
+    free_interrupts: [EXTI1, EXTI2],
+
+    tasks: {
+        EXTI0: {
+            path: exti0,
+            priority: 1,
+            interarrival: 1000,
+            wcet: 100,
+            spawn: [foo],
+        },
+
+        foo: {
+            path: foo,
+            priority: 2,
+            capacity: 1,
+            interarrival: 500,
+            wcet: 50,
+        },
+
+        tick: {
+            path: tick,
+            priority: 3,
+            period: 200,
+            wcet: 10,
+        },
+    },
+";
+
+#[test]
+fn overheads() {
+    let app = analysis::parse_app(SOFTWARE).unwrap();
+    let ownerships = analysis::ownerships(&app);
+    let sections = blocking::declared(&app);
+    let mut diagnostics = Diagnostics::new();
+    let responses = rta::app(&app, &ownerships, &wcets(&app), &sections, &mut diagnostics)
+        .unwrap();
+
+    // R = 100 + 0 + ((50 + 5) + (10 + 5) + 8) = 178
+    let exti0 = &responses[&Ident::from("EXTI0")];
+    assert_eq!((exti0.wcet, exti0.blocking, exti0.interference), (100, 0, 78));
+
+    // `EXTI0` enqueues `foo`: R = (50 + 5) + 3 + ((10 + 5) + 8) = 81
+    let foo = &responses[&Ident::from("foo")];
+    assert_eq!((foo.wcet, foo.blocking, foo.interference), (55, 3, 23));
+
+    // nothing else runs at priority 3 or above
+    let tick = &responses[&Ident::from("tick")];
+    assert_eq!((tick.wcet, tick.blocking, tick.interference), (15, 0, 0));
+}
+
+/// The errors of the analysis of `input` with `wcets`, sorted
+fn errors(input: &str, wcets: &rta::Wcets) -> Vec<(Vec<String>, String)> {
+    let app = analysis::parse_app(input).unwrap();
+    let ownerships = analysis::ownerships(&app);
+
+    let mut diagnostics = Diagnostics::new();
+    assert!(rta::app(&app, &ownerships, wcets, &Default::default(), &mut diagnostics).is_none());
+
+    let mut errors = diagnostics.errors().to_vec();
+    errors.sort();
+    errors
+}
+
+fn at(path: &[&str]) -> Vec<String> {
+    path.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn undeclared_overheads() {
+    let input = SOFTWARE.replace("dispatch: 5, ", "");
+    let errors = errors(&input, &wcets(&analysis::parse_app(&input).unwrap()));
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].0, at(&["tasks", "foo", "wcet"]));
+    assert!(errors[0].1.contains("`dispatch` overhead"));
+    assert_eq!(errors[1].0, at(&["tasks", "tick", "wcet"]));
+
+    let input = SOFTWARE.replace("release: 8 ", "");
+    let errors = ::errors(&input, &wcets(&analysis::parse_app(&input).unwrap()));
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, at(&["overheads"]));
+    assert!(errors[0].1.contains("`release` overhead"));
+}
+
+#[test]
+fn wcets_of_other_tasks() {
+    let mut wcets = wcets(&analysis::parse_app(APP).unwrap());
+    wcets.remove(&Ident::from("EXTI1"));
+    wcets.insert(Ident::from("EXTI3"), 10);
+
+    // each task needs a WCET, and each WCET a task
+    let errors = errors(APP, &wcets);
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].0, at(&["tasks", "EXTI1"]));
+    assert!(errors[0].1.contains("no worst case execution time"));
+    assert_eq!(errors[1].0, at(&["tasks", "EXTI3"]));
+    assert!(errors[1].1.contains("not a task"));
+}
+
+/// The errors of the schedulability check of `input`, sorted
+fn check(input: &str) -> Vec<(Vec<String>, String)> {
+    let app = analysis::parse_app(input).unwrap();
+    let ownerships = analysis::ownerships(&app);
+
+    let mut diagnostics = Diagnostics::new();
+    rta::check(&app, &ownerships, &Default::default(), &mut diagnostics);
+
+    let mut errors = diagnostics.errors().to_vec();
+    errors.sort();
+    errors
+}
+
+#[test]
+fn unschedulable() {
+    // only `EXTI2` misses its deadline
+    let errors = check(APP);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, at(&["tasks", "EXTI2"]));
+    assert!(errors[0].1.contains("R = C + B + I = 20 + 7 (`EXTI1` holding `B`) + 0 = 27 > D = 25"));
+
+    assert!(check(&APP.replace("deadline: 25", "deadline: 30")).is_empty());
+}
+
+#[test]
+fn some_wcets() {
+    // `wcet` is all-or-nothing: the tasks without it are reported
+    let errors = check(&APP.replace("wcet: 40,", "").replace("wcet: 30,", ""));
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].0, at(&["tasks", "EXTI0"]));
+    assert!(errors[0].1.contains("must declare its `wcet`"));
+    assert_eq!(errors[1].0, at(&["tasks", "EXTI1"]));
+
+    // no `wcet`, no analysis
+    let none = APP.replace("wcet: 40,", "").replace("wcet: 30,", "").replace("wcet: 20,", "");
+    assert!(check(&none).is_empty());
+}
@@ -0,0 +1,21 @@
+  /* breakpoints with an immediate, see `rtfm::bkpt_{1,2,3}` */
+  .section .text.__rtfm_bkpt_1
+  .global __rtfm_bkpt_1
+  .thumb_func
+__rtfm_bkpt_1:
+  bkpt #0x01
+  bx lr
+
+  .section .text.__rtfm_bkpt_2
+  .global __rtfm_bkpt_2
+  .thumb_func
+__rtfm_bkpt_2:
+  bkpt #0x02
+  bx lr
+
+  .section .text.__rtfm_bkpt_3
+  .global __rtfm_bkpt_3
+  .thumb_func
+__rtfm_bkpt_3:
+  bkpt #0x03
+  bx lr
@@ -1,3 +1,5 @@
+extern crate cc;
+
 use std::env;
 
 fn main() {
@@ -7,5 +9,14 @@ fn main() {
         println!("cargo:rustc-cfg=armv6m");
     }
 
+    // the breakpoints used by the `wcet_bkpt` and `wcet_nop` modes
+    let wcet = env::var_os("CARGO_FEATURE_WCET_BKPT").is_some()
+        || env::var_os("CARGO_FEATURE_WCET_NOP").is_some();
+    if target.starts_with("thumb") && wcet {
+        cc::Build::new().file("asm.s").compile("rtfm");
+        println!("cargo:rustc-cfg=rtfm_bkpt");
+    }
+
+    println!("cargo:rerun-if-changed=asm.s");
     println!("cargo:rerun-if-changed=build.rs");
 }
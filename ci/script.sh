@@ -4,18 +4,22 @@ main() {
     if [ $TARGET = x86_64-unknown-linux-gnu ]; then
         cargo build
         cargo test --test cfail
+        RUSTFLAGS="" cargo test --features host \
+                 --test host --test explore --test exceptions --test units
+        RUSTFLAGS="" cargo test --manifest-path analysis/Cargo.toml
+        RUSTFLAGS="" cargo test --manifest-path tools/Cargo.toml
         return
     fi
 
     case $TARGET in
         thumbv7em-none-eabi*)
-            xargo check --target $TARGET --features cm7-r0p1
-            xargo check --target $TARGET --features cm7-r0p1 --examples
+            cargo check --target $TARGET --features cm7-r0p1
+            cargo check --target $TARGET --features cm7-r0p1 --examples
         ;;
     esac
 
-    xargo check --target $TARGET
-    xargo check --target $TARGET --examples
+    cargo check --target $TARGET
+    cargo check --target $TARGET --examples
 }
 
 main
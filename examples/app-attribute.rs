@@ -0,0 +1,63 @@
+//! The one task example written with the `#[app]` attribute
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m;
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f103xx;
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(feature = "klee_mode")]
+extern crate klee;
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
+
+use rtfm::attr::app;
+
+#[app(device = stm32f103xx)]
+mod app {
+    use cortex_m::peripheral::syst::SystClkSource;
+    use rtfm::Threshold;
+    use stm32f103xx::GPIOC;
+
+    // `static mut` items are the resources
+    static mut ON: bool = false;
+
+    // the handlers carry the keys of their contexts, minus `path`
+    #[init]
+    fn init(mut p: init::Peripherals, _r: init::Resources) {
+        p.device.RCC.apb2enr.modify(|_, w| w.iopcen().enabled());
+        p.device
+            .GPIOC
+            .crh
+            .modify(|_, w| w.mode13().output().cnf13().push());
+
+        p.core.SYST.set_clock_source(SystClkSource::Core);
+        p.core.SYST.set_reload(8_000_000); // 1s
+        p.core.SYST.enable_interrupt();
+        p.core.SYST.enable_counter();
+    }
+
+    #[idle]
+    fn idle() -> ! {
+        loop {
+            rtfm::wfi();
+        }
+    }
+
+    // `binds` names the exception this task is bound to; the signature of the handler is checked
+    // against the `resources` of the task
+    #[allow(unsafe_code)]
+    #[task(binds = SYS_TICK, resources = [ON])]
+    fn sys_tick(_t: &mut Threshold, mut r: SYS_TICK::Resources) {
+        *r.ON = !*r.ON;
+
+        // NOTE(unsafe) atomic write to a stateless register
+        if *r.ON {
+            unsafe { (*GPIOC::ptr()).bsrr.write(|w| w.bs13().set()) }
+        } else {
+            unsafe { (*GPIOC::ptr()).bsrr.write(|w| w.br13().reset()) }
+        }
+    }
+}
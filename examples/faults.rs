@@ -0,0 +1,68 @@
+//! Fault exceptions bound to tasks
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f103xx;
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(feature = "klee_mode")]
+extern crate klee;
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
+
+use rtfm::{app, Resource, Threshold};
+
+app! {
+    device: stm32f103xx,
+
+    resources: {
+        static FAULTS: u32 = 0;
+    },
+
+    tasks: {
+        // `init` programs the priority of `BUS_FAULT` and enables it
+        BUS_FAULT: {
+            path: bus_fault,
+            priority: 3,
+            resources: [FAULTS],
+        },
+
+        EXTI0: {
+            path: exti0,
+            priority: 1,
+            resources: [FAULTS],
+        },
+
+        // non-maskable: no `priority` and no resources
+        HARD_FAULT: {
+            path: hard_fault,
+            local: {
+                static COUNT: u32 = 0;
+            },
+        },
+    },
+}
+
+fn init(_p: init::Peripherals, _r: init::Resources) {}
+
+fn idle() -> ! {
+    loop {
+        rtfm::wfi();
+    }
+}
+
+fn bus_fault(t: &mut Threshold, mut r: BUS_FAULT::Resources) {
+    *r.FAULTS.borrow_mut(t) += 1;
+}
+
+fn exti0(t: &mut Threshold, r: EXTI0::Resources) {
+    let _faults: u32 = r.FAULTS.claim(t, |faults, _| *faults);
+}
+
+fn hard_fault(r: HARD_FAULT::Resources) {
+    *r.COUNT += 1;
+
+    loop {}
+}
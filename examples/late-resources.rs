@@ -1,16 +1,21 @@
 //! Demonstrates initialization of resources in `init`.
 #![deny(unsafe_code)]
 #![deny(warnings)]
-#![feature(proc_macro)]
 #![no_std]
+#![no_main]
 
 extern crate cortex_m_rtfm as rtfm;
-extern crate stm32f103xx;
+extern crate stm32f413;
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(feature = "klee_mode")]
+extern crate klee;
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
 
 use rtfm::{app, Threshold};
 
 app! {
-    device: stm32f103xx,
+    device: stm32f413,
 
     resources: {
         // Usually, resources are initialized with a constant initializer:
@@ -32,11 +37,16 @@ app! {
         // PORT is used by 2 tasks, making it a shared resource. This just tests
         // another internal code path and is not important for the example.
         static PORT: u16;
+
+        // MAC_ADDRESS and RX_COUNT are only used by `idle`, which gets plain
+        // references to them. Again, this only tests another code path.
+        static MAC_ADDRESS: [u8; 6];
+        static RX_COUNT: u32;
     },
 
     idle: {
         // Test that late resources can be used in idle
-        resources: [IP_ADDRESS],
+        resources: [IP_ADDRESS, &MAC_ADDRESS, RX_COUNT],
     },
 
     tasks: {
@@ -67,7 +77,9 @@ fn init(_p: init::Peripherals, _r: init::Resources) -> init::LateResources {
         // This struct will contain fields for all resources with omitted
         // initializers.
         IP_ADDRESS: ip_address,
+        MAC_ADDRESS: [0x02, 0, 0, 0, 0, 0x01],
         PORT: 0,
+        RX_COUNT: 0,
     }
 }
 
@@ -80,8 +92,13 @@ fn sys_tick(_t: &mut Threshold, r: SYS_TICK::Resources) {
 
 fn exti0(_t: &mut Threshold, _r: EXTI0::Resources) {}
 
-fn idle(_t: &mut Threshold, _r: idle::Resources) -> ! {
+fn idle(_t: &mut Threshold, r: idle::Resources) -> ! {
+    // `&'static [u8; 6]` and `&'static mut u32`
+    let _mac_address: &[u8; 6] = r.MAC_ADDRESS;
+    let rx_count: &mut u32 = r.RX_COUNT;
+
     loop {
+        *rx_count += 1;
         rtfm::wfi();
     }
 }
@@ -0,0 +1,60 @@
+//! Task-local state that persists across invocations
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f103xx;
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(feature = "klee_mode")]
+extern crate klee;
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
+
+use rtfm::{app, Resource, Threshold};
+
+app! {
+    device: stm32f103xx,
+
+    resources: {
+        static SHARED: u32 = 0;
+    },
+
+    tasks: {
+        // `COUNT` is only visible to `SYS_TICK` so it's not a resource
+        SYS_TICK: {
+            path: sys_tick,
+            resources: [SHARED],
+            local: {
+                static COUNT: u32 = 0;
+            },
+        },
+
+        EXTI0: {
+            path: exti0,
+            priority: 2,
+            resources: [SHARED],
+        },
+    },
+}
+
+fn init(_p: init::Peripherals, _r: init::Resources) {}
+
+fn idle() -> ! {
+    loop {
+        rtfm::wfi();
+    }
+}
+
+fn sys_tick(t: &mut Threshold, mut r: SYS_TICK::Resources) {
+    // no critical section needed to access the locals
+    *r.COUNT += 1;
+
+    let count = *r.COUNT;
+    r.SHARED.claim_mut(t, |shared, _| *shared = count);
+}
+
+fn exti0(t: &mut Threshold, r: EXTI0::Resources) {
+    let _shared: u32 = *r.SHARED.borrow(t);
+}
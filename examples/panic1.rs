@@ -1,13 +1,14 @@
 //! Minimal example with zero tasks
 //#![deny(unsafe_code)]
-// IMPORTANT always include this feature gate
-#![feature(proc_macro)]
-#![feature(used)]
 #![no_std]
+#![no_main]
 
 extern crate cortex_m_rtfm as rtfm;
 // IMPORTANT always do this rename
 extern crate stm32f413;
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
 
 #[macro_use]
 extern crate klee;
@@ -62,7 +63,7 @@ fn idle() -> ! {
 //
 // 1> rustup override set nightly-2018-01-10
 //
-// 2> xargo build --example panic1 --features klee_mode --target x86_64-unknown-linux-gnu
+// 2> cargo build --example panic1 --features klee_mode --target x86_64-unknown-linux-gnu
 //
 // 1) We need a slightly older version of Rust with a LLVM 4 backend to run KLEE
 //    `rustup override` will stick the directory to a set tool-chain (run once)
@@ -1,13 +1,14 @@
 //! Minimal example with zero tasks
 //#![deny(unsafe_code)]
-// IMPORTANT always include this feature gate
-#![feature(proc_macro)]
-#![feature(used)]
 #![no_std]
+#![no_main]
 
 extern crate cortex_m_rtfm as rtfm;
 // IMPORTANT always do this rename
 extern crate stm32f413;
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
 
 #[macro_use]
 extern crate klee;
@@ -60,7 +61,7 @@ fn idle() -> ! {
 // The real power of KLEE is the ability to symbolic execution.
 // We mark the mutable variable `j` as being symbolic (unknown).
 //
-// > xargo build --example panic2 --features klee_mode --target x86_64-unknown-linux-gnu
+// > cargo build --example panic2 --features klee_mode --target x86_64-unknown-linux-gnu
 //
 // You can now let KLEE run on the `.bc` file.
 //
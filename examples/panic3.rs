@@ -1,13 +1,14 @@
 //! Minimal example with zero tasks
 //#![deny(unsafe_code)]
-// IMPORTANT always include this feature gate
-#![feature(proc_macro)]
-#![feature(used)]
 #![no_std]
+#![no_main]
 
 extern crate cortex_m_rtfm as rtfm;
 // IMPORTANT always do this rename
 extern crate stm32f413;
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
 
 #[macro_use]
 extern crate klee;
@@ -89,9 +90,17 @@ fn idle() -> ! {
 // You might find that the task identifier is no longer 0.
 // On the host side you find the list of tasks by.
 //
-// > more klee/tasks.txt
-// autogenerated file
-// ["EXTI1", "EXTI2"]
+// > more target/rtfm/app.json
+// {
+//   "version": 1,
+//   "device": "stm32f413",
+//   ..
+//   "tasks": [
+//     { "name": "EXTI1", .. },
+//     { "name": "EXTI2", .. }
+//   ],
+//   ..
+// }
 //
 // In this case EXTI => task 0, EXTI2 = task 1, but they might be swapped
 // due to the underlying data structure being an (unordered) hash-map.
@@ -1,13 +1,14 @@
 //! Minimal example with zero tasks
 //#![deny(unsafe_code)]
-// IMPORTANT always include this feature gate
-#![feature(proc_macro)]
-#![feature(used)]
 #![no_std]
+#![no_main]
 
 extern crate cortex_m_rtfm as rtfm;
 // IMPORTANT always do this rename
 extern crate stm32f413;
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
 
 #[macro_use]
 extern crate klee;
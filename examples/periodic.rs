@@ -0,0 +1,78 @@
+//! Periodic software tasks
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f103xx;
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(feature = "klee_mode")]
+extern crate klee;
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
+
+use rtfm::{app, Threshold};
+
+app! {
+    device: stm32f103xx,
+
+    free_interrupts: [EXTI0, EXTI1],
+
+    // Execution time of the runtime, in clock cycles, used by the
+    // schedulability analysis
+    overheads: {
+        dispatch: 40,
+        queue: 20,
+        release: 60,
+    },
+
+    resources: {
+        static SAMPLES: u32 = 0;
+    },
+
+    tasks: {
+        // Released every 8_000 clock cycles; the deadline defaults to the
+        // period
+        sample: {
+            path: sample,
+            period: 8000,
+            priority: 2,
+            resources: [SAMPLES],
+            wcet: 500,
+        },
+
+        // Released every 80_000 clock cycles, starting 1_000 clock cycles
+        // after `init` returns
+        report: {
+            path: report,
+            period: 80000,
+            offset: 1000,
+            priority: 1,
+            resources: [SAMPLES],
+            wcet: 2000,
+        },
+    },
+}
+
+fn init(_p: init::Peripherals, _r: init::Resources) {}
+
+fn idle() -> ! {
+    loop {
+        rtfm::wfi();
+    }
+}
+
+fn sample(_t: &mut Threshold, mut r: sample::Resources) {
+    *r.SAMPLES += 1;
+}
+
+fn report(t: &mut Threshold, mut r: report::Resources) {
+    use rtfm::Resource;
+
+    let _samples = r.SAMPLES.claim_mut(t, |samples, _| {
+        let n = *samples;
+        *samples = 0;
+        n
+    });
+}
@@ -0,0 +1,70 @@
+//! Resources that some tasks only read
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f103xx;
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(feature = "klee_mode")]
+extern crate klee;
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
+
+use rtfm::{app, ReadOnlyResource, Resource, Threshold};
+
+app! {
+    device: stm32f103xx,
+
+    resources: {
+        static CONFIG: u32 = 0;
+    },
+
+    tasks: {
+        // writes to `CONFIG`
+        EXTI0: {
+            path: exti0,
+            priority: 1,
+            resources: [CONFIG],
+        },
+
+        // `EXTI1` and `EXTI2` only read `CONFIG` (`&`) so they don't block each
+        // other: the read ceiling of `CONFIG` is 1, the priority of its only
+        // writer
+        EXTI1: {
+            path: exti1,
+            priority: 2,
+            resources: [&CONFIG],
+        },
+
+        EXTI2: {
+            path: exti2,
+            priority: 3,
+            resources: [&CONFIG],
+        },
+    },
+}
+
+fn init(_p: init::Peripherals, _r: init::Resources) {}
+
+fn idle() -> ! {
+    loop {
+        rtfm::wfi();
+    }
+}
+
+fn exti0(t: &mut Threshold, mut r: EXTI0::Resources) {
+    // claiming for writing raises the threshold to 3, the ceiling of `CONFIG`
+    r.CONFIG.claim_mut(t, |config, _| *config += 1);
+}
+
+fn exti1(t: &mut Threshold, r: EXTI1::Resources) {
+    // no critical section is needed: the writer can't preempt this task
+    let _config: u32 = r.CONFIG.claim(t, |config, _| *config);
+}
+
+fn exti2(_t: &mut Threshold, r: EXTI2::Resources) {
+    // and the proxy derefs to a shared reference
+    let _config: u32 = *r.CONFIG;
+}
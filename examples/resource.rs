@@ -1,12 +1,12 @@
 //! Example using shared resources
-// IMPORTANT always include this feature gate
-#![feature(proc_macro)]
-#![feature(used)]
 #![no_std]
-#![feature(asm)]
+#![no_main]
 extern crate cortex_m_rtfm as rtfm;
 // IMPORTANT always do this rename
 extern crate stm32f413;
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
 
 #[macro_use]
 extern crate klee;
@@ -101,7 +101,7 @@ fn idle() -> ! {
 //
 // Assignment 1.
 // Build the application for KLEE analysis.
-// > xargo build --example resource  --features klee_mode --target x86_64-unknown-linux-gnu
+// > cargo build --example resource  --features klee_mode --target x86_64-unknown-linux-gnu
 //
 // Start the KLEE docker
 // > docker run --rm --user $(id -u):$(id -g) -v $PWD/target/x86_64-unknown-linux-gnu/debug/examples:/mnt -w /mnt -it afoht/llvm-klee-4 /bin/bash
@@ -121,10 +121,13 @@ fn idle() -> ! {
 //
 // When compiled with the --features klee_mode, resources are treated as symbolic.
 // Investigate the output files (in `klee-last`). Use `ktest-tool` to examine the tests.
-// The file `klee/tasks.txt` gives the order of tasks, it may vary for each run.
+// The `tasks` list in `target/rtfm/app.json` gives the order of tasks (sorted by name).
 // ```
-// // autogenerated file
-// ["EXTI1", "EXTI2", "EXTI3"]
+// "tasks": [
+//   { "name": "EXTI1", .. },
+//   { "name": "EXTI2", .. },
+//   { "name": "EXTI3", .. }
+// ],
 // ```
 //
 // How many tests were generated for task EXIT1?
@@ -170,7 +173,7 @@ fn idle() -> ! {
 // Now we will analyse the exection time for each such test.
 //
 // In order to do that comiple your application.
-// > xargo build --example resource --release --features wcet_bkpt --target thumbv7em-none-eabihf
+// > cargo build --example resource --release --features wcet_bkpt --target thumbv7em-none-eabihf
 //
 // We build in --release for optimizing the performance.
 // The --features wcet_bkpt will insert a `bkpt` instruction on LOCK ond UNLOCK of
@@ -491,7 +494,7 @@ fn idle() -> ! {
 // even remove comlete loops.
 //
 // Now lets have a look at using optimized LLVM and how it affects the number of tests.
-// > xargo build --example resource --release --features klee_mode --target x86_64-unknown-linux-gnu
+// > cargo build --example resource --release --features klee_mode --target x86_64-unknown-linux-gnu
 //
 // and now start a docker for the optimized build
 // > docker run --rm --user $(id -u):$(id -g) -v $PWD/target/x86_64-unknown-linux-gnu/release/examples:/mnt -w /mnt -it afoht/llvm-klee-4 /bin/bash
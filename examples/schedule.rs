@@ -0,0 +1,61 @@
+//! Software tasks released at a later time through the timer queue
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f103xx;
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(feature = "klee_mode")]
+extern crate klee;
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
+
+use rtfm::{app, Duration, Threshold};
+
+// 8 MHz clock
+const PERIOD: u32 = 8_000_000;
+
+app! {
+    device: stm32f103xx,
+
+    free_interrupts: [EXTI0],
+
+    resources: {
+        static ON: bool = false;
+    },
+
+    init: {
+        schedule: [toggle],
+    },
+
+    tasks: {
+        // A software task that schedules itself; `SYS_TICK` releases it once
+        // its time has come
+        toggle: {
+            path: toggle,
+            priority: 1,
+            input: bool,
+            resources: [ON],
+            schedule: [toggle],
+        },
+    },
+}
+
+fn init(_p: init::Peripherals, _r: init::Resources, mut s: init::Schedule) {
+    s.toggle(Duration::from_cycles(PERIOD), true).unwrap();
+}
+
+fn idle() -> ! {
+    loop {
+        rtfm::wfi();
+    }
+}
+
+fn toggle(t: &mut Threshold, mut r: toggle::Resources, mut s: toggle::Schedule, on: bool) {
+    *r.ON = on;
+
+    // `toggle` has a single message slot which was freed before it started
+    s.toggle(t, Duration::from_cycles(PERIOD), !on).unwrap();
+}
@@ -0,0 +1,93 @@
+//! Software tasks that pass messages to each other
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f103xx;
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(feature = "klee_mode")]
+extern crate klee;
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
+
+use rtfm::{app, Threshold};
+
+app! {
+    device: stm32f103xx,
+
+    // Interrupts not used by the application; they are used to run the software
+    // tasks. One interrupt is needed per priority level used by software tasks
+    free_interrupts: [EXTI0, EXTI1],
+
+    resources: {
+        static TOTAL: u32 = 0;
+    },
+
+    init: {
+        spawn: [ping],
+    },
+
+    tasks: {
+        // A hardware task that spawns a software task
+        TIM2: {
+            path: tim2,
+            priority: 1,
+            spawn: [ping],
+        },
+
+        // A software task: it's not bound to an interrupt; it runs when some
+        // other context spawns it. Up to 4 messages can be pending
+        ping: {
+            path: ping,
+            priority: 1,
+            capacity: 4,
+            input: u32,
+            resources: [TOTAL],
+            spawn: [pong],
+        },
+
+        pong: {
+            path: pong,
+            priority: 2,
+            capacity: 2,
+            input: (u32, bool),
+            resources: [TOTAL],
+        },
+    },
+}
+
+fn init(_p: init::Peripherals, _r: init::Resources, mut s: init::Spawn) {
+    // the message is queued; `ping` runs once `init` returns
+    s.ping(0).unwrap();
+}
+
+fn idle() -> ! {
+    loop {
+        rtfm::wfi();
+    }
+}
+
+fn tim2(t: &mut Threshold, mut s: TIM2::Spawn) {
+    // `Err` means that the queue of `ping` is full; the message is returned
+    if s.ping(t, 1).is_err() {
+        // ..
+    }
+}
+
+fn ping(t: &mut Threshold, mut r: ping::Resources, mut s: ping::Spawn, n: u32) {
+    use rtfm::Resource;
+
+    let total = r.TOTAL.claim_mut(t, |total, _| {
+        *total += n;
+        *total
+    });
+
+    // `pong` has higher priority so it preempts `ping` right away
+    let _ = s.pong(t, (total, n % 2 == 0));
+}
+
+fn pong(_t: &mut Threshold, mut r: pong::Resources, (total, _even): (u32, bool)) {
+    *r.TOTAL = total;
+}
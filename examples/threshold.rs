@@ -0,0 +1,66 @@
+//! Preemption thresholds
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f103xx;
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(feature = "klee_mode")]
+extern crate klee;
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
+
+use rtfm::{app, Resource, Threshold};
+
+app! {
+    device: stm32f103xx,
+
+    resources: {
+        static SHARED: u32 = 0;
+    },
+
+    tasks: {
+        // once started `EXTI0` runs at priority 2 so `EXTI1` can't preempt it:
+        // the two tasks never use the stack at the same time
+        EXTI0: {
+            path: exti0,
+            priority: 1,
+            threshold: 2,
+            resources: [SHARED],
+        },
+
+        EXTI1: {
+            path: exti1,
+            priority: 2,
+            resources: [SHARED],
+        },
+
+        // `EXTI2` can still preempt both
+        EXTI2: {
+            path: exti2,
+            priority: 3,
+        },
+    },
+}
+
+fn init(_p: init::Peripherals, _r: init::Resources) {}
+
+fn idle() -> ! {
+    loop {
+        rtfm::wfi();
+    }
+}
+
+fn exti0(t: &mut Threshold, mut r: EXTI0::Resources) {
+    // no critical section: the ceiling of `SHARED` (2) doesn't exceed the
+    // threshold of this task
+    *r.SHARED.borrow_mut(t) += 1;
+}
+
+fn exti1(t: &mut Threshold, mut r: EXTI1::Resources) {
+    *r.SHARED.borrow_mut(t) += 2;
+}
+
+fn exti2() {}
@@ -0,0 +1,69 @@
+//! A pool of buffers shared as a multi-unit resource
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f103xx;
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(feature = "klee_mode")]
+extern crate klee;
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
+
+use rtfm::{app, Threshold, UnitResource};
+
+app! {
+    device: stm32f103xx,
+
+    resources: {
+        static BUFFERS: [[u8; 16]; 3] = [[0; 16]; 3];
+    },
+
+    tasks: {
+        // holds up to 2 buffers at once
+        EXTI0: {
+            path: exti0,
+            priority: 1,
+            resources: [BUFFERS],
+            units: { BUFFERS: 2 },
+        },
+
+        // holds a single buffer at a time (the default)
+        EXTI1: {
+            path: exti1,
+            priority: 2,
+            resources: [BUFFERS],
+        },
+    },
+}
+
+fn init(_p: init::Peripherals, _r: init::Resources) {}
+
+fn idle() -> ! {
+    loop {
+        rtfm::wfi();
+    }
+}
+
+fn exti0(t: &mut Threshold, mut r: EXTI0::Resources) {
+    // one buffer is left after this claim so `EXTI1` can still preempt this
+    // task: the ceiling of `BUFFERS` stays at 1
+    r.BUFFERS.claim_units(t, 2, |buffers, _| {
+        if let Some(buffer) = buffers.get_mut(0) {
+            buffer[0] = 1;
+        }
+        if let Some(buffer) = buffers.get_mut(1) {
+            buffer[0] = 2;
+        }
+    });
+}
+
+fn exti1(t: &mut Threshold, mut r: EXTI1::Resources) {
+    r.BUFFERS.claim_units(t, 1, |buffers, _| {
+        if let Some(buffer) = buffers.get_mut(0) {
+            buffer[0] = 3;
+        }
+    });
+}
@@ -1,12 +1,16 @@
 //! Minimal example with zero tasks
 #![deny(unsafe_code)]
 #![deny(warnings)]
-// IMPORTANT always include this feature gate
-#![feature(proc_macro)]
 #![no_std]
+#![no_main]
 
 extern crate cortex_m_rtfm as rtfm; // IMPORTANT always do this rename
 extern crate stm32f103xx; // the device crate
+// KLEE builds use the panic handler of the `klee` crate
+#[cfg(feature = "klee_mode")]
+extern crate klee;
+#[cfg(not(feature = "klee_mode"))]
+extern crate panic_halt;
 
 // import the procedural macro
 use rtfm::app;
@@ -524,13 +524,13 @@ def ktest_iterate():
         os.chdir(rustoutputfolder)
     except IOError:
         print(rustoutputfolder + "not found. Need to run\n")
-        print("xargo build --example " + example_name + " --features" +
+        print("cargo build --example " + example_name + " --features" +
               " klee_mode --target x86_64-unknown-linux-gnu ")
         print("\nand docker run --rm --user (id -u):(id -g)" +
               "-v $PWD" + "/" + klee_out_folder + ":/mnt" +
               "-w /mnt -it afoht/llvm-klee-4 /bin/bash ")
         if autobuild:
-            xargo_run("klee")
+            cargo_run("klee")
             klee_run()
         else:
             print("Run the above commands before proceeding")
@@ -540,7 +540,7 @@ def ktest_iterate():
         """
         The folder is empty, generate some files
         """
-        xargo_run("klee")
+        cargo_run("klee")
         klee_run()
 
     dirlist = next(os.walk("."))[1]
@@ -579,38 +579,104 @@ def ktest_iterate():
     return file_list
 
 
+def rtfm_meta_read(path):
+    """ Read the task table the `app!` macro embeds in the `.rtfm_meta`
+    section of the ELF file at `path`
+
+    Returns a list of (name, priority, interarrival) tuples sorted by name,
+    which is also the order in which the tasks are numbered in klee_mode
+    """
+
+    with open(path, 'rb') as fin:
+        elf = fin.read()
+
+    # ELF32 little endian header: section header table offset, entry size,
+    # number of entries and index of the section name table
+    (shoff,) = struct.unpack_from('<I', elf, 0x20)
+    (shentsize, shnum, shstrndx) = struct.unpack_from('<HHH', elf, 0x2e)
+
+    def section(i):
+        # (name offset, file offset, size)
+        (name, _, _, _, offset, size) = struct.unpack_from(
+            '<IIIIII', elf, shoff + i * shentsize)
+        return (name, offset, size)
+
+    (_, strtab, _) = section(shstrndx)
+    data = None
+    for i in range(shnum):
+        (name, offset, size) = section(i)
+        end = elf.index(b'\0', strtab + name)
+        if elf[strtab + name:end] == b'.rtfm_meta':
+            data = elf[offset:offset + size]
+            break
+
+    if data is None:
+        print("The ELF has no `.rtfm_meta` section")
+        sys.exit(1)
+
+    if data[0:4] != b'RTFM' or bytearray(data)[4] != 2:
+        print("Unsupported `.rtfm_meta` table")
+        sys.exit(1)
+
+    data = bytearray(data)
+    ntasks = data[5]
+    nresources = data[6]
+    pos = [8]
+
+    def u8():
+        pos[0] += 1
+        return data[pos[0] - 1]
+
+    def string():
+        n = u8()
+        pos[0] += n
+        return data[pos[0] - n:pos[0]].decode()
+
+    for _ in range(nresources):
+        string()  # name
+        u8()  # ceiling
+
+    tasks = []
+    for _ in range(ntasks):
+        name = string()
+        string()  # symbol
+        priority = u8()
+        u8()  # exception number
+        (interarrival,) = struct.unpack_from('<I', data, pos[0])
+        pos[0] += 4
+        pos[0] += u8()  # resources
+        tasks.append((name, priority, interarrival))
+
+    return tasks
+
+
 def tasklist_get():
-    """ Parse the automatically generated tasklist
+    """ Read the task list from the ELF file loaded in GDB
     """
 
-    if debug:
-        print(os.getcwd())
-    with open('klee/tasks.txt') as fin:
-        for line in fin:
-                # print(line)
-            if not line == "// autogenerated file\n":
-                return [x.strip().strip("[]\"").split(' ')
-                        for x in line.split(',')]
+    return [[name, str(priority), str(interarrival)]
+            for (name, priority, interarrival)
+            in rtfm_meta_read(gdb.progspaces()[0].filename)]
 
 
-""" Run xargo for building """
+""" Run cargo for building """
 
 
-def xargo_run(mode):
+def cargo_run(mode):
 
     if "klee" in mode:
-        xargo_cmd = ("xargo build --release --example " + example_name
+        cargo_cmd = ("cargo build --release --example " + example_name
                      + " --features "
                      + "klee_mode --target x86_64-unknown-linux-gnu ")
     elif "stm" in mode:
-        xargo_cmd = ("xargo build --release --example " + example_name +
+        cargo_cmd = ("cargo build --release --example " + example_name +
                      " --features " +
                      "wcet_bkpt --target thumbv7em-none-eabihf")
     else:
         print("Provide either 'klee' or 'stm' as mode")
         sys.exit(1)
 
-    call(xargo_cmd, shell=True)
+    call(cargo_cmd, shell=True)
 
 
 """ Stub for running KLEE on the LLVM IR """
@@ -709,20 +775,20 @@ if gdb.progspaces()[0].filename:
         os.path.exists(gdb.progspaces()[0].filename)
     except IOError:
         """ Compiles the given example """
-        xargo_run("stm")
-        xargo_run("klee")
+        cargo_run("stm")
+        cargo_run("klee")
 else:
     example_name = debug_file
     print("Defaulting to example '%s' for debugging." % example_name)
     try:
         if example_name not in os.listdir(stm_out_folder):
             """ Compiles the default example """
-            xargo_run("stm")
-            xargo_run("klee")
+            cargo_run("stm")
+            cargo_run("klee")
     except IOError:
         """ Compiles the default example """
-        xargo_run("stm")
-        xargo_run("klee")
+        cargo_run("stm")
+        cargo_run("klee")
 
 """ Tell GDB to load the file """
 gdb.execute("file %s" % (stm_out_folder + example_name))
@@ -778,7 +844,7 @@ gdb.execute("continue")
 #
 # Assignment 1.
 # Run the example and study the output.
-# you may need to run xargo clean first
+# you may need to run cargo clean first
 #
 # It generates `output data`, a list of list, something like:
 # Finished all ktest files!
@@ -838,7 +904,7 @@ gdb.execute("continue")
 # The vector
 # interarrival = [100, 30, 40]
 # should match the arrival time of EXTI1, EXTI2, and EXTI3 respectively
-# you may need to change the order depending or your klee/tasks.txt file
+# you may need to change the order depending or your target/rtfm/app.json file
 # (in the future interarrival and deadlines will be in the RTFM model,
 # but for now we introduce them by hand)
 #
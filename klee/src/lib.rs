@@ -1,15 +1,13 @@
 #![no_std]
-#![feature(compiler_builtins_lib)]
-#![feature(lang_items)]
 
-extern crate compiler_builtins;
 extern crate cstr_core;
 extern crate cty;
 
-// mod lang_items;
 pub mod ll;
 
 use core::mem;
+#[cfg(feature = "klee_mode")]
+use core::panic::PanicInfo;
 
 use cty::c_void;
 
@@ -42,6 +40,13 @@ pub fn k_mk_symbol<T>(t: &mut T, name: &CStr) {
     }
 }
 
+/// panics abort, so KLEE reports them as errors
+#[cfg(feature = "klee_mode")]
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    k_abort()
+}
+
 #[inline(always)]
 pub fn k_abort() -> ! {
     unsafe {
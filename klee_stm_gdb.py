@@ -473,17 +473,83 @@ def ktest_iterate():
     return file_list
 
 
+def rtfm_meta_read(path):
+    """ Read the task table the `app!` macro embeds in the `.rtfm_meta`
+    section of the ELF file at `path`
+
+    Returns a list of (name, priority, interarrival) tuples sorted by name,
+    which is also the order in which the tasks are numbered in klee_mode
+    """
+
+    with open(path, 'rb') as fin:
+        elf = fin.read()
+
+    # ELF32 little endian header: section header table offset, entry size,
+    # number of entries and index of the section name table
+    (shoff,) = struct.unpack_from('<I', elf, 0x20)
+    (shentsize, shnum, shstrndx) = struct.unpack_from('<HHH', elf, 0x2e)
+
+    def section(i):
+        # (name offset, file offset, size)
+        (name, _, _, _, offset, size) = struct.unpack_from(
+            '<IIIIII', elf, shoff + i * shentsize)
+        return (name, offset, size)
+
+    (_, strtab, _) = section(shstrndx)
+    data = None
+    for i in range(shnum):
+        (name, offset, size) = section(i)
+        end = elf.index(b'\0', strtab + name)
+        if elf[strtab + name:end] == b'.rtfm_meta':
+            data = elf[offset:offset + size]
+            break
+
+    if data is None:
+        print("The ELF has no `.rtfm_meta` section")
+        sys.exit(1)
+
+    if data[0:4] != b'RTFM' or bytearray(data)[4] != 2:
+        print("Unsupported `.rtfm_meta` table")
+        sys.exit(1)
+
+    data = bytearray(data)
+    ntasks = data[5]
+    nresources = data[6]
+    pos = [8]
+
+    def u8():
+        pos[0] += 1
+        return data[pos[0] - 1]
+
+    def string():
+        n = u8()
+        pos[0] += n
+        return data[pos[0] - n:pos[0]].decode()
+
+    for _ in range(nresources):
+        string()  # name
+        u8()  # ceiling
+
+    tasks = []
+    for _ in range(ntasks):
+        name = string()
+        string()  # symbol
+        priority = u8()
+        u8()  # exception number
+        (interarrival,) = struct.unpack_from('<I', data, pos[0])
+        pos[0] += 4
+        pos[0] += u8()  # resources
+        tasks.append((name, priority, interarrival))
+
+    return tasks
+
+
 def tasklist_get():
-    """ Parse the automatically generated tasklist
+    """ Read the task list from the ELF file loaded in GDB
     """
 
-    if debug:
-        print(os.getcwd())
-    with open('klee/tasks.txt') as fin:
-            for line in fin:
-                # print(line)
-                if not line == "// autogenerated file\n":
-                    return [x.strip().strip("[]\"") for x in line.split(',')]
+    return [name for (name, _, _)
+            in rtfm_meta_read(gdb.progspaces()[0].filename)]
 
 
 def xargo_run(mode):
@@ -0,0 +1,575 @@
+//! The `#[app]` attribute: the application is declared as a module
+//!
+//! ``` text
+//! #[app(device = stm32f103xx)]
+//! mod app {
+//!     static mut ON: bool = false;
+//!
+//!     #[init]
+//!     fn init(p: init::Peripherals, r: init::Resources) { .. }
+//!
+//!     #[task(binds = SYS_TICK, resources = [ON])]
+//!     fn sys_tick(t: &mut Threshold, r: SYS_TICK::Resources) { .. }
+//! }
+//! ```
+//!
+//! The module is lowered to the equivalent `app!` input, which goes through
+//! the same pipeline as the macro, and its items are emitted in place of the
+//! module. Unlike `app!` the handlers are at hand, so their signatures are
+//! checked against the arguments the generated code passes to them.
+use quote::Tokens;
+use syn::{self, DelimToken, Delimited, FnArg, FunctionRetTy, Ident, ItemKind, Token, TokenTree,
+          Ty};
+
+use analysis::check::{App, Kind};
+use analysis::Diagnostics;
+
+use syntax::error::*;
+
+/// An `#[app]` module
+pub struct Module {
+    /// The input of the `app!` macro equivalent to this module
+    pub app: String,
+    /// The items of the module minus its resources and the RTFM attributes
+    pub items: Vec<TokenTree>,
+    name: Ident,
+    handlers: Vec<Handler>,
+}
+
+/// A function that has an `#[init]`, `#[idle]` or `#[task]` attribute
+struct Handler {
+    context: Context,
+    name: Ident,
+    inputs: Vec<Ty>,
+    output: Ty,
+}
+
+enum Context {
+    Init,
+    Idle,
+    Task(Ident),
+}
+
+impl Module {
+    /// Maps a location of the `app!` input to the item of this module that
+    /// declared it
+    pub fn locate(&self, at: &[String]) -> Vec<String> {
+        let item = match (at.get(0).map(|s| &s[..]), at.get(1)) {
+            (Some("resources"), Some(name)) => Some(name.clone()),
+            (Some("tasks"), Some(name)) => self.handler(|context| match *context {
+                Context::Task(ref task) => task == name,
+                _ => false,
+            }),
+            (Some("init"), _) => self.handler(|context| match *context {
+                Context::Init => true,
+                _ => false,
+            }),
+            (Some("idle"), _) => self.handler(|context| match *context {
+                Context::Idle => true,
+                _ => false,
+            }),
+            _ => None,
+        };
+
+        match item {
+            Some(item) => vec![self.name.as_ref().to_owned(), item],
+            None => vec![],
+        }
+    }
+
+    fn handler<F>(&self, f: F) -> Option<String>
+    where
+        F: Fn(&Context) -> bool,
+    {
+        self.handlers
+            .iter()
+            .find(|h| f(&h.context))
+            .map(|h| h.name.as_ref().to_owned())
+    }
+}
+
+/// Parses the arguments and the item of an `#[app]` attribute
+pub fn parse(args: &str, input: &str) -> Result<Module> {
+    let args = syn::parse_token_trees(args)?;
+    let tts = syn::parse_token_trees(input)?;
+
+    // the arguments may include the parentheses of the attribute
+    let args = match args.get(0) {
+        Some(&TokenTree::Delimited(Delimited {
+            delim: DelimToken::Paren,
+            ref tts,
+        })) if args.len() == 1 =>
+        {
+            &tts[..]
+        }
+        _ => &args[..],
+    };
+
+    let (name, body) = match tts.iter()
+        .position(|tt| *tt == TokenTree::Token(Token::Ident(Ident::new("mod"))))
+        .map(|i| (tts.get(i + 1), tts.get(i + 2)))
+    {
+        Some((
+            Some(&TokenTree::Token(Token::Ident(ref name))),
+            Some(&TokenTree::Delimited(Delimited {
+                delim: DelimToken::Brace,
+                ref tts,
+            })),
+        )) => (name.clone(), tts),
+        _ => bail!("`#[app]` must be applied to a module with a body, `mod $NAME { .. }`"),
+    };
+
+    let mut app = String::new();
+    for (key, value) in ::attr::arguments(args)? {
+        ensure!(
+            key == "device" || key == "free_interrupts" || key == "instrument"
+                || key == "nvic_prio_bits" || key == "overheads",
+            "unknown argument `{}`; the only arguments of `#[app]` are `device`, \
+             `free_interrupts`, `instrument`, `nvic_prio_bits` and `overheads`",
+            key
+        );
+
+        app.push_str(&format!("{}: {},", key, value));
+    }
+
+    let mut resources = String::new();
+    let mut init = None;
+    let mut idle = None;
+    let mut tasks = String::new();
+    let mut handlers = vec![];
+    let mut items = vec![];
+
+    for item in ::attr::items(body) {
+        let (attribute, item) = ::attr::attribute(item)?;
+
+        // skip the outer attributes and doc comments
+        let mut start = 0;
+        loop {
+            match (item.get(start), item.get(start + 1)) {
+                (Some(&TokenTree::Token(Token::DocComment(_))), _) => start += 1,
+                (
+                    Some(&TokenTree::Token(Token::Pound)),
+                    Some(&TokenTree::Delimited(Delimited {
+                        delim: DelimToken::Bracket,
+                        ..
+                    })),
+                ) => start += 2,
+                _ => break,
+            }
+        }
+
+        if item.get(start) == Some(&TokenTree::Token(Token::Ident(Ident::new("static"))))
+            && item.get(start + 1) == Some(&TokenTree::Token(Token::Ident(Ident::new("mut"))))
+        {
+            ensure!(
+                attribute.is_none(),
+                "`static mut` items are resources; they can't have RTFM attributes"
+            );
+
+            resources.push_str(&::attr::resource(&item[start + 2..])?);
+            continue;
+        }
+
+        let (kind, args) = match attribute {
+            Some(attribute) => attribute,
+            None => {
+                items.extend(item);
+                continue;
+            }
+        };
+
+        let (name, inputs, output) = ::attr::signature(&item)?;
+
+        let mut binds = None;
+        let mut fields = format!("path: {},", name);
+        for (key, value) in ::attr::arguments(&args)? {
+            ensure!(
+                key != "path",
+                "`{}` is the handler of its context; the `path` argument is not allowed",
+                name
+            );
+
+            if key == "binds" && kind == "task" {
+                binds = Some(Ident::new(value));
+            } else {
+                fields.push_str(&format!("{}: {},", key, value));
+            }
+        }
+
+        let context = match &kind[..] {
+            "init" => {
+                ensure!(init.is_none(), "`#[init]` can only be used once");
+                init = Some(fields);
+                Context::Init
+            }
+            "idle" => {
+                ensure!(idle.is_none(), "`#[idle]` can only be used once");
+                idle = Some(fields);
+                Context::Idle
+            }
+            _ => {
+                // tasks that don't bind an interrupt are software tasks
+                // named after their handler
+                let task = binds.unwrap_or_else(|| name.clone());
+                tasks.push_str(&format!("{}: {{ {} }},", task, fields));
+                Context::Task(task)
+            }
+        };
+
+        handlers.push(Handler {
+            context,
+            name,
+            inputs,
+            output,
+        });
+        items.extend(item);
+    }
+
+    app.push_str(&format!("resources: {{ {} }},", resources));
+    if let Some(init) = init {
+        app.push_str(&format!("init: {{ {} }},", init));
+    }
+    if let Some(idle) = idle {
+        app.push_str(&format!("idle: {{ {} }},", idle));
+    }
+    app.push_str(&format!("tasks: {{ {} }},", tasks));
+
+    Ok(Module {
+        app,
+        items,
+        name,
+        handlers,
+    })
+}
+
+/// Checks that the signatures of the handlers match the arguments the
+/// generated code passes to them
+pub fn signatures(app: &App, module: &Module, diag: &mut Diagnostics) {
+    for handler in &module.handlers {
+        let (context, inputs, output) = match handler.context {
+            Context::Init => {
+                let mut inputs = vec!["init::Peripherals".to_owned()];
+                if app.resources.values().any(|res| res.expr.is_some()) {
+                    inputs.push("init::Resources".to_owned());
+                }
+                if !app.init.spawn.is_empty() {
+                    inputs.push("init::Spawn".to_owned());
+                }
+                if !app.init.schedule.is_empty() {
+                    inputs.push("init::Schedule".to_owned());
+                }
+
+                let output = if app.resources.values().any(|res| res.expr.is_none()) {
+                    "init::LateResources"
+                } else {
+                    "()"
+                };
+
+                ("`init`".to_owned(), inputs, output.to_owned())
+            }
+            Context::Idle => {
+                let idle = &app.idle;
+
+                let mut inputs = vec![];
+                if !idle.resources.is_empty() || !idle.spawn.is_empty()
+                    || !idle.schedule.is_empty()
+                {
+                    inputs.push("&mut Threshold".to_owned());
+                }
+                if !idle.resources.is_empty() {
+                    inputs.push("idle::Resources".to_owned());
+                }
+                if !idle.spawn.is_empty() {
+                    inputs.push("idle::Spawn".to_owned());
+                }
+                if !idle.schedule.is_empty() {
+                    inputs.push("idle::Schedule".to_owned());
+                }
+
+                ("`idle`".to_owned(), inputs, "!".to_owned())
+            }
+            Context::Task(ref name) => {
+                let task = match app.tasks.get(name) {
+                    Some(task) => task,
+                    None => continue,
+                };
+
+                let mut inputs = vec![];
+                if !task.resources.is_empty() || !task.spawn.is_empty()
+                    || !task.schedule.is_empty()
+                {
+                    inputs.push("&mut Threshold".to_owned());
+                }
+                if !task.resources.is_empty() || !task.local.is_empty() {
+                    inputs.push(format!("{}::Resources", name));
+                }
+                if !task.spawn.is_empty() {
+                    inputs.push(format!("{}::Spawn", name));
+                }
+                if !task.schedule.is_empty() {
+                    inputs.push(format!("{}::Schedule", name));
+                }
+                if let Kind::Software {
+                    input: Some(ref ty),
+                    ..
+                } = task.kind
+                {
+                    inputs.push(quote!(#ty).as_str().to_owned());
+                }
+
+                (format!("task `{}`", name), inputs, "()".to_owned())
+            }
+        };
+
+        let expected = inputs
+            .iter()
+            .map(|ty| syn::parse_type(ty).expect("BUG: error parsing an argument type"))
+            .collect::<Vec<_>>();
+        let ret = syn::parse_type(&output).expect("BUG: error parsing a return type");
+
+        let matches = handler.inputs.len() == expected.len()
+            && handler
+                .inputs
+                .iter()
+                .zip(&expected)
+                .all(|(ty, expected)| ::attr::matches(ty, expected))
+            && ::attr::matches(&handler.output, &ret);
+
+        if !matches {
+            diag.error(
+                &[module.name.as_ref(), handler.name.as_ref()],
+                format!(
+                    "the handler of {} must have signature `fn({}){}`",
+                    context,
+                    inputs.join(", "),
+                    if output == "()" {
+                        String::new()
+                    } else {
+                        format!(" -> {}", output)
+                    }
+                ),
+            );
+        }
+    }
+}
+
+/// Whether the type `ty` written by the user is the `expected` type
+///
+/// Paths only need to end with the segments of the expected path so that
+/// both `Threshold` and `rtfm::Threshold` are accepted.
+fn matches(ty: &Ty, expected: &Ty) -> bool {
+    match (ty, expected) {
+        (&Ty::Paren(ref ty), _) => ::attr::matches(ty, expected),
+        (&Ty::Rptr(_, ref ty), &Ty::Rptr(_, ref expected)) => {
+            ty.mutability == expected.mutability && ::attr::matches(&ty.ty, &expected.ty)
+        }
+        (&Ty::Path(None, ref path), &Ty::Path(None, ref expected)) => {
+            path.segments.len() >= expected.segments.len()
+                && path.segments[path.segments.len() - expected.segments.len()..]
+                    == expected.segments[..]
+        }
+        _ => ty == expected,
+    }
+}
+
+/// Splits the body of a module into items
+fn items(tts: &[TokenTree]) -> Vec<&[TokenTree]> {
+    let mut items = vec![];
+
+    let mut start = 0;
+    // whether the item has an initializer, like `static` and `const` items,
+    // in which case it ends at a semicolon
+    let mut init = false;
+    for (i, tt) in tts.iter().enumerate() {
+        let end = match *tt {
+            TokenTree::Token(Token::Semi) => true,
+            TokenTree::Token(Token::Eq) => {
+                init = true;
+                false
+            }
+            TokenTree::Delimited(Delimited {
+                delim: DelimToken::Brace,
+                ..
+            }) => !init,
+            _ => false,
+        };
+
+        if end {
+            items.push(&tts[start..i + 1]);
+            start = i + 1;
+            init = false;
+        }
+    }
+
+    if start < tts.len() {
+        items.push(&tts[start..]);
+    }
+
+    items
+}
+
+/// Removes the RTFM attribute, if any, from `item`
+///
+/// Returns the name of the attribute and its arguments, and the rest of the
+/// item.
+fn attribute(item: &[TokenTree]) -> Result<(Option<(String, Vec<TokenTree>)>, Vec<TokenTree>)> {
+    let mut attribute = None;
+    let mut rest = vec![];
+
+    let mut i = 0;
+    while i < item.len() {
+        if item[i] == TokenTree::Token(Token::Pound) {
+            if let Some(&TokenTree::Delimited(Delimited {
+                delim: DelimToken::Bracket,
+                ref tts,
+            })) = item.get(i + 1)
+            {
+                let rtfm = match (tts.get(0), tts.get(1), tts.len()) {
+                    (Some(&TokenTree::Token(Token::Ident(ref name))), None, 1)
+                    | (
+                        Some(&TokenTree::Token(Token::Ident(ref name))),
+                        Some(&TokenTree::Delimited(Delimited {
+                            delim: DelimToken::Paren,
+                            ..
+                        })),
+                        2,
+                    ) if name == "init" || name == "idle" || name == "task" =>
+                    {
+                        Some(name.as_ref().to_owned())
+                    }
+                    _ => None,
+                };
+
+                if let Some(name) = rtfm {
+                    ensure!(
+                        attribute.is_none(),
+                        "an item can have at most one of the `#[init]`, `#[idle]` and \
+                         `#[task]` attributes"
+                    );
+
+                    let args = match tts.get(1) {
+                        Some(&TokenTree::Delimited(Delimited { ref tts, .. })) => tts.clone(),
+                        _ => vec![],
+                    };
+
+                    attribute = Some((name, args));
+                    i += 2;
+                    continue;
+                }
+
+                rest.extend(item[i..i + 2].iter().cloned());
+                i += 2;
+                continue;
+            }
+        }
+
+        rest.push(item[i].clone());
+        i += 1;
+    }
+
+    Ok((attribute, rest))
+}
+
+/// Parses the `key = value, ..` arguments of an attribute
+fn arguments(tts: &[TokenTree]) -> Result<Vec<(String, String)>> {
+    let mut args = vec![];
+
+    for arg in tts.split(|tt| *tt == TokenTree::Token(Token::Comma)) {
+        if arg.is_empty() {
+            continue;
+        }
+
+        match (arg.get(0), arg.get(1)) {
+            (Some(&TokenTree::Token(Token::Ident(ref key))), Some(&TokenTree::Token(Token::Eq)))
+                if arg.len() > 2 =>
+            {
+                args.push((key.as_ref().to_owned(), ::attr::string(&arg[2..])));
+            }
+            _ => bail!("expected `$KEY = $VALUE`, found `{}`", ::attr::string(arg)),
+        }
+    }
+
+    Ok(args)
+}
+
+/// Converts the `$NAME: $TY = $EXPR;` rest of a `static mut` item into a
+/// resource; an initial value of `()` declares a late resource
+fn resource(tts: &[TokenTree]) -> Result<String> {
+    let mut parts = tts.splitn(2, |tt| *tt == TokenTree::Token(Token::Eq));
+    let decl = parts.next().unwrap_or(&[]);
+    let expr = match parts.next() {
+        Some(expr) => expr,
+        None => bail!("`static mut {}` has no initial value", ::attr::string(decl)),
+    };
+    let expr = match expr.last() {
+        Some(&TokenTree::Token(Token::Semi)) => &expr[..expr.len() - 1],
+        _ => expr,
+    };
+
+    let late = match expr.get(0) {
+        Some(&TokenTree::Delimited(Delimited {
+            delim: DelimToken::Paren,
+            ref tts,
+        })) => expr.len() == 1 && tts.is_empty(),
+        _ => false,
+    };
+
+    Ok(if late {
+        format!("static {};", ::attr::string(decl))
+    } else {
+        format!(
+            "static {} = {};",
+            ::attr::string(decl),
+            ::attr::string(expr)
+        )
+    })
+}
+
+/// Parses the signature of the function `item` into its name, the types of
+/// its arguments and its return type
+fn signature(item: &[TokenTree]) -> Result<(Ident, Vec<Ty>, Ty)> {
+    // the body is not parsed; only the signature is needed
+    let sig = match item.last() {
+        Some(&TokenTree::Delimited(Delimited {
+            delim: DelimToken::Brace,
+            ..
+        })) => &item[..item.len() - 1],
+        _ => bail!(
+            "RTFM attributes can only be applied to functions, found `{}`",
+            ::attr::string(item)
+        ),
+    };
+
+    let item = syn::parse_item(&format!("{} {{}}", ::attr::string(sig)))
+        .map_err(Error::from)
+        .chain_err(|| "parsing the signature of a handler")?;
+
+    let decl = match item.node {
+        ItemKind::Fn(decl, ..) => decl,
+        _ => bail!(
+            "RTFM attributes can only be applied to functions, found `{}`",
+            item.ident
+        ),
+    };
+
+    let mut inputs = vec![];
+    for arg in decl.inputs {
+        match arg {
+            FnArg::Captured(_, ty) | FnArg::Ignored(ty) => inputs.push(ty),
+            _ => bail!("handler `{}` can't take `self`", item.ident),
+        }
+    }
+
+    let output = match decl.output {
+        FunctionRetTy::Default => Ty::Tup(vec![]),
+        FunctionRetTy::Ty(ty) => ty,
+    };
+
+    Ok((item.ident, inputs, output))
+}
+
+fn string(tts: &[TokenTree]) -> String {
+    let mut tokens = Tokens::new();
+    tokens.append_all(tts);
+    tokens.as_str().to_owned()
+}
@@ -0,0 +1,74 @@
+//! Compile errors attached to the tokens of the `app!` input that caused them
+//!
+//! Each error of the `Diagnostics` is emitted as a `compile_error!` invocation
+//! whose tokens carry the span of the offending token, so the compiler reports
+//! it there.
+use analysis::Diagnostics;
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Returns the errors as `compile_error!`s located at the tokens of `input`
+pub fn emit(diagnostics: &Diagnostics, input: TokenStream) -> TokenStream {
+    diagnostics
+        .errors()
+        .iter()
+        .map(|&(ref at, ref message)| {
+            ::diag::compile_error(::diag::span(input.clone(), at), message)
+        })
+        .collect()
+}
+
+/// `compile_error!("$message")` with all its tokens at `span`
+fn compile_error(span: Span, message: &str) -> TokenStream {
+    let mut message = Literal::string(message);
+    message.set_span(span);
+
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(span);
+
+    let mut args = Group::new(Delimiter::Brace, TokenTree::from(message).into());
+    args.set_span(span);
+
+    vec![
+        TokenTree::from(Ident::new("compile_error", span)),
+        TokenTree::from(bang),
+        TokenTree::from(args),
+    ].into_iter()
+        .collect()
+}
+
+/// Returns the span of the token at `at`
+///
+/// Each element of the path is searched among the tokens of the current level;
+/// the search then continues inside the first delimited group (the value of
+/// the key) that follows it. If an element can't be found the span of the
+/// closest match is returned.
+fn span(input: TokenStream, at: &[String]) -> Span {
+    let mut span = Span::call_site();
+    let mut stream = input;
+
+    for (i, name) in at.iter().enumerate() {
+        let mut tts = stream.into_iter();
+
+        match tts.by_ref().find(|tt| match *tt {
+            TokenTree::Ident(ref ident) => ident.to_string() == *name,
+            _ => false,
+        }) {
+            Some(tt) => span = tt.span(),
+            None => break,
+        }
+
+        if i + 1 == at.len() {
+            break;
+        }
+
+        match tts.find(|tt| match *tt {
+            TokenTree::Group(..) => true,
+            _ => false,
+        }) {
+            Some(TokenTree::Group(group)) => stream = group.stream(),
+            _ => break,
+        }
+    }
+
+    span
+}
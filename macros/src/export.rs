@@ -0,0 +1,275 @@
+//! Machine readable description of the application
+//!
+//! The description is a JSON document with the following shape:
+//!
+//! ``` text
+//! {
+//!   "version": 2,
+//!   "device": "stm32f413",
+//!   "init": { "path": "init", "resources": [..], "stack": null },
+//!   "idle": {
+//!     "path": "idle", "resources": [..], "read_only": [..], "critical_sections": { .. },
+//!     "stack": null
+//!   },
+//!   "tasks": [
+//!     {
+//!       "name": "EXTI1",
+//!       "kind": "interrupt",
+//!       "symbol": "EXTI1",
+//!       "path": "exti1",
+//!       "priority": 1,
+//!       "threshold": 1,
+//!       "interarrival": 100,
+//!       "period": null,
+//!       "offset": 0,
+//!       "deadline": 100,
+//!       "wcet": 37,
+//!       "stack": 64,
+//!       "enabled": true,
+//!       "resources": ["X", "Y"],
+//!       "read_only": ["Y"],
+//!       "critical_sections": { "X": 14, "Y": 9 }
+//!     }
+//!   ],
+//!   "dispatchers": [{ "priority": 2, "symbol": "EXTI2" }],
+//!   "timer_queue": { "priority": 3, "symbol": "SysTick" },
+//!   "resources": [
+//!     {
+//!       "name": "X", "type": "u32", "late": false, "init": false, "ceiling": 2,
+//!       "read_ceiling": 1
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Tasks and resources are sorted by name; this is also the order in which
+//! tasks are numbered in `klee_mode`.
+//! `wcet`, `stack` and `period` are `null` when they are not declared (only
+//! periodic tasks have a `period`); `ceiling` and `read_ceiling` are `null` for
+//! resources that are only accessed by `init`. `read_only` lists the resources
+//! that a context only reads; the `read_ceiling` of a resource is the ceiling
+//! its readers claim it with. The `threshold` of a task is its preemption
+//! threshold, equal to its `priority` unless declared.
+//!
+//! `symbol` is the handler a task runs from: the exception handler, the
+//! interrupt handler or, for software tasks, the handler of the interrupt that
+//! dispatches them. `dispatchers` lists those interrupts, one per priority
+//! level of the software tasks. `timer_queue` is the `SysTick` handler that
+//! drives `schedule` and the periodic tasks; `null` if there's none.
+use std::env;
+use std::fmt::Write as FmtWrite;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use syntax::{CriticalSections, Resources};
+use syntax::error::*;
+
+use analysis::analyze::{self, Dispatchers, Ownerships};
+use analysis::check::{App, Exception, Kind};
+
+/// Version of the format; bump it on every backwards incompatible change
+const VERSION: u32 = 2;
+
+/// Environment variable that overrides the location of the description
+const PATH_VAR: &str = "RTFM_APP_MODEL";
+
+/// Default location of the description, relative to the crate being compiled
+const DEFAULT_PATH: &str = "target/rtfm/app.json";
+
+pub fn app(app: &App, ownerships: &Ownerships, dispatchers: &Dispatchers) -> Result<()> {
+    let path = env::var_os(PATH_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_PATH));
+
+    write(&path, &json(app, ownerships, dispatchers))
+        .chain_err(|| format!("writing the application description to {}", path.display()))
+}
+
+fn write(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).chain_err(|| "creating the parent directory")?;
+    }
+
+    let mut file = File::create(path).chain_err(|| "creating the file")?;
+    file.write_all(contents.as_bytes())
+        .chain_err(|| "writing the file")?;
+
+    Ok(())
+}
+
+fn json(app: &App, ownerships: &Ownerships, dispatchers: &Dispatchers) -> String {
+    let mut names = app.tasks.keys().collect::<Vec<_>>();
+    names.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+    let mut tasks = vec![];
+    for name in names {
+        let task = &app.tasks[name];
+        let (kind, symbol, enabled) = match task.kind {
+            Kind::Exception(ref e) => ("exception", e.symbol(), true),
+            Kind::Interrupt { enabled } => ("interrupt", name.as_ref(), enabled),
+            Kind::Software { .. } => (
+                "software",
+                dispatchers[&task.priority].interrupt.as_ref(),
+                true,
+            ),
+        };
+
+        let path = &task.path;
+        tasks.push(format!(
+            "{{ \"name\": {}, \"kind\": {}, \"symbol\": {}, \"path\": {}, \"priority\": {}, \
+             \"threshold\": {}, \"interarrival\": {}, \"period\": {}, \"offset\": {}, \
+             \"deadline\": {}, \"wcet\": {}, \"stack\": {}, \"enabled\": {}, \"resources\": {}, \
+             \"read_only\": {}, \"critical_sections\": {} }}",
+            string(name.as_ref()),
+            string(kind),
+            string(symbol),
+            string(&quote!(#path).to_string()),
+            task.priority,
+            task.threshold,
+            task.interarrival,
+            optional(task.period),
+            task.offset,
+            task.deadline,
+            optional(task.wcet),
+            optional(task.stack),
+            enabled,
+            list(&task.resources),
+            list(&task.read_only),
+            lengths(&task.critical_sections),
+        ));
+    }
+
+    let mut names = app.resources.keys().collect::<Vec<_>>();
+    names.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+    let mut resources = vec![];
+    for name in names {
+        let resource = &app.resources[name];
+        let ty = &resource.ty;
+        let ceiling = ownerships
+            .get(name)
+            .map(|o| o.ceiling().to_string())
+            .unwrap_or_else(|| "null".to_owned());
+        let read_ceiling = ownerships
+            .get(name)
+            .map(|o| o.read_ceiling().to_string())
+            .unwrap_or_else(|| "null".to_owned());
+
+        resources.push(format!(
+            "{{ \"name\": {}, \"type\": {}, \"late\": {}, \"init\": {}, \"ceiling\": {}, \
+             \"read_ceiling\": {} }}",
+            string(name.as_ref()),
+            string(&quote!(#ty).to_string()),
+            resource.expr.is_none(),
+            app.init.resources.contains(name),
+            ceiling,
+            read_ceiling,
+        ));
+    }
+
+    let dispatchers = dispatchers
+        .iter()
+        .map(|(priority, dispatcher)| handler(*priority, dispatcher.interrupt.as_ref()))
+        .collect::<Vec<_>>();
+
+    let timer_queue = analyze::timer_queue(app)
+        .map(|tq| handler(tq.priority, Exception::SYS_TICK.symbol()))
+        .unwrap_or_else(|| "null".to_owned());
+
+    let device = &app.device;
+    let init = &app.init.path;
+    let idle = &app.idle.path;
+
+    let mut out = String::new();
+    writeln!(out, "{{").unwrap();
+    writeln!(out, "  \"version\": {},", VERSION).unwrap();
+    writeln!(out, "  \"device\": {},", string(&quote!(#device).to_string())).unwrap();
+    writeln!(
+        out,
+        "  \"init\": {{ \"path\": {}, \"resources\": {}, \"stack\": {} }},",
+        string(&quote!(#init).to_string()),
+        list(&app.init.resources),
+        optional(app.init.stack),
+    ).unwrap();
+    writeln!(
+        out,
+        "  \"idle\": {{ \"path\": {}, \"resources\": {}, \"read_only\": {}, \
+         \"critical_sections\": {}, \"stack\": {} }},",
+        string(&quote!(#idle).to_string()),
+        list(&app.idle.resources),
+        list(&app.idle.read_only),
+        lengths(&app.idle.critical_sections),
+        optional(app.idle.stack),
+    ).unwrap();
+    writeln!(out, "  \"tasks\": [{}],", array(&tasks)).unwrap();
+    writeln!(out, "  \"dispatchers\": [{}],", dispatchers.join(", ")).unwrap();
+    writeln!(out, "  \"timer_queue\": {},", timer_queue).unwrap();
+    writeln!(out, "  \"resources\": [{}]", array(&resources)).unwrap();
+    writeln!(out, "}}").unwrap();
+
+    out
+}
+
+/// Formats an interrupt or exception handler of the runtime
+fn handler(priority: u8, symbol: &str) -> String {
+    format!("{{ \"priority\": {}, \"symbol\": {} }}", priority, string(symbol))
+}
+
+/// Formats the items of a JSON array, one per line
+fn array(items: &[String]) -> String {
+    if items.is_empty() {
+        String::new()
+    } else {
+        format!("\n    {}\n  ", items.join(",\n    "))
+    }
+}
+
+/// Formats a set of resources as a sorted JSON array of strings
+fn list(resources: &Resources) -> String {
+    let mut names = resources.iter().map(|r| r.as_ref()).collect::<Vec<_>>();
+    names.sort();
+
+    let names = names.into_iter().map(string).collect::<Vec<_>>();
+    format!("[{}]", names.join(", "))
+}
+
+/// Formats critical section lengths as a JSON object sorted by resource name
+fn lengths(sections: &CriticalSections) -> String {
+    let mut names = sections.keys().collect::<Vec<_>>();
+    names.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+    let fields = names
+        .into_iter()
+        .map(|name| format!("{}: {}", string(name.as_ref()), sections[name]))
+        .collect::<Vec<_>>();
+
+    if fields.is_empty() {
+        "{}".to_owned()
+    } else {
+        format!("{{ {} }}", fields.join(", "))
+    }
+}
+
+/// Formats an optional number; `None` becomes `null`
+fn optional(n: Option<u32>) -> String {
+    n.map(|n| n.to_string())
+        .unwrap_or_else(|| "null".to_owned())
+}
+
+/// Formats `s` as a JSON string literal
+fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -1,26 +1,30 @@
 //! Procedural macros of the `cortex-m-rtfm` crate
 //#![deny(warnings)]
-#![feature(proc_macro)]
-#![recursion_limit = "128"]
+#![recursion_limit = "256"]
 
 #[macro_use]
 extern crate error_chain;
 extern crate proc_macro;
 #[macro_use]
 extern crate quote;
+extern crate rtfm_analysis as analysis;
 extern crate rtfm_syntax as syntax;
 extern crate syn;
 
+use std::env;
 use std::fs::File;
-use std::io::Write;
-use std::path::Path;
+use std::io::Read;
 
+use analysis::analyze::Dispatchers;
+use analysis::blocking::{self, CriticalSections};
+use analysis::{App, Diagnostics, Ownerships};
 use proc_macro::TokenStream;
-use syntax::App;
 use syntax::error::*;
 
-mod analyze;
-mod check;
+mod attr;
+mod diag;
+mod export;
+mod section;
 mod trans;
 
 /// The `app!` macro, a macro used to specify the tasks and resources of a RTFM application.
@@ -31,6 +35,14 @@ mod trans;
 /// app! {
 ///     device: ..,
 ///
+///     free_interrupts: [..],
+///
+///     instrument: ..,
+///
+///     nvic_prio_bits: ..,
+///
+///     overheads: { .. },
+///
 ///     resources: { .. },
 ///
 ///     init: { .. },
@@ -46,6 +58,60 @@ mod trans;
 /// The value of this key is a Rust path, like `foo::bar::baz`, that must point to a *device crate*,
 /// a crate generated using `svd2rust`.
 ///
+/// # `free_interrupts`
+///
+/// This key is optional. Its value is a list of device specific interrupts that the application
+/// doesn't use. These interrupts are used to run the *software tasks* (see `tasks.$TASK.input`):
+/// each priority level used by software tasks is assigned one of these interrupts, in order,
+/// starting from the lowest priority. The list must contain at least as many interrupts as there
+/// are such priority levels.
+///
+/// If this key is omitted its value defaults to an empty list.
+///
+/// # `instrument`
+///
+/// This key is optional. Its value is a path to a type that implements `rtfm::Instrument`; the
+/// generated code reports the start and the end of each task, and the critical sections of the
+/// claims, to it. `rtfm::instrument` has implementations that place breakpoints or `nop`s around
+/// the critical sections, and one that records the events in host tests; with the `trace` feature
+/// `rtfm::trace::Trace` logs them into a ring buffer on the target.
+///
+/// If this key is omitted the events are discarded, unless the `wcet_bkpt` or `wcet_nop` feature
+/// is enabled, in which case it defaults to `rtfm::instrument::Breakpoints` or
+/// `rtfm::instrument::Nops`, respectively.
+///
+/// # `nvic_prio_bits`
+///
+/// This key is optional. Its value is the number of priority bits of the device, its
+/// `NVIC_PRIO_BITS`; the compilation fails if they don't match. It's required by
+/// `tasks.$TASK.priority: auto`, which uses at most `1 << nvic_prio_bits` priority levels.
+///
+/// # `overheads`
+///
+/// This key is optional. Its value is a map from runtime operations to their execution time, in
+/// clock cycles, and it's used by the schedulability analysis (see `tasks.$TASK.wcet`):
+///
+/// ``` text
+/// overheads: {
+///     dispatch: 30,
+///     queue: 12,
+///     release: 40,
+/// },
+/// ```
+///
+/// - `dispatch`, the execution time of a dispatcher per software task it runs. It's added to the
+/// `wcet` of each software task. Required for the analysis of applications with software tasks.
+///
+/// - `queue`, the length of the longest critical section the runtime executes on the message
+/// queues of the software tasks or on the timer queue. These critical sections block the tasks
+/// whose priority is not greater than the ceiling of the queue, like the critical sections on
+/// resources do. If omitted they are bounded by the WCET of the task that executes them, if known.
+///
+/// - `release`, the execution time of the `SYS_TICK` handler per task it moves from the timer queue
+/// into a ready queue. Each release interferes with the tasks whose priority is not greater than
+/// the priority of the handler. Required for the analysis of applications that use `schedule` or
+/// periodic tasks.
+///
 /// # `resources`
 ///
 /// This key is optional. Its value is a list of `static` variables. These variables are the data
@@ -73,6 +139,10 @@ mod trans;
 /// ``` text
 /// init: {
 ///     path: ..,
+///     resources: [..],
+///     schedule: [..],
+///     spawn: [..],
+///     stack: ..,
 /// }
 /// ```
 ///
@@ -95,17 +165,45 @@ mod trans;
 ///
 /// If this key is omitted its value is assumed to be an empty list.
 ///
+/// ## `init.schedule`
+///
+/// This key is optional. Its value is a list of software tasks `init` can schedule. See
+/// `tasks.$TASK.schedule` for details; like `init::Spawn` the methods of `init::Schedule` don't
+/// take a `Threshold` argument.
+///
+/// ## `init.spawn`
+///
+/// This key is optional. Its value is a list of software tasks `init` can spawn. See
+/// `tasks.$TASK.spawn` for details; note that the methods of `init::Spawn` don't take a
+/// `Threshold` argument. The spawned tasks start running after `init` returns.
+///
+/// ## `init.stack`
+///
+/// This key is optional. Its value is an integer with type `u32` that specifies the stack usage of
+/// the `init` function in bytes. See `tasks.$TASK.stack` for details.
+///
 /// # `idle`
 ///
 /// This key is optional. Its value is a set of key values. All the possible keys are shown below:
 ///
 /// ``` text
 /// idle: {
+///     critical_sections: { .. },
 ///     path: ..,
 ///     resources: [..],
+///     schedule: [..],
+///     spawn: [..],
+///     stack: ..,
+///     units: { .. },
 /// }
 /// ```
 ///
+/// ## `idle.critical_sections`
+///
+/// This key is optional. Its value is a set of `$RESOURCE: $CYCLES` pairs that specify the length,
+/// in clock cycles, of the longest critical section (`claim`) the `idle` loop performs on each of
+/// its resources. See `tasks.$TASK.critical_sections` for details.
+///
 /// ## `idle.path`
 ///
 /// This key is optional. Its value is a Rust path, like `foo::bar::baz`, that points to the idle
@@ -117,9 +215,31 @@ mod trans;
 ///
 /// This key is optional. Its value is a list of resources the `idle` loop has access to. The
 /// resources in this list must be a subset of the resources listed in the top `resources` key.
+/// Resources prefixed with `&` are only read by `idle`; see `tasks.$TASK.resources`.
 ///
 /// If omitted its value defaults to an empty list.
 ///
+/// ## `idle.schedule`
+///
+/// This key is optional. Its value is a list of software tasks the `idle` loop can schedule. See
+/// `tasks.$TASK.schedule` for details.
+///
+/// ## `idle.spawn`
+///
+/// This key is optional. Its value is a list of software tasks the `idle` loop can spawn. See
+/// `tasks.$TASK.spawn` for details.
+///
+/// ## `idle.stack`
+///
+/// This key is optional. Its value is an integer with type `u32` that specifies the stack usage of
+/// the `idle` loop in bytes. See `tasks.$TASK.stack` for details.
+///
+/// ## `idle.units`
+///
+/// This key is optional. Its value is a set of `$RESOURCE: $UNITS` pairs that specify how many
+/// units of each multi-unit resource the `idle` loop holds at once. See `tasks.$TASK.units` for
+/// details.
+///
 /// # `tasks`
 ///
 /// This key is optional. Its value is a list of tasks. Each task itself is a set of key value pair.
@@ -128,10 +248,24 @@ mod trans;
 /// ``` text
 /// tasks: {
 ///     $TASK: {
+///         capacity: ..,
+///         critical_sections: { .. },
+///         deadline: ..,
 ///         enabled: ..,
+///         input: ..,
+///         interarrival: ..,
+///         local: { .. },
+///         offset: ..,
 ///         path: ..,
+///         period: ..,
 ///         priority: ..,
 ///         resources: [..],
+///         schedule: [..],
+///         spawn: [..],
+///         stack: ..,
+///         threshold: ..,
+///         units: { .. },
+///         wcet: ..,
 ///     },
 /// }
 /// ```
@@ -140,9 +274,68 @@ mod trans;
 ///
 /// ## `tasks.$TASK`
 ///
-/// The key must be either a Cortex-M exception or a device specific interrupt. `PENDSV`, `SVCALL`,
+/// The key must be either a Cortex-M exception or a device specific interrupt. `NMI`,
+/// `HARD_FAULT`, `MEM_MANAGE`, `BUS_FAULT`, `USAGE_FAULT`, `SVCALL`, `DEBUG_MONITOR`, `PENDSV` and
 /// `SYS_TICK` are considered as exceptions. All other names are assumed to be interrupts.
 ///
+/// The handlers of the exceptions are exported under the names `cortex-m-rt` 0.6 places in the
+/// vector table: `NonMaskableInt`, `UserHardFault`, `MemoryManagement`, `BusFault`, `UsageFault`,
+/// `SVCall`, `DebugMonitor`, `PendSV` and `SysTick`. The handlers of the interrupts are exported
+/// under the name of the interrupt.
+///
+/// The priorities of the configurable exceptions are programmed in `init`, which also enables the
+/// `MEM_MANAGE`, `BUS_FAULT` and `USAGE_FAULT` faults (otherwise they escalate to `HARD_FAULT`)
+/// and the `DEBUG_MONITOR` exception when they are bound to tasks. These exceptions are not
+/// available on ARMv6-M devices.
+///
+/// `NMI` and `HARD_FAULT` are *non-maskable*: they run above every other task, `NMI` above
+/// `HARD_FAULT`, and no critical section can mask them. Their tasks can't declare a `priority` or a
+/// `threshold` and can't access `resources` or `spawn` and `schedule` other tasks; they can use
+/// `local` variables. The `HARD_FAULT` task runs from `UserHardFault`, which `cortex-m-rt` calls
+/// with the stacked registers (`rtfm::ExceptionFrame`) and which must not return: once the task
+/// returns the handler spins forever.
+///
+/// Tasks that declare a `capacity`, an `input` or a `period` are *software tasks* instead: they are
+/// not bound to an interrupt and their name can be any identifier. Software tasks run when other
+/// contexts spawn or schedule them (see `tasks.$TASK.spawn`), or periodically (see
+/// `tasks.$TASK.period`), and are dispatched from one of the `free_interrupts`.
+///
+/// ## `tasks.$TASK.capacity`
+///
+/// This key is only valid for software tasks. Its value is an integer with type `u8` that specifies
+/// how many messages can be pending, i.e. spawned but not yet processed, for this task. The
+/// messages are stored in statically allocated queues.
+///
+/// If this key is omitted its value defaults to `1`.
+///
+/// ## `tasks.$TASK.critical_sections`
+///
+/// This key is optional. Its value is a set of `$RESOURCE: $CYCLES` pairs that specify the length,
+/// in clock cycles, of the longest critical section (`claim`) this task performs on each of its
+/// resources.
+///
+/// ``` text
+/// critical_sections: {
+///     X: 14,
+///     Y: 9,
+/// },
+/// ```
+///
+/// The lengths are used to compute the blocking time of higher priority tasks in the
+/// schedulability analysis (see `tasks.$TASK.wcet`). Lengths can also be imported from
+/// measurements: if the `RTFM_CRITICAL_SECTIONS` environment variable points to a file where each
+/// line has the form `$TASK $RESOURCE $CYCLES` those lengths are used for the critical sections
+/// that are not declared in the macro. Critical sections of unknown length are bounded by the
+/// `wcet` of the task that contains them.
+///
+/// ## `tasks.$TASK.deadline`
+///
+/// This key is optional. Its value is an integer with type `u32` that specifies the relative
+/// deadline of this task in clock cycles. The deadline can't be greater than the interarrival time
+/// of the task.
+///
+/// If this key is omitted its value defaults to the value of `interarrival`.
+///
 /// ## `tasks.$TASK.enabled`
 ///
 /// This key is optional for interrupts and forbidden for exceptions. Its value must be a boolean
@@ -151,11 +344,57 @@ mod trans;
 ///
 /// If this key is omitted its value defaults to `true`.
 ///
+/// ## `tasks.$TASK.input`
+///
+/// This key is only valid for software tasks. Its value is the type of the message this task
+/// receives each time it's spawned; the message is passed to the task handler as its last
+/// argument. Types that contain commas must be parenthesized, e.g. `(u8, u16)`.
+///
+/// If this key is omitted the task takes no message.
+///
+/// ## `tasks.$TASK.interarrival`
+///
+/// This key is optional, but it must be specified if either `wcet` or `deadline` is, unless the
+/// task is periodic. Its value is an integer with type `u32` that specifies the minimum time, in
+/// clock cycles, between two releases of this task.
+///
+/// If this key is omitted its value defaults to `1`. Periodic tasks can't use this key; their
+/// interarrival time is their `period`.
+///
+/// ## `tasks.$TASK.local`
+///
+/// This key is optional. Its value is a list of `static` variables, with the same syntax as the top
+/// `resources` key, whose values persist across invocations of this task. Each variable must have
+/// an initial value. The task accesses them through `&'static mut` fields of its `$TASK::Resources`
+/// argument, named after the variables, without a `Threshold`.
+///
+/// Unlike resources these variables can't be shared with other contexts, so they are not part of
+/// the ceiling analysis. Their names can't clash with the resources of the task.
+///
+/// ## `tasks.$TASK.offset`
+///
+/// This key is only valid for periodic tasks. Its value is an integer with type `u32` that
+/// specifies the time, in clock cycles, between the end of `init` and the first release of this
+/// task.
+///
+/// If this key is omitted its value defaults to `0`.
+///
 /// ## `tasks.$TASK.path`
 ///
 /// The value of this key is a Rust path, like `foo::bar::baz`, that points to the handler of this
 /// task.
 ///
+/// ## `tasks.$TASK.period`
+///
+/// This key is optional. Its value is an integer with type `u32` that makes this task a *periodic*
+/// software task released every `period` clock cycles, starting `offset` clock cycles after `init`
+/// returns. The releases are driven by the timer queue (see `tasks.$TASK.schedule`) and don't
+/// drift: each release is computed from the previous one, not from the time it was processed.
+///
+/// Periodic tasks take no `input` and can't be spawned or scheduled by other contexts. A release
+/// that finds all the `capacity` message slots of the task pending is dropped. The `period` is also
+/// the interarrival time of the task in the schedulability analysis.
+///
 /// ## `tasks.$TASK.priority`
 ///
 /// This key is optional. Its value is an integer with type `u8` that specifies the priority of this
@@ -163,14 +402,164 @@ mod trans;
 /// NVIC priority bits the device has; if the device has 4 priority bits the maximum allowed value
 /// would be 16.
 ///
-/// If this key is omitted its value defaults to `1`.
+/// If this key is omitted its value defaults to `1`. The key is not valid for the non-maskable
+/// exceptions, `NMI` and `HARD_FAULT`.
+///
+/// The value can also be `auto`, in which case the priority is computed by the `app!` macro. Either
+/// all the tasks or none of them must use `auto`, each task using it must specify its
+/// `interarrival` time or its `period`, and `nvic_prio_bits` must be declared. If every task
+/// declares its `wcet` the priorities are chosen, using a variant of Audsley's algorithm, such that
+/// the task set passes the schedulability analysis; a compile error is raised if the algorithm
+/// doesn't find such an assignment. Otherwise the priorities are assigned in deadline monotonic
+/// order: the shorter the `deadline` the higher the priority. Tasks share priority levels when the
+/// device has fewer levels than needed.
 ///
 /// ## `tasks.$TASK.resources`
 ///
 /// This key is optional. Its value is a list of resources this task has access to. The resources in
 /// this list must be a subset of the resources listed in the top `resources` key.
 ///
+/// A resource prefixed with `&`, as in `resources: [X, &Y]`, is only read by this task. The task
+/// gets a read-only proxy for it that implements `rtfm::ReadOnlyResource`, which has no
+/// `borrow_mut` or `claim_mut` methods, or a shared reference if no task that writes to the
+/// resource can preempt this one. Readers don't block each other: claiming a resource for reading
+/// only raises the preemption threshold to the highest priority among the tasks that write to it
+/// (its *read ceiling*), which also shortens the blocking time used in the schedulability
+/// analysis.
+///
 /// If omitted its value defaults to an empty list.
+///
+/// ## `tasks.$TASK.schedule`
+///
+/// This key is optional. Its value is a list of software tasks this task can schedule to run at a
+/// later time. The task handler receives a `$TASK::Schedule` argument, after `$TASK::Spawn`, with
+/// one method per task in this list:
+///
+/// ``` ignore
+/// fn exti0(t: &mut Threshold, mut s: EXTI0::Schedule) {
+///     // run `foo`, whose `input` is `u32`, with the message `42` 8_000 clock cycles from now
+///     if let Err(_message) = s.foo(t, Duration::from_cycles(8_000), 42) {
+///         // the queue of `foo` is full
+///     }
+/// }
+/// ```
+///
+/// Scheduled messages wait in a timer queue sorted by release time. Time is measured with the DWT
+/// cycle counter (not available on ARMv6-M devices) and the `SYS_TICK` exception is reserved to
+/// release the tasks from the queue: it can't be bound to a task if any context uses `schedule`.
+/// The `SYS_TICK` handler runs at the highest priority among the scheduled tasks. The timer queue
+/// is shared by the schedulers and the `SYS_TICK` handler; its ceiling is computed like those of
+/// resources.
+///
+/// If omitted its value defaults to an empty list.
+///
+/// ## `tasks.$TASK.spawn`
+///
+/// This key is optional. Its value is a list of software tasks this task can spawn. The task
+/// handler receives a `$TASK::Spawn` argument, after `$TASK::Resources`, with one method per task
+/// in this list:
+///
+/// ``` ignore
+/// fn exti0(t: &mut Threshold, mut s: EXTI0::Spawn) {
+///     // spawn `foo`, whose `input` is `u32`, with the message `42`
+///     if let Err(_message) = s.foo(t, 42) {
+///         // the queue of `foo` is full
+///     }
+/// }
+/// ```
+///
+/// The queues of the software tasks are shared by their spawners and their dispatchers; their
+/// ceilings are computed like those of resources.
+///
+/// If omitted its value defaults to an empty list.
+///
+/// ## `tasks.$TASK.stack`
+///
+/// This key is optional. Its value is an integer with type `u32` that specifies the stack usage of
+/// this task in bytes, including the functions it calls.
+///
+/// All tasks share a single stack. The `rtfm-stack` tool of the `rtfm-tools` crate combines these
+/// values with the task priorities to bound the worst case usage of the shared stack. Tasks that
+/// don't declare it get the stack usage of their handler and of the functions it calls, from the
+/// `.stack_sizes` section and the call graph of the ELF; the tool gives no bound if a handler
+/// calls through function pointers or recurses. Software tasks are accounted for through the
+/// dispatchers that run them, and the `SYS_TICK` handler through its call graph; their stack usage
+/// always comes from the ELF.
+///
+/// ## `tasks.$TASK.threshold`
+///
+/// This key is optional. Its value is an integer with type `u8` that specifies the preemption
+/// threshold of this task: once the task has started it runs with its priority raised to this
+/// value, so it can only be preempted by tasks whose priority is greater than its threshold. The
+/// threshold can't be lower than the `priority` of the task, nor be used along `priority: auto`.
+///
+/// Tasks whose thresholds are not lower than each other's priorities form a non-preemptive group:
+/// they never stack on top of each other, which lowers the worst case stack usage (see the
+/// `rtfm-stack` tool), and their claims on resources with ceilings up to their thresholds need no
+/// critical section. The price is blocking: a lower priority task whose threshold is not lower
+/// than the priority of another task can delay the latter for its whole execution time; the
+/// schedulability analysis accounts for this.
+///
+/// If omitted its value defaults to the `priority` of the task.
+///
+/// ## `tasks.$TASK.units`
+///
+/// This key is optional. Its value is a set of `$RESOURCE: $UNITS` pairs, like `units: { BUFFERS: 2
+/// }`, that specify the maximum number of units of each resource this task holds at once. The
+/// resources must be in this task's `resources` list.
+///
+/// A resource that appears in any `units` set is a *multi-unit* resource: a pool of interchangeable
+/// units. Its type must be an array, `[T; N]` with `1 <= N <= 32`, whose elements are the units.
+/// Contexts that list the resource but don't declare its units hold one unit at a time. Multi-unit
+/// resources can't be read-only nor be assigned to `init`.
+///
+/// The proxy of a multi-unit resource implements `rtfm::UnitResource`: `claim_units` takes `n`
+/// units from the pool for the span of a closure. The ceiling of the resource is dynamic; while
+/// `k` units are available it's the highest priority among the contexts that may hold more than `k`
+/// units, so a claim only raises the preemption threshold enough to exclude the tasks that could
+/// find the pool short. The blocking time used by the schedulability analysis is computed with the
+/// highest of these ceilings.
+///
+/// ## `tasks.$TASK.wcet`
+///
+/// This key is optional. Its value is an integer with type `u32` that specifies the worst case
+/// execution time of this task in clock cycles.
+///
+/// `wcet` is all-or-nothing: if any task declares its `wcet` then all tasks must do so, and each
+/// task that doesn't is reported as an error. In that case a response time analysis of the task
+/// set is performed and the compilation fails, with an error at each task that can miss its
+/// deadline explaining its response time. The analysis accounts for the execution time of the
+/// runtime as declared in `overheads`.
+///
+/// # Application description
+///
+/// Every expansion of this macro writes a machine readable (JSON) description of the application
+/// -- its device, tasks, resources and resource ceilings -- to `target/rtfm/app.json`, relative to
+/// the root of the crate being compiled. The location can be changed by setting the
+/// `RTFM_APP_MODEL` environment variable to a different path. The `version` field of the document
+/// is bumped on every backwards incompatible change of its layout.
+///
+/// The same information is available to the application itself: the macro generates a `rtfm_meta`
+/// module with `const` tables of the tasks (name, priority, interarrival time, deadline and
+/// resources) and of the resources (name and ceiling). See the `rtfm::meta` module for details.
+///
+/// Finally, a compact, versioned table of the tasks (name, handler symbol, priority, exception
+/// number, interarrival time and resources) and of the resource ceilings is placed in the
+/// `.rtfm_meta` linker section. The handler symbol of a software task is that of its dispatcher.
+/// Interrupt numbers are not in the table; readers look the handler symbols up in the vector
+/// table. The table is not loaded into the device memory but it stays in the
+/// ELF so host tools can always recover the task set of the binary they work on; `rtfm-tools`
+/// provides a reader. The linker script must keep the section, e.g. in `memory.x`:
+///
+/// ``` text
+/// SECTIONS
+/// {
+///   .rtfm_meta (INFO) :
+///   {
+///     KEEP(*(.rtfm_meta));
+///   }
+/// }
+/// ```
 #[proc_macro]
 pub fn app(ts: TokenStream) -> TokenStream {
     match run(ts) {
@@ -179,30 +568,165 @@ pub fn app(ts: TokenStream) -> TokenStream {
     }
 }
 
+/// The attribute form of the `app!` macro: the application is a module that holds its resources
+/// and task handlers. Reexported as `rtfm::attr::app`.
+///
+/// ``` text
+/// #[app(device = .., free_interrupts = [..], instrument = ..)]
+/// mod app {
+///     static mut A: bool = false;
+///     static mut B: Thing = ();
+///
+///     #[init(resources = [..], schedule = [..], spawn = [..], stack = ..)]
+///     fn init(p: init::Peripherals, r: init::Resources) -> init::LateResources { .. }
+///
+///     #[idle(resources = [..], ..)]
+///     fn idle(t: &mut Threshold, r: idle::Resources) -> ! { .. }
+///
+///     #[task(binds = EXTI0, priority = 2, resources = [A, B])]
+///     fn exti0(t: &mut Threshold, r: EXTI0::Resources) { .. }
+///
+///     #[task(input = u32, priority = 1)]
+///     fn foo(input: u32) { .. }
+/// }
+/// ```
+///
+/// The arguments of the attribute are the `device`, `free_interrupts` and `instrument` keys of
+/// `app!`, written as `key = value`. The `static mut` items of the module are the `resources`; a
+/// resource whose initial value is `()` is a late resource. The functions with an `#[init]`,
+/// `#[idle]` or `#[task]` attribute are the handlers of those contexts, and the arguments of the
+/// attribute are the keys of the context minus `path`.
+///
+/// The `binds` argument of `#[task]` names the exception or interrupt the task is bound to, i.e.
+/// the `$TASK` of `app!`. A task without `binds` is a software task named after its handler, so it
+/// must declare a `capacity`, an `input` or a `period`.
+///
+/// The module must be at the root of the crate. It's replaced with its items, minus the RTFM
+/// attributes and the resources, followed by the code `app!` would generate. Unlike `app!`, the
+/// signatures of the handlers are checked: a handler whose arguments or return type don't match
+/// the keys of its context is reported at its name, along the signature it must have.
+#[proc_macro_attribute]
+pub fn app_module(args: TokenStream, input: TokenStream) -> TokenStream {
+    match attribute(args, input) {
+        Err(e) => panic!("{}", error_chain::ChainedError::display(&e)),
+        Ok(ts) => ts,
+    }
+}
+
 fn run(ts: TokenStream) -> Result<TokenStream> {
     let input = format!("{}", ts);
 
-    let app = App::parse(&input).chain_err(|| "parsing")?;
-    let app = syntax::check::app(app).chain_err(|| "checking the AST")?;
-    let app = check::app(app)?;
+    let mut diagnostics = Diagnostics::new();
+    match ::expand(&input, None, &mut diagnostics)? {
+        Some(tokens) => Ok(tokens),
+        None => Ok(::diag::emit(&diagnostics, ts)),
+    }
+}
+
+fn attribute(args: TokenStream, input: TokenStream) -> Result<TokenStream> {
+    let module = attr::parse(&format!("{}", args), &format!("{}", input))
+        .chain_err(|| "parsing the `#[app]` module")?;
 
-    let ownerships = analyze::app(&app);
-    let tokens = trans::app(&app, &ownerships);
+    let mut diagnostics = Diagnostics::new();
+    match ::expand(&module.app, Some(&module), &mut diagnostics)? {
+        Some(tokens) => {
+            let items = &module.items;
+            let items: TokenStream = quote!(#(#items)*)
+                .as_str()
+                .parse()
+                .map_err(|_| "BUG: error parsing the items of the module")?;
 
-    if cfg!(feature = "klee_mode") {
-        println!("tasks");
-        let mut tasks = Vec::new();
-        for (id, task) in app.tasks {
-            println!("{}", id);
-            tasks.push(format!("{} {} {}", id, task.priority, task.interarrival));
+            Ok(vec![items, tokens].into_iter().collect())
         }
+        // the items refer to the generated code; leave them out
+        None => Ok(::diag::emit(&diagnostics, input)),
+    }
+}
 
-        let path = Path::new("klee/tasks.txt");
+/// Environment variable that points to a file with measured critical section
+/// lengths
+const SECTIONS_VAR: &str = "RTFM_CRITICAL_SECTIONS";
 
-        let mut file = File::create(path).unwrap();
-        write!(file, "// autogenerated file\n{:?}", tasks).unwrap();
+/// Critical section lengths listed in the file pointed to by `SECTIONS_VAR`;
+/// none if the variable is not set
+fn measured() -> Result<CriticalSections> {
+    let path = match env::var_os(SECTIONS_VAR) {
+        Some(path) => path,
+        None => return Ok(CriticalSections::new()),
+    };
+
+    let mut input = String::new();
+    File::open(&path)
+        .and_then(|mut f| f.read_to_string(&mut input))
+        .chain_err(|| format!("reading {}", path.to_string_lossy()))?;
+
+    blocking::measured(&input).chain_err(|| format!("parsing {}", path.to_string_lossy()))
+}
+
+/// Expands the `app!` input `input`
+///
+/// Returns `None` if errors were recorded in `diagnostics`.
+fn expand(
+    input: &str,
+    module: Option<&attr::Module>,
+    diagnostics: &mut Diagnostics,
+) -> Result<Option<TokenStream>> {
+    let mut app = analysis::check_app(input, diagnostics)?;
+    let analyses = match app {
+        Some(ref mut app) => ::analyze(app, diagnostics)?,
+        None => None,
+    };
+
+    if let Some(module) = module {
+        diagnostics.relocate(|at| module.locate(at));
+
+        if let Some(ref app) = app {
+            attr::signatures(app, module, diagnostics);
+        }
     }
-    Ok(format!("{}", tokens)
+
+    let (app, (ownerships, dispatchers)) = match (app, analyses, diagnostics.is_empty()) {
+        (Some(app), Some(analyses), true) => (app, analyses),
+        _ => return Ok(None),
+    };
+
+    let tokens = trans::app(&app, &ownerships, &dispatchers);
+    let table = section::app(&app, &ownerships, &dispatchers)?;
+
+    export::app(&app, &ownerships, &dispatchers)?;
+
+    Ok(Some(format!("{}{}", tokens, table)
         .parse()
-        .map_err(|_| "BUG: error parsing the generated code")?)
+        .map_err(|_| "BUG: error parsing the generated code")?))
+}
+
+/// Assigns the `auto` priorities of the checked `app` and checks that the task set is schedulable
+///
+/// Returns `None` if errors were recorded in `diagnostics`.
+fn analyze(
+    app: &mut App,
+    diagnostics: &mut Diagnostics,
+) -> Result<Option<(Ownerships, Dispatchers)>> {
+    let measured = ::measured()?;
+    analysis::assign::app(app, &measured, diagnostics);
+    if !diagnostics.is_empty() {
+        return Ok(None);
+    }
+
+    let ownerships = analysis::ownerships(app);
+    let dispatchers = diagnostics.check(&["free_interrupts"], analysis::analyze::dispatchers(app));
+    analysis::rta::check(app, &ownerships, &measured, diagnostics);
+
+    // there's no clock to drive the timer queue on the host
+    if cfg!(feature = "host") && analysis::analyze::timer_queue(app).is_some() {
+        diagnostics.error(
+            &[],
+            "the host backend doesn't support `schedule` or periodic tasks".to_owned(),
+        );
+    }
+
+    Ok(match dispatchers {
+        Some(dispatchers) if diagnostics.is_empty() => Some((ownerships, dispatchers)),
+        _ => None,
+    })
 }
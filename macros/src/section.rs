@@ -0,0 +1,111 @@
+//! Task table placed in the `.rtfm_meta` linker section
+//!
+//! The table travels with the binary so host tools (see `rtfm-tools`) can
+//! recover the task set from any ELF built from the application. All the
+//! integers are little endian; strings are prefixed by their length as a `u8`.
+//!
+//! ``` text
+//! header:    b"RTFM" VERSION:u8 TASKS:u8 RESOURCES:u8 0:u8
+//! resource:  NAME:str CEILING:u8                      (0xff = unused)
+//! task:      NAME:str SYMBOL:str PRIORITY:u8 EXCEPTION:u8 (0xff = interrupt,
+//!            0xfe = software task) INTERARRIVAL:u32 N:u8 RESOURCE:u8 * N
+//!            (indices into the resources)
+//! ```
+//!
+//! Tasks and resources are sorted by name. The symbol of a software task is
+//! the handler of the interrupt that dispatches it.
+//!
+//! The interrupt numbers are not part of the table: the device crate doesn't
+//! provide them as constants. Readers find the interrupt number of a task by
+//! looking up the address of its symbol in the vector table of the ELF.
+use quote::Tokens;
+
+use analysis::analyze::{Dispatchers, Ownerships};
+use analysis::check::{App, Kind};
+
+use syntax::error::*;
+
+/// Version of the table layout; bump on every backwards incompatible change
+const VERSION: u8 = 2;
+
+/// Marks unused resources and interrupts
+const NONE: u8 = 0xff;
+
+/// Marks software tasks
+const SOFTWARE: u8 = 0xfe;
+
+pub fn app(app: &App, ownerships: &Ownerships, dispatchers: &Dispatchers) -> Result<Tokens> {
+    let mut rnames = app.resources.keys().collect::<Vec<_>>();
+    rnames.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+    let mut tnames = app.tasks.keys().collect::<Vec<_>>();
+    tnames.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+    ensure!(
+        rnames.len() < 256 && tnames.len() < 256,
+        "too many tasks or resources for the `.rtfm_meta` table"
+    );
+
+    let mut bytes = vec![];
+    bytes.extend_from_slice(b"RTFM");
+    bytes.push(VERSION);
+    bytes.push(tnames.len() as u8);
+    bytes.push(rnames.len() as u8);
+    bytes.push(0);
+
+    for name in &rnames {
+        string(&mut bytes, name.as_ref())?;
+        bytes.push(ownerships.get(*name).map(|o| o.ceiling()).unwrap_or(NONE));
+    }
+
+    for name in &tnames {
+        let task = &app.tasks[*name];
+
+        string(&mut bytes, name.as_ref())?;
+        string(
+            &mut bytes,
+            match task.kind {
+                Kind::Exception(ref e) => e.symbol(),
+                Kind::Interrupt { .. } => name.as_ref(),
+                Kind::Software { .. } => dispatchers[&task.priority].interrupt.as_ref(),
+            },
+        )?;
+        bytes.push(task.priority);
+        bytes.push(match task.kind {
+            Kind::Exception(ref e) => e.nr() as u8,
+            Kind::Interrupt { .. } => NONE,
+            Kind::Software { .. } => SOFTWARE,
+        });
+        for i in 0..4 {
+            bytes.push((task.interarrival >> (8 * i)) as u8);
+        }
+
+        let mut resources = task.resources
+            .iter()
+            .map(|r| rnames.iter().position(|n| *n == r).expect("BUG: unknown resource"))
+            .collect::<Vec<_>>();
+        resources.sort();
+
+        bytes.push(resources.len() as u8);
+        bytes.extend(resources.into_iter().map(|i| i as u8));
+    }
+
+    let len = bytes.len();
+    Ok(quote! {
+        #[link_section = ".rtfm_meta"]
+        #[no_mangle]
+        pub static RTFM_META: [u8; #len] = [#(#bytes),*];
+    })
+}
+
+fn string(bytes: &mut Vec<u8>, s: &str) -> Result<()> {
+    ensure!(
+        s.len() < 256,
+        "`{}` is too long for the `.rtfm_meta` table",
+        s
+    );
+
+    bytes.push(s.len() as u8);
+    bytes.extend_from_slice(s.as_bytes());
+
+    Ok(())
+}
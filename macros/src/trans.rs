@@ -1,42 +1,114 @@
 use quote::{Ident, Tokens};
-use syn::{Lit, StrStyle};
+use syn::{Lit, StrStyle, Ty};
 
-use analyze::Ownerships;
-use check::{App, Kind};
+use analysis::analyze::{self, Dispatchers, Ownerships};
+use analysis::check::{App, Exception, Kind};
+use syntax::Spawn;
 
 fn krate() -> Ident {
     Ident::from("rtfm")
 }
 
-pub fn app(app: &App, ownerships: &Ownerships) -> Tokens {
+pub fn app(app: &App, ownerships: &Ownerships, dispatchers: &Dispatchers) -> Tokens {
     let mut root = vec![];
-    let mut main = vec![];
+    let mut init = vec![];
+    let mut idle = vec![];
 
-    ::trans::init(app, &mut main, &mut root);
-    ::trans::idle(app, ownerships, &mut main, &mut root);
+    ::trans::init(app, ownerships, dispatchers, &mut init, &mut root);
+    ::trans::idle(app, ownerships, dispatchers, &mut idle, &mut root);
     ::trans::resources(app, ownerships, &mut root);
-    ::trans::tasks(app, ownerships, &mut root);
+    ::trans::tasks(app, ownerships, dispatchers, &mut root);
+    ::trans::dispatchers(app, ownerships, dispatchers, &mut root);
+    ::trans::timer_queue(app, ownerships, dispatchers, &mut root);
+    ::trans::meta(app, ownerships, &mut root);
+    ::trans::instrument(app, &mut root);
+
+    if let Some(bits) = app.nvic_prio_bits {
+        let device = &app.device;
+        let bits = usize::from(bits);
+
+        // the priorities computed by `priority: auto` are only valid if the device has this many
+        // priority bits
+        root.push(quote! {
+            #[allow(dead_code)]
+            const CHECK_NVIC_PRIO_BITS: [(); #bits] = [(); ::#device::NVIC_PRIO_BITS as usize];
+        });
+    }
 
-    root.push(quote! {
-        #[allow(private_no_mangle_fns)]
-        #[allow(unsafe_code)]
-        //#[no_mangle]
-        fn main() {
-            #(#main)*
-        }
-    });
+    if cfg!(feature = "host") {
+        // on the host, tests call `rtfm_init` to start the application without entering `idle`;
+        // `main` is replaced by the test harness under `cargo test`
+        root.push(quote! {
+            #[allow(unsafe_code)]
+            pub fn rtfm_init() {
+                #(#init)*
+            }
+
+            #[allow(dead_code)]
+            fn main() {
+                rtfm_init();
+                #(#idle)*
+            }
+        });
+    } else {
+        // called by the reset handler of `cortex-m-rt`, or by KLEE in klee mode where `idle` is
+        // not run; the application is `#![no_main]`
+        let ret = if cfg!(feature = "klee_mode") {
+            None
+        } else {
+            Some(quote!(-> !))
+        };
+        root.push(quote! {
+            #[allow(unsafe_code)]
+            #[export_name = "main"]
+            pub extern "C" fn main() #ret {
+                #(#init)*
+                #(#idle)*
+            }
+        });
+    }
 
     quote!(#(#root)*)
 }
 
-fn idle(app: &App, ownerships: &Ownerships, main: &mut Vec<Tokens>, root: &mut Vec<Tokens>) {
+fn instrument(app: &App, root: &mut Vec<Tokens>) {
+    let krate = krate();
+
+    let instrument = match app.instrument {
+        Some(ref path) => quote!(#path),
+        None => if cfg!(feature = "wcet_bkpt") {
+            quote!(#krate::instrument::Breakpoints)
+        } else if cfg!(feature = "wcet_nop") {
+            quote!(#krate::instrument::Nops)
+        } else {
+            quote!(#krate::instrument::Noop)
+        },
+    };
+
+    // the implementation of `rtfm::Instrument` that receives the scheduling events; see the
+    // `claim`s and the task entry points
+    root.push(quote! {
+        #[allow(non_camel_case_types)]
+        type rtfm_Instrument = #instrument;
+    });
+}
+
+fn idle(
+    app: &App,
+    ownerships: &Ownerships,
+    dispatchers: &Dispatchers,
+    main: &mut Vec<Tokens>,
+    root: &mut Vec<Tokens>,
+) {
     let krate = krate();
 
     let mut mod_items = vec![];
     let mut tys = vec![];
     let mut exprs = vec![];
 
-    if !app.idle.resources.is_empty() {
+    if !app.idle.resources.is_empty() || !app.idle.spawn.is_empty()
+        || !app.idle.schedule.is_empty()
+    {
         tys.push(quote!(&mut #krate::Threshold));
         exprs.push(quote!(unsafe { &mut #krate::Threshold::new(0) }));
     }
@@ -66,21 +138,37 @@ fn idle(app: &App, ownerships: &Ownerships, main: &mut Vec<Tokens>, root: &mut V
                     name
                 ));
                 let ty = &resource.ty;
+                let _name = Ident::new(format!("_{}", name.as_ref()));
 
-                rfields.push(quote! {
-                    pub #name: &'static mut #ty,
-                });
+                if app.idle.read_only.contains(name) {
+                    rfields.push(quote! {
+                        pub #name: &'static #ty,
+                    });
 
-                let _name = Ident::new(format!("_{}", name.as_ref()));
-                rexprs.push(if resource.expr.is_some() {
-                    quote! {
-                        #name: &mut #super_::#_name,
-                    }
+                    rexprs.push(if resource.expr.is_some() {
+                        quote! {
+                            #name: &#super_::#_name,
+                        }
+                    } else {
+                        quote! {
+                            #name: &*#super_::#_name.as_ptr(),
+                        }
+                    });
                 } else {
-                    quote! {
-                        #name: #super_::#_name.as_mut(),
-                    }
-                });
+                    rfields.push(quote! {
+                        pub #name: &'static mut #ty,
+                    });
+
+                    rexprs.push(if resource.expr.is_some() {
+                        quote! {
+                            #name: &mut #super_::#_name,
+                        }
+                    } else {
+                        quote! {
+                            #name: &mut *#super_::#_name.as_mut_ptr(),
+                        }
+                    });
+                }
             } else {
                 rfields.push(quote! {
                     pub #name: ::idle::#name,
@@ -128,12 +216,44 @@ fn idle(app: &App, ownerships: &Ownerships, main: &mut Vec<Tokens>, root: &mut V
         exprs.push(quote!(unsafe { idle::Resources::new() }));
     }
 
+    if !app.idle.spawn.is_empty() {
+        ::trans::spawn(
+            app,
+            ownerships,
+            dispatchers,
+            &Ident::new("idle"),
+            Some(0),
+            &app.idle.spawn,
+            &mut mod_items,
+            root,
+        );
+
+        tys.push(quote!(idle::Spawn));
+        exprs.push(quote!(unsafe { idle::Spawn::new() }));
+    }
+
+    if !app.idle.schedule.is_empty() {
+        ::trans::schedule(
+            app,
+            ownerships,
+            &Ident::new("idle"),
+            Some(0),
+            &app.idle.schedule,
+            &mut mod_items,
+            root,
+        );
+
+        tys.push(quote!(idle::Schedule));
+        exprs.push(quote!(unsafe { idle::Schedule::new() }));
+    }
+
     let device = &app.device;
     for name in &app.idle.resources {
         let ceiling = ownerships[name].ceiling();
+        let read_ceiling = ownerships[name].read_ceiling();
 
         // owned resource
-        if ceiling == 0 {
+        if ownerships[name].is_owned() {
             continue;
         }
 
@@ -146,7 +266,7 @@ fn idle(app: &App, ownerships: &Ownerships, main: &mut Vec<Tokens>, root: &mut V
         let _static = if resource.expr.is_some() {
             quote!(#_name)
         } else {
-            quote!(#_name.some)
+            quote!((*#_name.as_mut_ptr()))
         };
 
         mod_items.push(quote! {
@@ -154,13 +274,58 @@ fn idle(app: &App, ownerships: &Ownerships, main: &mut Vec<Tokens>, root: &mut V
             pub struct #name { _0: core::marker::PhantomData<*const ()> }
         });
 
+        if let Some(&max) = app.idle.units.get(name) {
+            root.push(::trans::units(
+                app,
+                ownerships,
+                quote!(idle::#name),
+                name,
+                max,
+            ));
+
+            continue;
+        }
+
+        if app.idle.read_only.contains(name) {
+            root.push(quote! {
+                #[allow(unsafe_code)]
+                unsafe impl #krate::ReadOnlyResource for idle::#name {
+                    type Data = #ty;
+
+                    fn borrow<'cs>(&'cs self, t: &'cs Threshold) -> &'cs Self::Data {
+                        assert!(t.value() >= #read_ceiling);
+
+                        unsafe { &#_static }
+                    }
+
+                    fn claim<R, F>(&self, t: &mut Threshold, f: F) -> R
+                    where
+                        F: FnOnce(&Self::Data, &mut Threshold) -> R
+                    {
+                        unsafe {
+                            #krate::claim::<::rtfm_Instrument, _, _, _>(
+                                &#_static,
+                                Some(::rtfm_meta::resources::#name),
+                                #read_ceiling,
+                                #device::NVIC_PRIO_BITS,
+                                t,
+                                f,
+                            )
+                        }
+                    }
+                }
+            });
+
+            continue;
+        }
+
         root.push(quote! {
             #[allow(unsafe_code)]
             unsafe impl #krate::Resource for idle::#name {
                 type Data = #ty;
 
                 fn borrow<'cs>(&'cs self, t: &'cs Threshold) -> &'cs Self::Data {
-                    assert!(t.value() >= #ceiling);
+                    assert!(t.value() >= #read_ceiling);
 
                     unsafe { &#_static }
                 }
@@ -179,9 +344,10 @@ fn idle(app: &App, ownerships: &Ownerships, main: &mut Vec<Tokens>, root: &mut V
                     F: FnOnce(&Self::Data, &mut Threshold) -> R
                 {
                     unsafe {
-                        #krate::claim(
+                        #krate::claim::<::rtfm_Instrument, _, _, _>(
                             &#_static,
-                            #ceiling,
+                            Some(::rtfm_meta::resources::#name),
+                            #read_ceiling,
                             #device::NVIC_PRIO_BITS,
                             t,
                             f,
@@ -194,8 +360,9 @@ fn idle(app: &App, ownerships: &Ownerships, main: &mut Vec<Tokens>, root: &mut V
                     F: FnOnce(&mut Self::Data, &mut Threshold) -> R
                 {
                     unsafe {
-                        #krate::claim(
+                        #krate::claim::<::rtfm_Instrument, _, _, _>(
                             &mut #_static,
+                            Some(::rtfm_meta::resources::#name),
                             #ceiling,
                             #device::NVIC_PRIO_BITS,
                             t,
@@ -231,7 +398,13 @@ fn idle(app: &App, ownerships: &Ownerships, main: &mut Vec<Tokens>, root: &mut V
     }
 }
 
-fn init(app: &App, main: &mut Vec<Tokens>, root: &mut Vec<Tokens>) {
+fn init(
+    app: &App,
+    ownerships: &Ownerships,
+    dispatchers: &Dispatchers,
+    main: &mut Vec<Tokens>,
+    root: &mut Vec<Tokens>,
+) {
     let device = &app.device;
     let krate = krate();
 
@@ -308,6 +481,37 @@ fn init(app: &App, main: &mut Vec<Tokens>, root: &mut Vec<Tokens>) {
         exprs.push(quote!(init::Resources::new()));
     }
 
+    if !app.init.spawn.is_empty() {
+        ::trans::spawn(
+            app,
+            ownerships,
+            dispatchers,
+            &Ident::new("init"),
+            None,
+            &app.init.spawn,
+            &mut mod_items,
+            root,
+        );
+
+        tys.push(quote!(init::Spawn));
+        exprs.push(quote!(init::Spawn::new()));
+    }
+
+    if !app.init.schedule.is_empty() {
+        ::trans::schedule(
+            app,
+            ownerships,
+            &Ident::new("init"),
+            None,
+            &app.init.schedule,
+            &mut mod_items,
+            root,
+        );
+
+        tys.push(quote!(init::Schedule));
+        exprs.push(quote!(init::Schedule::new()));
+    }
+
     // Initialization statements for late resources
     let mut late_resource_init = vec![];
 
@@ -326,7 +530,7 @@ fn init(app: &App, main: &mut Vec<Tokens>, root: &mut Vec<Tokens>) {
             });
 
             late_resource_init.push(quote! {
-                #_name = #krate::UntaggedOption { some: _late_resources.#name };
+                #_name = #krate::MaybeUninit::new(_late_resources.#name);
             });
         }
 
@@ -361,8 +565,51 @@ fn init(app: &App, main: &mut Vec<Tokens>, root: &mut Vec<Tokens>) {
     let mut exceptions = vec![];
     let mut interrupts = vec![];
     for (name, task) in &app.tasks {
+        if cfg!(feature = "host") {
+            // the tasks are registered with the software NVIC of `rtfm::host`
+            let _name = Ident::new(format!("_{}", name.as_ref()));
+            let priority = task.priority;
+            match task.kind {
+                Kind::Exception(ref e) => {
+                    let nr = e.nr();
+                    exceptions.push(if e.is_non_maskable() {
+                        // fixed priority
+                        quote! {
+                            #krate::host::register_exception(#nr, 0, #_name);
+                        }
+                    } else {
+                        quote! {
+                            let prio_bits = #device::NVIC_PRIO_BITS;
+                            let hw = ((1 << prio_bits) - #priority) << (8 - prio_bits);
+                            #krate::host::register_exception(#nr, hw, #_name);
+                        }
+                    });
+                }
+                Kind::Interrupt { enabled } => {
+                    interrupts.push(quote! {
+                        let prio_bits = #device::NVIC_PRIO_BITS;
+                        let hw = ((1 << prio_bits) - #priority) << (8 - prio_bits);
+                        #krate::host::register_interrupt(#device::Interrupt::#name, hw, #_name);
+                    });
+
+                    if enabled {
+                        interrupts.push(quote! {
+                            #krate::host::enable(#device::Interrupt::#name);
+                        });
+                    }
+                }
+                Kind::Software { .. } => {}
+            }
+            continue;
+        }
+
         match task.kind {
             Kind::Exception(ref e) => {
+                // fixed priority and always enabled
+                if e.is_non_maskable() {
+                    continue;
+                }
+
                 if exceptions.is_empty() {
                     exceptions.push(quote! {
                         let scb = &*#device::SCB::ptr();
@@ -376,6 +623,20 @@ fn init(app: &App, main: &mut Vec<Tokens>, root: &mut Vec<Tokens>) {
                     let hw = ((1 << prio_bits) - #priority) << (8 - prio_bits);
                     scb.shpr[#nr - 4].write(hw);
                 });
+
+                if let Some(bit) = e.shcsr_enable() {
+                    // otherwise the fault escalates to `HARD_FAULT`
+                    exceptions.push(quote! {
+                        scb.shcsr.modify(|r| r | (1 << #bit));
+                    });
+                }
+
+                if let Exception::DEBUG_MONITOR = *e {
+                    // MON_EN
+                    exceptions.push(quote! {
+                        (*#device::DCB::ptr()).demcr.modify(|r| r | (1 << 16));
+                    });
+                }
             }
             Kind::Interrupt { enabled } => {
                 // Interrupt. These are enabled / disabled through the NVIC
@@ -402,6 +663,83 @@ fn init(app: &App, main: &mut Vec<Tokens>, root: &mut Vec<Tokens>) {
                     });
                 }
             }
+            // dispatched from one of the interrupts below
+            Kind::Software { .. } => {}
+        }
+    }
+
+    for (&priority, dispatcher) in dispatchers {
+        let interrupt = &dispatcher.interrupt;
+        if cfg!(feature = "host") {
+            let _interrupt = Ident::new(format!("_{}", interrupt));
+            interrupts.push(quote! {
+                let prio_bits = #device::NVIC_PRIO_BITS;
+                let hw = ((1 << prio_bits) - #priority) << (8 - prio_bits);
+                #krate::host::register_dispatcher(#device::Interrupt::#interrupt, hw, #_interrupt);
+                #krate::host::enable(#device::Interrupt::#interrupt);
+            });
+            continue;
+        }
+
+        if interrupts.is_empty() {
+            interrupts.push(quote! {
+                let mut nvic: #device::NVIC = core::mem::transmute(());
+            });
+        }
+
+        interrupts.push(quote! {
+            let prio_bits = #device::NVIC_PRIO_BITS;
+            let hw = ((1 << prio_bits) - #priority) << (8 - prio_bits);
+            nvic.set_priority(#device::Interrupt::#interrupt, hw);
+            nvic.enable(#device::Interrupt::#interrupt);
+        });
+    }
+
+    // the `SYS_TICK` handler releases the scheduled and the periodic tasks
+    let mut timer_queue = None;
+    let mut periodic = None;
+    if let Some(tq) = analyze::timer_queue(app) {
+        if exceptions.is_empty() {
+            exceptions.push(quote! {
+                let scb = &*#device::SCB::ptr();
+            });
+        }
+
+        let priority = tq.priority;
+        exceptions.push(quote! {
+            let prio_bits = #device::NVIC_PRIO_BITS;
+            let hw = ((1 << prio_bits) - #priority) << (8 - prio_bits);
+            scb.shpr[15 - 4].write(hw);
+        });
+
+        // the cycle counter must be running before `init` can schedule tasks
+        timer_queue = Some(quote!(#krate::tq::enable();));
+
+        // the periodic tasks are first released `offset` clock cycles after
+        // `init` returns
+        let mut releases = vec![];
+        for name in &tq.tasks {
+            let task = &app.tasks[name];
+            if task.period.is_none() {
+                continue;
+            }
+
+            let offset = task.offset;
+            releases.push(quote! {
+                let _ = _TQ.enqueue(#krate::tq::NotReady {
+                    instant: start + #krate::Duration::from_cycles(#offset),
+                    task: _TQTasks::#name,
+                    index: 0,
+                });
+            });
+        }
+
+        if !releases.is_empty() {
+            periodic = Some(quote! {
+                let start = #krate::Instant::now();
+                #(#releases)*
+                #krate::tq::pend();
+            });
         }
     }
 
@@ -421,8 +759,10 @@ fn init(app: &App, main: &mut Vec<Tokens>, root: &mut Vec<Tokens>) {
                 let init: fn(#(#tys,)*) #ret = #init;
 
                 #krate::atomic(unsafe { &mut #krate::Threshold::new(0) }, |_t| unsafe {
+                    #timer_queue
                     let _late_resources = init(#(#exprs,)*);
                     #(#late_resource_init)*
+                    #periodic
 
                     #(#exceptions)*
                     #(#interrupts)*
@@ -434,10 +774,20 @@ fn init(app: &App, main: &mut Vec<Tokens>, root: &mut Vec<Tokens>) {
         let mut tasks = vec![];
         let mut index: u32 = 0;
 
-        for (name, _task) in &app.tasks {
+        // sorted by name so that the indices match the order of the tasks in the
+        // `.rtfm_meta` table and in the application description
+        let mut names = app.tasks.keys().collect::<Vec<_>>();
+        names.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+        for name in names {
             let _name = Ident::new(format!("_{}", name.as_ref()));
-            tasks.push(quote!{
-                #index => { #_name(); }
+            tasks.push(if app.tasks[name].kind.is_software() {
+                // software tasks are not analyzed yet but they keep their index
+                quote!(#index => {})
+            } else {
+                quote! {
+                    #index => { #_name(); }
+                }
             });
             index += 1;
         }
@@ -465,41 +815,116 @@ fn init(app: &App, main: &mut Vec<Tokens>, root: &mut Vec<Tokens>) {
 fn resources(app: &App, ownerships: &Ownerships, root: &mut Vec<Tokens>) {
     let krate = krate();
 
-    for name in ownerships.keys() {
+    // the queues of the software tasks are declared by `dispatchers`
+    let names = ownerships
+        .keys()
+        .filter(|name| app.resources.contains_key(name))
+        .collect::<Vec<_>>();
+
+    for name in &names {
         let _name = Ident::new(format!("_{}", name.as_ref()));
 
         // Declare the static that holds the resource
-        let resource = app.resources
-            .get(name)
-            .expect(&format!("BUG: resource {} has no definition", name));
+        let resource = &app.resources[*name];
 
         let expr = &resource.expr;
         let ty = &resource.ty;
 
         root.push(match *expr {
             Some(ref expr) => quote! {
-                #[allow(private_no_mangle_statics)]
                 #[no_mangle]
                 static mut #_name: #ty = #expr;
             },
             None => quote! {
                 // Resource initialized in `init`
-                #[allow(private_no_mangle_statics)]
                 #[no_mangle]
-                static mut #_name: #krate::UntaggedOption<#ty> =
-                    #krate::UntaggedOption { none: () };
+                static mut #_name: #krate::MaybeUninit<#ty> =
+                    #krate::MaybeUninit::uninit();
             },
         });
     }
 
+    // bitmasks of the available units of the multi-unit resources
+    for (name, &len) in &app.units {
+        let _free = Ident::new(format!("_{}_FREE", name.as_ref()));
+        let mask = ::std::u32::MAX >> (32 - u32::from(len));
+
+        root.push(quote! {
+            #[allow(non_upper_case_globals)]
+            static mut #_free: u32 = #mask;
+        });
+    }
+
+    if cfg!(feature = "host") {
+        // used by the tests to start each schedule of `rtfm::host::explore` from the same state,
+        // and to check invariants over the resources; late resources are initialized by
+        // `rtfm_init`
+        let mut resets = vec![];
+        let mut fields = vec![];
+        let mut exprs = vec![];
+        for name in &names {
+            let _name = Ident::new(format!("_{}", name.as_ref()));
+            let resource = &app.resources[*name];
+            let ty = &resource.ty;
+
+            fields.push(quote! {
+                pub #name: &'static mut #ty,
+            });
+
+            match resource.expr {
+                Some(ref expr) => {
+                    resets.push(quote! {
+                        #_name = #expr;
+                    });
+                    exprs.push(quote! {
+                        #name: &mut #_name,
+                    });
+                }
+                None => exprs.push(quote! {
+                    #name: &mut *#_name.as_mut_ptr(),
+                }),
+            }
+        }
+
+        // all the units are back in their pools
+        for (name, &len) in &app.units {
+            let _free = Ident::new(format!("_{}_FREE", name.as_ref()));
+            let mask = ::std::u32::MAX >> (32 - u32::from(len));
+
+            resets.push(quote! {
+                #_free = #mask;
+            });
+        }
+
+        root.push(quote! {
+            #[allow(unsafe_code)]
+            pub unsafe fn rtfm_reset() {
+                #(#resets)*
+            }
+
+            #[allow(non_camel_case_types)]
+            #[allow(non_snake_case)]
+            pub struct rtfm_Resources {
+                #(#fields)*
+            }
+
+            #[allow(unsafe_code)]
+            pub unsafe fn rtfm_resources() -> rtfm_Resources {
+                rtfm_Resources {
+                    #(#exprs)*
+                }
+            }
+        });
+    }
+
     if cfg!(feature = "klee_mode") {
         // collect the identifiers for our resources
 
-        let mut names = vec![];
-        for name in ownerships.keys() {
+        let mut symbols = vec![];
+        for name in &names {
             let _name = Ident::new(format!("_{}", name.as_ref()));
             let _nameq = Ident::new(format!("\"_{}\"", name.as_ref()));
-            names.push(quote!{
+            symbols.push(quote!{
                 k_symbol!(&mut #_name, #_nameq);
             });
         }
@@ -507,17 +932,17 @@ fn resources(app: &App, ownerships: &Ownerships, root: &mut Vec<Tokens>) {
         // generate a function setting all resources to symbolic
         root.push(quote!{
             pub unsafe fn make_resources_symbolic() {
-                #(#names)*
+                #(#symbols)*
             }
         });
     } else {
         if cfg!(feature = "wcet_nop") || cfg!(feature = "wcet_bkpt") {
             // collect the identifiers for our resources
 
-            let mut names = vec![];
-            for name in ownerships.keys() {
+            let mut reads = vec![];
+            for name in &names {
                 let _name = Ident::new(format!("_{}", name.as_ref()));
-                names.push(quote!{
+                reads.push(quote!{
                     let _ = k_read(&#_name);
                 });
             }
@@ -525,14 +950,14 @@ fn resources(app: &App, ownerships: &Ownerships, root: &mut Vec<Tokens>) {
             // generate a function reading all resources
             root.push(quote!{
                 pub unsafe fn read_resources() {
-                    #(#names)*
+                    #(#reads)*
                 }
             });
         }
     }
 }
 
-fn tasks(app: &App, ownerships: &Ownerships, root: &mut Vec<Tokens>) {
+fn tasks(app: &App, ownerships: &Ownerships, dispatchers: &Dispatchers, root: &mut Vec<Tokens>) {
     let device = &app.device;
     let krate = krate();
 
@@ -542,10 +967,13 @@ fn tasks(app: &App, ownerships: &Ownerships, root: &mut Vec<Tokens>) {
         let mut items = vec![];
 
         let has_resources = !task.resources.is_empty();
+        let has_locals = !task.local.is_empty();
 
-        if has_resources {
+        if has_resources || has_locals {
             for rname in &task.resources {
                 let ceiling = ownerships[rname].ceiling();
+                // shared (read) accesses only need to exclude the writers
+                let read_ceiling = ownerships[rname].read_ceiling();
                 let _rname = Ident::new(format!("_{}", rname.as_ref()));
                 let resource = app.resources
                     .get(rname)
@@ -555,7 +983,7 @@ fn tasks(app: &App, ownerships: &Ownerships, root: &mut Vec<Tokens>) {
                 let _static = if resource.expr.is_some() {
                     quote!(#_rname)
                 } else {
-                    quote!(#_rname.some)
+                    quote!((*#_rname.as_mut_ptr()))
                 };
 
                 items.push(quote! {
@@ -563,145 +991,377 @@ fn tasks(app: &App, ownerships: &Ownerships, root: &mut Vec<Tokens>) {
                     pub struct #rname { _0: PhantomData<*const ()> }
                 });
 
-                root.push(quote! {
-                    #[allow(unsafe_code)]
-                    unsafe impl #krate::Resource for #tname::#rname {
-                        type Data = #ty;
-
-                        fn borrow<'cs>(&'cs self, t: &'cs Threshold) -> &'cs Self::Data {
-                            assert!(t.value() >= #ceiling);
-
-                            unsafe { &#_static }
-                        }
+                if let Some(&max) = task.units.get(rname) {
+                    root.push(::trans::units(
+                        app,
+                        ownerships,
+                        quote!(#tname::#rname),
+                        rname,
+                        max,
+                    ));
+                } else if task.read_only.contains(rname) {
+                    root.push(quote! {
+                        #[allow(unsafe_code)]
+                        unsafe impl #krate::ReadOnlyResource for #tname::#rname {
+                            type Data = #ty;
 
-                        fn borrow_mut<'cs>(
-                            &'cs mut self,
-                            t: &'cs Threshold,
-                        ) -> &'cs mut Self::Data {
-                            assert!(t.value() >= #ceiling);
+                            fn borrow<'cs>(&'cs self, t: &'cs Threshold) -> &'cs Self::Data {
+                                assert!(t.value() >= #read_ceiling);
 
-                            unsafe { &mut #_static }
-                        }
+                                unsafe { &#_static }
+                            }
 
-                        fn claim<R, F>(&self, t: &mut Threshold, f: F) -> R
-                        where
-                            F: FnOnce(&Self::Data, &mut Threshold) -> R
-                        {
-                            unsafe {
-                                #krate::claim(
-                                    &#_static,
-                                    #ceiling,
-                                    #device::NVIC_PRIO_BITS,
-                                    t,
-                                    f,
-                                )
+                            fn claim<R, F>(&self, t: &mut Threshold, f: F) -> R
+                            where
+                                F: FnOnce(&Self::Data, &mut Threshold) -> R
+                            {
+                                unsafe {
+                                    #krate::claim::<::rtfm_Instrument, _, _, _>(
+                                        &#_static,
+                                        Some(::rtfm_meta::resources::#rname),
+                                        #read_ceiling,
+                                        #device::NVIC_PRIO_BITS,
+                                        t,
+                                        f,
+                                    )
+                                }
                             }
                         }
+                    });
 
-                        fn claim_mut<R, F>(&mut self, t: &mut Threshold, f: F) -> R
-                        where
-                            F: FnOnce(&mut Self::Data, &mut Threshold) -> R
-                        {
-                            unsafe {
-                                #krate::claim(
-                                    &mut #_static,
-                                    #ceiling,
-                                    #device::NVIC_PRIO_BITS,
-                                    t,
-                                    f,
-                                )
+                    if read_ceiling <= task.threshold {
+                        root.push(quote! {
+                            #[allow(unsafe_code)]
+                            impl core::ops::Deref for #tname::#rname {
+                                type Target = #ty;
+
+                                fn deref(&self) -> &Self::Target {
+                                    unsafe { &#_static }
+                                }
                             }
-                        }
+                        });
                     }
-                });
-
-                if ceiling <= task.priority {
+                } else {
                     root.push(quote! {
                         #[allow(unsafe_code)]
-                        impl core::ops::Deref for #tname::#rname {
-                            type Target = #ty;
+                        unsafe impl #krate::Resource for #tname::#rname {
+                            type Data = #ty;
+
+                            fn borrow<'cs>(&'cs self, t: &'cs Threshold) -> &'cs Self::Data {
+                                assert!(t.value() >= #read_ceiling);
 
-                            fn deref(&self) -> &Self::Target {
                                 unsafe { &#_static }
                             }
-                        }
 
-                        #[allow(unsafe_code)]
-                        impl core::ops::DerefMut for #tname::#rname {
-                            fn deref_mut(&mut self) -> &mut Self::Target {
+                            fn borrow_mut<'cs>(
+                                &'cs mut self,
+                                t: &'cs Threshold,
+                            ) -> &'cs mut Self::Data {
+                                assert!(t.value() >= #ceiling);
+
                                 unsafe { &mut #_static }
                             }
+
+                            fn claim<R, F>(&self, t: &mut Threshold, f: F) -> R
+                            where
+                                F: FnOnce(&Self::Data, &mut Threshold) -> R
+                            {
+                                unsafe {
+                                    #krate::claim::<::rtfm_Instrument, _, _, _>(
+                                        &#_static,
+                                        Some(::rtfm_meta::resources::#rname),
+                                        #read_ceiling,
+                                        #device::NVIC_PRIO_BITS,
+                                        t,
+                                        f,
+                                    )
+                                }
+                            }
+
+                            fn claim_mut<R, F>(&mut self, t: &mut Threshold, f: F) -> R
+                            where
+                                F: FnOnce(&mut Self::Data, &mut Threshold) -> R
+                            {
+                                unsafe {
+                                    #krate::claim::<::rtfm_Instrument, _, _, _>(
+                                        &mut #_static,
+                                        Some(::rtfm_meta::resources::#rname),
+                                        #ceiling,
+                                        #device::NVIC_PRIO_BITS,
+                                        t,
+                                        f,
+                                    )
+                                }
+                            }
                         }
-                    })
+                    });
+
+                    if ceiling <= task.priority {
+                        root.push(quote! {
+                            #[allow(unsafe_code)]
+                            impl core::ops::Deref for #tname::#rname {
+                                type Target = #ty;
+
+                                fn deref(&self) -> &Self::Target {
+                                    unsafe { &#_static }
+                                }
+                            }
+
+                            #[allow(unsafe_code)]
+                            impl core::ops::DerefMut for #tname::#rname {
+                                fn deref_mut(&mut self) -> &mut Self::Target {
+                                    unsafe { &mut #_static }
+                                }
+                            }
+                        })
+                    }
                 }
 
-                fields.push(quote! {
-                    pub #rname: #rname,
-                });
+                if has_locals {
+                    fields.push(quote! {
+                        pub #rname: ::#tname::#rname,
+                    });
 
-                exprs.push(quote! {
-                    #rname: #rname { _0: PhantomData },
-                });
+                    exprs.push(quote! {
+                        #rname: ::#tname::#rname { _0: ::core::marker::PhantomData },
+                    });
+                } else {
+                    fields.push(quote! {
+                        pub #rname: #rname,
+                    });
+
+                    exprs.push(quote! {
+                        #rname: #rname { _0: PhantomData },
+                    });
+                }
             }
 
-            items.push(quote! {
-                #[allow(non_snake_case)]
-                pub struct Resources {
-                    #(#fields)*
+            if has_locals {
+                // like `idle::Resources` the struct is defined in the root so
+                // the types and initializers of the locals resolve there. The
+                // locals are `static` variables of `new`; handing out
+                // `&'static mut` references to them is sound because a task
+                // can't preempt itself
+                let mut statics = vec![];
+                for (lname, local) in &task.local {
+                    let ty = &local.ty;
+                    let expr = local.expr.as_ref().expect("BUG: local without initializer");
+
+                    statics.push(quote! {
+                        static mut #lname: #ty = #expr;
+                    });
+
+                    fields.push(quote! {
+                        pub #lname: &'static mut #ty,
+                    });
+
+                    exprs.push(quote! {
+                        #lname: &mut #lname,
+                    });
                 }
-            });
 
-            items.push(quote! {
-                #[allow(unsafe_code)]
-                impl Resources {
-                    pub unsafe fn new() -> Self {
-                        Resources {
-                            #(#exprs)*
+                let _resources = Ident::new(format!("_{}Resources", tname));
+                root.push(quote! {
+                    #[allow(non_camel_case_types)]
+                    #[allow(non_snake_case)]
+                    pub struct #_resources {
+                        #(#fields)*
+                    }
+
+                    #[allow(unsafe_code)]
+                    impl #_resources {
+                        pub unsafe fn new() -> Self {
+                            #(#statics)*
+
+                            #_resources {
+                                #(#exprs)*
+                            }
                         }
                     }
-                }
-            });
+                });
+
+                items.push(quote! {
+                    pub use ::#_resources as Resources;
+                });
+            } else {
+                items.push(quote! {
+                    #[allow(non_snake_case)]
+                    pub struct Resources {
+                        #(#fields)*
+                    }
+                });
+
+                items.push(quote! {
+                    #[allow(unsafe_code)]
+                    impl Resources {
+                        pub unsafe fn new() -> Self {
+                            Resources {
+                                #(#exprs)*
+                            }
+                        }
+                    }
+                });
+            }
         }
 
         let mut tys = vec![];
         let mut exprs = vec![];
 
         let priority = task.priority;
-        if has_resources {
+        let threshold = task.threshold;
+        if has_resources || !task.spawn.is_empty() || !task.schedule.is_empty() {
             tys.push(quote!(&mut #krate::Threshold));
-            exprs.push(quote! {
-                &mut if #priority == 1 << #device::NVIC_PRIO_BITS {
-                    #krate::Threshold::new(::core::u8::MAX)
-                } else {
-                    #krate::Threshold::new(#priority)
+            exprs.push(if threshold > priority {
+                // the token of the `claim` that raises the preemption threshold
+                quote!(_t)
+            } else {
+                quote! {
+                    &mut if #priority == 1 << #device::NVIC_PRIO_BITS {
+                        #krate::Threshold::new(::core::u8::MAX)
+                    } else {
+                        #krate::Threshold::new(#priority)
+                    }
                 }
             });
         }
 
-        if has_resources {
+        if has_resources || has_locals {
             tys.push(quote!(#tname::Resources));
             exprs.push(quote!(#tname::Resources::new()));
         }
 
+        if !task.spawn.is_empty() {
+            ::trans::spawn(
+                app,
+                ownerships,
+                dispatchers,
+                &Ident::new(tname.as_ref()),
+                Some(priority),
+                &task.spawn,
+                &mut items,
+                root,
+            );
+
+            tys.push(quote!(#tname::Spawn));
+            exprs.push(quote!(#tname::Spawn::new()));
+        }
+
+        if !task.schedule.is_empty() {
+            ::trans::schedule(
+                app,
+                ownerships,
+                &Ident::new(tname.as_ref()),
+                Some(priority),
+                &task.schedule,
+                &mut items,
+                root,
+            );
+
+            tys.push(quote!(#tname::Schedule));
+            exprs.push(quote!(#tname::Schedule::new()));
+        }
+
+        // software tasks also take the message posted by `spawn` / `schedule`
+        let (arg, ty) = match task.kind {
+            Kind::Software { input: Some(ref ty), .. } => {
+                exprs.push(quote!(input));
+                (Some(quote!(input: #ty)), Some(quote!(#ty)))
+            }
+            _ => (None, None),
+        };
+
+        // once started the task runs with BASEPRI raised to its threshold
+        let call = if threshold > priority {
+            quote! {
+                #krate::threshold::<::rtfm_Instrument, _, _>(
+                    #threshold,
+                    #device::NVIC_PRIO_BITS,
+                    &mut #krate::Threshold::new(#priority),
+                    |_t| f(#(#exprs,)*),
+                )
+            }
+        } else {
+            quote!(f(#(#exprs,)*))
+        };
+
+        let start = quote! {
+            <::rtfm_Instrument as #krate::Instrument>::task_start(::rtfm_meta::tasks::#tname);
+        };
+        let end = quote! {
+            <::rtfm_Instrument as #krate::Instrument>::task_end(::rtfm_meta::tasks::#tname);
+        };
+
         let path = &task.path;
         let _tname = Ident::new(format!("_{}", tname));
-        let export_name = Lit::Str(tname.as_ref().to_owned(), StrStyle::Cooked);
-        root.push(quote! {
-            #[allow(non_snake_case)]
-            #[allow(unsafe_code)]
-            #[export_name = #export_name]
-            pub unsafe extern "C" fn #_tname() {
-                let f: fn(#(#tys,)*) = #path;
+        if let Kind::Software { .. } = task.kind {
+            // called by the dispatcher; see `dispatchers`
+            root.push(quote! {
+                #[allow(non_snake_case)]
+                #[allow(unsafe_code)]
+                unsafe fn #_tname(#arg) {
+                    let f: fn(#(#tys,)* #ty) = #path;
+
+                    #start
+                    #call;
+                    #end
+                }
+            });
+        } else {
+            // the handlers of the exceptions are exported under their `cortex-m-rt` names
+            let symbol = match task.kind {
+                Kind::Exception(ref e) => e.symbol(),
+                _ => tname.as_ref(),
+            };
+            let export_name = Lit::Str(symbol.to_owned(), StrStyle::Cooked);
+
+            // `cortex-m-rt` calls `UserHardFault` with the stacked registers and expects it to
+            // never return so the task runs from a wrapper with that signature
+            let hard_fault = match task.kind {
+                Kind::Exception(Exception::HARD_FAULT) => true,
+                _ => false,
+            };
+            let export = if hard_fault {
+                None
+            } else {
+                Some(quote!(#[export_name = #export_name]))
+            };
 
-                f(#(#exprs,)*)
+            root.push(quote! {
+                #[allow(non_snake_case)]
+                #[allow(unsafe_code)]
+                #export
+                pub unsafe extern "C" fn #_tname() {
+                    let f: fn(#(#tys,)*) = #path;
+
+                    #start
+                    #call;
+                    #end
+                }
+            });
+
+            if hard_fault {
+                root.push(quote! {
+                    #[allow(non_snake_case)]
+                    #[allow(unsafe_code)]
+                    #[export_name = #export_name]
+                    pub unsafe extern "C" fn _UserHardFault(_frame: &#krate::ExceptionFrame) -> ! {
+                        #_tname();
+
+                        // a hard fault is not recoverable
+                        loop {
+                            core::sync::atomic::compiler_fence(
+                                core::sync::atomic::Ordering::SeqCst,
+                            );
+                        }
+                    }
+                });
             }
-        });
+        }
 
-        if cfg!(feature = "wcet_bkpt") || cfg!(feature = "wcet_nop") {
+        if (cfg!(feature = "wcet_bkpt") || cfg!(feature = "wcet_nop")) && !task.kind.is_software() {
             let _stub_tname = Ident::new(format!("stub_{}", tname));
             root.push(quote! {
                 #[inline(never)]
-                #[allow(private_no_mangle_fns)]
                 #[no_mangle]
                 #[allow(non_snake_case)]
                 fn #_stub_tname() {
@@ -714,12 +1374,11 @@ fn tasks(app: &App, ownerships: &Ownerships, root: &mut Vec<Tokens>) {
             });
         }
 
-        root.push(quote!{
-            #[allow(non_snake_case)]
-            #[allow(unsafe_code)]
-            mod #tname {
-                use core::marker::PhantomData;
-
+        // the priorities of the non-maskable exceptions are fixed
+        let checks = if task.kind.is_non_maskable() {
+            None
+        } else {
+            Some(quote! {
                 #[allow(dead_code)]
                 #[deny(const_err)]
                 const CHECK_PRIORITY: (u8, u8) = (
@@ -727,13 +1386,31 @@ fn tasks(app: &App, ownerships: &Ownerships, root: &mut Vec<Tokens>) {
                     (1 << ::#device::NVIC_PRIO_BITS) - #priority,
                 );
 
+                #[allow(dead_code)]
+                #[deny(const_err)]
+                const CHECK_THRESHOLD: u8 = (1 << ::#device::NVIC_PRIO_BITS) - #threshold;
+            })
+        };
+
+        root.push(quote!{
+            #[allow(non_snake_case)]
+            #[allow(unsafe_code)]
+            mod #tname {
+                use core::marker::PhantomData;
+
+                #checks
+
                 #(#items)*
             }
         });
     }
     if cfg!(feature = "wcet_bkpt") || cfg!(feature = "wcet_nop") {
         let mut stubs = vec![];
-        for (name, _task) in &app.tasks {
+        for (name, task) in &app.tasks {
+            if task.kind.is_software() {
+                continue;
+            }
+
             let _name = Ident::new(format!("stub_{}", name.as_ref()));
             stubs.push(quote!{
                 #_name();
@@ -754,3 +1431,694 @@ fn tasks(app: &App, ownerships: &Ownerships, root: &mut Vec<Tokens>) {
         });
     }
 }
+
+/// Generates the `Spawn` token of a context that runs at `priority` (`None` for
+/// `init`) and can spawn the software tasks in `spawn`
+///
+/// The struct goes into the module of the context (`items`); its methods, one
+/// per software task, go into the root of the crate.
+fn spawn(
+    app: &App,
+    ownerships: &Ownerships,
+    dispatchers: &Dispatchers,
+    context: &Ident,
+    priority: Option<u8>,
+    spawn: &Spawn,
+    items: &mut Vec<Tokens>,
+    root: &mut Vec<Tokens>,
+) {
+    let device = &app.device;
+    let krate = krate();
+
+    items.push(quote! {
+        pub struct Spawn { _0: ::core::marker::PhantomData<*const ()> }
+
+        #[allow(unsafe_code)]
+        impl Spawn {
+            pub unsafe fn new() -> Self {
+                Spawn { _0: ::core::marker::PhantomData }
+            }
+        }
+    });
+
+    let mut names = spawn.iter().collect::<Vec<_>>();
+    names.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+    let mut methods = vec![];
+    for name in names {
+        let task = &app.tasks[name];
+        let ty = match task.kind {
+            Kind::Software { ref input, .. } => match *input {
+                Some(ref ty) => quote!(#ty),
+                None => quote!(()),
+            },
+            _ => unreachable!(),
+        };
+        let dispatcher = &dispatchers[&task.priority];
+
+        let _inputs = Ident::new(format!("_{}_INPUTS", name));
+        let _fq = Ident::new(format!("_{}_FQ", name));
+        let _rq = Ident::new(format!("_RQ{}", task.priority));
+        let _rq_tasks = Ident::new(format!("_RQ{}Tasks", task.priority));
+        let fq_ceiling = ownerships[&analyze::fq(name)].ceiling();
+        let rq_ceiling = ownerships[&analyze::rq(task.priority)].ceiling();
+        let interrupt = &dispatcher.interrupt;
+
+        // `init` runs with interrupts disabled
+        let (t, threshold) = match priority {
+            Some(_) => (Some(quote!(t: &mut #krate::Threshold,)), None),
+            None => (None, Some(quote!(let t = &mut #krate::Threshold::max();))),
+        };
+        let (input, unit) = match task.kind {
+            Kind::Software { input: Some(_), .. } => (Some(quote!(input: #ty)), None),
+            _ => (None, Some(quote!(let input = ();))),
+        };
+
+        methods.push(quote! {
+            /// Spawns the software task; returns the message back if the
+            /// queue of the task is full
+            pub fn #name(&mut self, #t #input) -> Result<(), #ty> {
+                #unit
+                unsafe {
+                    #threshold
+
+                    let index = #krate::claim::<::rtfm_Instrument, _, _, _>(
+                        &mut #_fq,
+                        None,
+                        #fq_ceiling,
+                        #device::NVIC_PRIO_BITS,
+                        t,
+                        |fq, _| fq.dequeue(),
+                    );
+
+                    if let Some(index) = index {
+                        // this slot is now exclusively ours
+                        core::ptr::write(
+                            (&mut #_inputs as *mut _ as *mut #ty).offset(index as isize),
+                            input,
+                        );
+
+                        #krate::claim::<::rtfm_Instrument, _, _, _>(
+                            &mut #_rq,
+                            None,
+                            #rq_ceiling,
+                            #device::NVIC_PRIO_BITS,
+                            t,
+                            |rq, _| {
+                                // the ready queue can hold as many messages as
+                                // there are slots so this can't fail
+                                let _ = rq.enqueue((#_rq_tasks::#name, index));
+                            },
+                        );
+
+                        #krate::set_pending(#device::Interrupt::#interrupt);
+
+                        Ok(())
+                    } else {
+                        Err(input)
+                    }
+                }
+            }
+        });
+    }
+
+    root.push(quote! {
+        #[allow(non_snake_case)]
+        #[allow(unsafe_code)]
+        impl #context::Spawn {
+            #(#methods)*
+        }
+    });
+}
+
+/// Generates the `Schedule` token of a context that runs at `priority` (`None`
+/// for `init`) and can schedule the software tasks in `schedule`
+///
+/// Like `Spawn` but the message is put in the timer queue and the task is
+/// released `after` clock cycles from now.
+fn schedule(
+    app: &App,
+    ownerships: &Ownerships,
+    context: &Ident,
+    priority: Option<u8>,
+    schedule: &Spawn,
+    items: &mut Vec<Tokens>,
+    root: &mut Vec<Tokens>,
+) {
+    let device = &app.device;
+    let krate = krate();
+
+    items.push(quote! {
+        pub struct Schedule { _0: ::core::marker::PhantomData<*const ()> }
+
+        #[allow(unsafe_code)]
+        impl Schedule {
+            pub unsafe fn new() -> Self {
+                Schedule { _0: ::core::marker::PhantomData }
+            }
+        }
+    });
+
+    let mut names = schedule.iter().collect::<Vec<_>>();
+    names.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+    let tq_ceiling = ownerships[&analyze::tq()].ceiling();
+
+    let mut methods = vec![];
+    for name in names {
+        let task = &app.tasks[name];
+        let ty = match task.kind {
+            Kind::Software { ref input, .. } => match *input {
+                Some(ref ty) => quote!(#ty),
+                None => quote!(()),
+            },
+            _ => unreachable!(),
+        };
+
+        let _inputs = Ident::new(format!("_{}_INPUTS", name));
+        let _fq = Ident::new(format!("_{}_FQ", name));
+        let fq_ceiling = ownerships[&analyze::fq(name)].ceiling();
+
+        // `init` runs with interrupts disabled
+        let (t, threshold) = match priority {
+            Some(_) => (Some(quote!(t: &mut #krate::Threshold,)), None),
+            None => (None, Some(quote!(let t = &mut #krate::Threshold::max();))),
+        };
+        let (input, unit) = match task.kind {
+            Kind::Software { input: Some(_), .. } => (Some(quote!(input: #ty)), None),
+            _ => (None, Some(quote!(let input = ();))),
+        };
+
+        methods.push(quote! {
+            /// Schedules the software task to be released `after` clock cycles
+            /// from now; returns the message back if the queue of the task is
+            /// full
+            pub fn #name(
+                &mut self,
+                #t
+                after: #krate::Duration,
+                #input
+            ) -> Result<(), #ty> {
+                #unit
+                unsafe {
+                    #threshold
+
+                    let index = #krate::claim::<::rtfm_Instrument, _, _, _>(
+                        &mut #_fq,
+                        None,
+                        #fq_ceiling,
+                        #device::NVIC_PRIO_BITS,
+                        t,
+                        |fq, _| fq.dequeue(),
+                    );
+
+                    if let Some(index) = index {
+                        // this slot is now exclusively ours
+                        core::ptr::write(
+                            (&mut #_inputs as *mut _ as *mut #ty).offset(index as isize),
+                            input,
+                        );
+
+                        let entry = #krate::tq::NotReady {
+                            instant: #krate::Instant::now() + after,
+                            task: _TQTasks::#name,
+                            index,
+                        };
+
+                        #krate::claim::<::rtfm_Instrument, _, _, _>(
+                            &mut _TQ,
+                            None,
+                            #tq_ceiling,
+                            #device::NVIC_PRIO_BITS,
+                            t,
+                            |tq, _| {
+                                // the timer queue can hold as many messages as
+                                // there are slots so this can't fail
+                                if let Ok(true) = tq.enqueue(entry) {
+                                    // new earliest entry; re-arm the timer
+                                    #krate::tq::pend();
+                                }
+                            },
+                        );
+
+                        Ok(())
+                    } else {
+                        Err(input)
+                    }
+                }
+            }
+        });
+    }
+
+    root.push(quote! {
+        #[allow(non_snake_case)]
+        #[allow(unsafe_code)]
+        impl #context::Schedule {
+            #(#methods)*
+        }
+    });
+}
+
+/// Generates the message queues of the software tasks and the interrupt
+/// handlers that dispatch them
+fn dispatchers(
+    app: &App,
+    ownerships: &Ownerships,
+    dispatchers: &Dispatchers,
+    root: &mut Vec<Tokens>,
+) {
+    let device = &app.device;
+    let krate = krate();
+
+    for (&priority, dispatcher) in dispatchers {
+        let _rq = Ident::new(format!("_RQ{}", priority));
+        let _rq_tasks = Ident::new(format!("_RQ{}Tasks", priority));
+        let rq_ceiling = ownerships[&analyze::rq(priority)].ceiling();
+        let capacity = dispatcher.capacity;
+
+        let mut variants = vec![];
+        let mut arms = vec![];
+        for name in &dispatcher.tasks {
+            let task = &app.tasks[name];
+            let (capacity, input) = match task.kind {
+                Kind::Software {
+                    capacity,
+                    ref input,
+                } => (capacity, input),
+                _ => unreachable!(),
+            };
+            let ty = match *input {
+                Some(ref ty) => quote!(#ty),
+                None => quote!(()),
+            };
+
+            let _inputs = Ident::new(format!("_{}_INPUTS", name));
+            let _fq = Ident::new(format!("_{}_FQ", name));
+            let _name = Ident::new(format!("_{}", name));
+            let fq_ceiling = ownerships[&analyze::fq(name)].ceiling();
+            let slots = (0..capacity).collect::<Vec<_>>();
+            let len = usize::from(capacity);
+
+            root.push(quote! {
+                #[allow(non_upper_case_globals)]
+                static mut #_inputs: #krate::MaybeUninit<[#ty; #len]> =
+                    #krate::MaybeUninit::uninit();
+
+                // indices of the free message slots
+                #[allow(non_upper_case_globals)]
+                static mut #_fq: #krate::Queue<u8, [u8; #len]> = #krate::Queue {
+                    buffer: #krate::MaybeUninit::new([#(#slots),*]),
+                    head: 0,
+                    len: #len,
+                    capacity: #len,
+                    _marker: core::marker::PhantomData,
+                };
+            });
+
+            let call = if input.is_some() {
+                quote!(#_name(input);)
+            } else {
+                quote!(#_name();)
+            };
+
+            variants.push(quote!(#name,));
+            arms.push(quote! {
+                #_rq_tasks::#name => {
+                    let input = core::ptr::read(
+                        (&#_inputs as *const _ as *const #ty).offset(index as isize),
+                    );
+
+                    #krate::claim::<::rtfm_Instrument, _, _, _>(
+                        &mut #_fq,
+                        None,
+                        #fq_ceiling,
+                        #device::NVIC_PRIO_BITS,
+                        t,
+                        |fq, _| {
+                            let _ = fq.enqueue(index);
+                        },
+                    );
+
+                    #call
+                }
+            });
+        }
+
+        let interrupt = &dispatcher.interrupt;
+        let _interrupt = Ident::new(format!("_{}", interrupt));
+        let export_name = Lit::Str(interrupt.as_ref().to_owned(), StrStyle::Cooked);
+        root.push(quote! {
+            #[allow(non_camel_case_types)]
+            #[derive(Clone, Copy)]
+            enum #_rq_tasks {
+                #(#variants)*
+            }
+
+            static mut #_rq: #krate::Queue<(#_rq_tasks, u8), [(#_rq_tasks, u8); #capacity]> =
+                #krate::Queue {
+                    buffer: #krate::MaybeUninit::uninit(),
+                    head: 0,
+                    len: 0,
+                    capacity: #capacity,
+                    _marker: core::marker::PhantomData,
+                };
+
+            #[allow(non_snake_case)]
+            #[allow(unsafe_code)]
+            #[allow(unused_variables)]
+            #[export_name = #export_name]
+            pub unsafe extern "C" fn #_interrupt() {
+                let t = &mut if #priority == 1 << #device::NVIC_PRIO_BITS {
+                    #krate::Threshold::new(::core::u8::MAX)
+                } else {
+                    #krate::Threshold::new(#priority)
+                };
+
+                while let Some((task, index)) = #krate::claim::<::rtfm_Instrument, _, _, _>(
+                    &mut #_rq,
+                    None,
+                    #rq_ceiling,
+                    #device::NVIC_PRIO_BITS,
+                    t,
+                    |rq, _| rq.dequeue(),
+                ) {
+                    match task {
+                        #(#arms)*
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Generates the timer queue and the `SYS_TICK` handler that moves the
+/// scheduled tasks into the ready queues once their release time has come and
+/// keeps releasing the periodic tasks
+fn timer_queue(
+    app: &App,
+    ownerships: &Ownerships,
+    dispatchers: &Dispatchers,
+    root: &mut Vec<Tokens>,
+) {
+    let device = &app.device;
+    let krate = krate();
+
+    let tq = match analyze::timer_queue(app) {
+        Some(tq) => tq,
+        None => return,
+    };
+
+    let priority = tq.priority;
+    let capacity = tq.capacity;
+    let tq_ceiling = ownerships[&analyze::tq()].ceiling();
+    let export_name = Lit::Str(Exception::SYS_TICK.symbol().to_owned(), StrStyle::Cooked);
+
+    let mut variants = vec![];
+    let mut arms = vec![];
+    for name in &tq.tasks {
+        let task_priority = app.tasks[name].priority;
+        let _rq = Ident::new(format!("_RQ{}", task_priority));
+        let _rq_tasks = Ident::new(format!("_RQ{}Tasks", task_priority));
+        let rq_ceiling = ownerships[&analyze::rq(task_priority)].ceiling();
+        let interrupt = &dispatchers[&task_priority].interrupt;
+
+        variants.push(quote!(#name,));
+
+        if let Some(period) = app.tasks[name].period {
+            let _fq = Ident::new(format!("_{}_FQ", name));
+            let fq_ceiling = ownerships[&analyze::fq(name)].ceiling();
+
+            arms.push(quote! {
+                _TQTasks::#name => {
+                    // the next release is one period after this one
+                    #krate::claim::<::rtfm_Instrument, _, _, _>(
+                        &mut _TQ,
+                        None,
+                        #tq_ceiling,
+                        #device::NVIC_PRIO_BITS,
+                        t,
+                        |tq, _| {
+                            let _ = tq.enqueue(#krate::tq::NotReady {
+                                instant: entry.instant + #krate::Duration::from_cycles(#period),
+                                task: _TQTasks::#name,
+                                index: 0,
+                            });
+                        },
+                    );
+
+                    let index = #krate::claim::<::rtfm_Instrument, _, _, _>(
+                        &mut #_fq,
+                        None,
+                        #fq_ceiling,
+                        #device::NVIC_PRIO_BITS,
+                        t,
+                        |fq, _| fq.dequeue(),
+                    );
+
+                    // no free slot means that the previous instances are still
+                    // pending; the task overran its period so this release is
+                    // dropped
+                    if let Some(index) = index {
+                        #krate::claim::<::rtfm_Instrument, _, _, _>(
+                            &mut #_rq,
+                            None,
+                            #rq_ceiling,
+                            #device::NVIC_PRIO_BITS,
+                            t,
+                            |rq, _| {
+                                let _ = rq.enqueue((#_rq_tasks::#name, index));
+                            },
+                        );
+
+                        #krate::set_pending(#device::Interrupt::#interrupt);
+                    }
+                }
+            });
+
+            continue;
+        }
+
+        arms.push(quote! {
+            _TQTasks::#name => {
+                #krate::claim::<::rtfm_Instrument, _, _, _>(
+                    &mut #_rq,
+                    None,
+                    #rq_ceiling,
+                    #device::NVIC_PRIO_BITS,
+                    t,
+                    |rq, _| {
+                        // the ready queue can hold as many messages as there
+                        // are slots so this can't fail
+                        let _ = rq.enqueue((#_rq_tasks::#name, entry.index));
+                    },
+                );
+
+                #krate::set_pending(#device::Interrupt::#interrupt);
+            }
+        });
+    }
+
+    root.push(quote! {
+        #[allow(non_camel_case_types)]
+        #[derive(Clone, Copy)]
+        enum _TQTasks {
+            #(#variants)*
+        }
+
+        static mut _TQ: #krate::tq::TimerQueue<
+            _TQTasks,
+            [#krate::tq::NotReady<_TQTasks>; #capacity],
+        > = #krate::tq::TimerQueue {
+            buffer: #krate::MaybeUninit::uninit(),
+            len: 0,
+            capacity: #capacity,
+            _marker: core::marker::PhantomData,
+        };
+
+        #[allow(non_snake_case)]
+        #[allow(unsafe_code)]
+        #[export_name = #export_name]
+        pub unsafe extern "C" fn _SYS_TICK() {
+            let t = &mut if #priority == 1 << #device::NVIC_PRIO_BITS {
+                #krate::Threshold::new(::core::u8::MAX)
+            } else {
+                #krate::Threshold::new(#priority)
+            };
+
+            while let Some(entry) = #krate::claim::<::rtfm_Instrument, _, _, _>(
+                &mut _TQ,
+                None,
+                #tq_ceiling,
+                #device::NVIC_PRIO_BITS,
+                t,
+                |tq, _| {
+                    let entry = tq.dequeue(#krate::Instant::now());
+
+                    if entry.is_none() {
+                        // nothing else is due; sleep until the earliest entry
+                        match tq.next() {
+                            Some(instant) => #krate::tq::arm(instant),
+                            None => #krate::tq::disarm(),
+                        }
+                    }
+
+                    entry
+                },
+            ) {
+                match entry.task {
+                    #(#arms)*
+                }
+            }
+        }
+    });
+}
+
+/// Generates the `UnitResource` implementation of the proxy `proxy` of the
+/// multi-unit resource `name` for a context that holds up to `max` units
+fn units(
+    app: &App,
+    ownerships: &Ownerships,
+    proxy: Tokens,
+    name: &syn::Ident,
+    max: u8,
+) -> Tokens {
+    let device = &app.device;
+    let krate = krate();
+
+    let resource = &app.resources[name];
+    let unit = match resource.ty {
+        Ty::Array(ref unit, _) => unit,
+        _ => unreachable!(),
+    };
+    let _name = Ident::new(format!("_{}", name.as_ref()));
+    let _static = if resource.expr.is_some() {
+        quote!(#_name)
+    } else {
+        quote!((*#_name.as_mut_ptr()))
+    };
+    let _free = Ident::new(format!("_{}_FREE", name.as_ref()));
+    let ceilings = ownerships[name]
+        .unit_ceilings()
+        .expect("BUG: not a multi-unit resource");
+    let max = usize::from(max);
+
+    quote! {
+        #[allow(unsafe_code)]
+        unsafe impl #krate::UnitResource for #proxy {
+            type Unit = #unit;
+
+            fn claim_units<R, F>(&mut self, t: &mut Threshold, n: usize, f: F) -> R
+            where
+                F: FnOnce(&mut #krate::Units<Self::Unit>, &mut Threshold) -> R
+            {
+                unsafe {
+                    #krate::claim_units::<::rtfm_Instrument, _, _, _>(
+                        &mut #_static as *mut _ as *mut #unit,
+                        &mut #_free,
+                        Some(::rtfm_meta::resources::#name),
+                        n,
+                        #max,
+                        &[#(#ceilings),*],
+                        #device::NVIC_PRIO_BITS,
+                        t,
+                        f,
+                    )
+                }
+            }
+        }
+    }
+}
+
+fn meta(app: &App, ownerships: &Ownerships, root: &mut Vec<Tokens>) {
+    let krate = krate();
+
+    // sort by name so that the indices don't depend on the HashMap order
+    let mut rnames = app.resources.keys().collect::<Vec<_>>();
+    rnames.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+    let mut tnames = app.tasks.keys().collect::<Vec<_>>();
+    tnames.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+
+    let mut resources = vec![];
+    let mut rindices = vec![];
+    for (i, name) in rnames.iter().enumerate() {
+        let sname = Lit::Str(name.as_ref().to_owned(), StrStyle::Cooked);
+        let ceiling = match ownerships.get(*name) {
+            Some(ownership) => {
+                let ceiling = ownership.ceiling();
+                quote!(Some(#ceiling))
+            }
+            None => quote!(None),
+        };
+
+        resources.push(quote! {
+            ::#krate::meta::Resource {
+                name: #sname,
+                ceiling: #ceiling,
+            },
+        });
+
+        rindices.push(quote! {
+            pub const #name: usize = #i;
+        });
+    }
+
+    let mut tasks = vec![];
+    let mut tindices = vec![];
+    for (i, name) in tnames.iter().enumerate() {
+        let task = &app.tasks[*name];
+
+        let sname = Lit::Str(name.as_ref().to_owned(), StrStyle::Cooked);
+        let priority = task.priority;
+        let interarrival = task.interarrival;
+        let deadline = task.deadline;
+        let wcet = match task.wcet {
+            Some(wcet) => quote!(Some(#wcet)),
+            None => quote!(None),
+        };
+        let mut task_resources = task.resources
+            .iter()
+            .map(|r| rnames.iter().position(|n| *n == r).expect("BUG: unknown resource"))
+            .collect::<Vec<_>>();
+        task_resources.sort();
+
+        tasks.push(quote! {
+            ::#krate::meta::Task {
+                name: #sname,
+                priority: #priority,
+                interarrival: #interarrival,
+                deadline: #deadline,
+                wcet: #wcet,
+                resources: &[#(#task_resources),*],
+            },
+        });
+
+        tindices.push(quote! {
+            pub const #name: usize = #i;
+        });
+    }
+
+    root.push(quote! {
+        /// Description of the application; see `rtfm::meta`
+        #[allow(dead_code)]
+        #[allow(missing_docs)]
+        pub mod rtfm_meta {
+            /// All the tasks, sorted by name
+            pub const TASKS: &'static [::#krate::meta::Task] = &[#(#tasks)*];
+
+            /// All the resources, sorted by name
+            pub const RESOURCES: &'static [::#krate::meta::Resource] = &[#(#resources)*];
+
+            /// Index of each task in `TASKS`
+            #[allow(non_upper_case_globals)]
+            pub mod tasks {
+                #(#tindices)*
+            }
+
+            /// Index of each resource in `RESOURCES`
+            #[allow(non_upper_case_globals)]
+            pub mod resources {
+                #(#rindices)*
+            }
+        }
+    });
+}
@@ -4,3 +4,13 @@ MEMORY
   FLASH : ORIGIN = 0x08000000, LENGTH = 64K
   RAM : ORIGIN = 0x20000000, LENGTH = 20K
 }
+
+/* Task table generated by the `app!` macro. It's kept in the ELF for host tools
+   but it's not loaded into the device memory */
+SECTIONS
+{
+  .rtfm_meta (INFO) :
+  {
+    KEEP(*(.rtfm_meta));
+  }
+}
@@ -0,0 +1,33 @@
+//! The attribute form of the `app!` macro
+//!
+//! ``` ignore
+//! use rtfm::attr::app;
+//!
+//! #[app(device = stm32f103xx)]
+//! mod app {
+//!     use rtfm::Threshold;
+//!
+//!     static mut ON: bool = false;
+//!
+//!     #[init]
+//!     fn init(_p: init::Peripherals, _r: init::Resources) {}
+//!
+//!     #[idle]
+//!     fn idle() -> ! {
+//!         loop {
+//!             rtfm::wfi();
+//!         }
+//!     }
+//!
+//!     #[task(binds = SYS_TICK, resources = [ON])]
+//!     fn sys_tick(_t: &mut Threshold, mut r: SYS_TICK::Resources) {
+//!         *r.ON = !*r.ON;
+//!     }
+//! }
+//! ```
+//!
+//! The module is replaced with its items and the code `app!` would generate
+//! for the equivalent input. The signatures of the handlers are checked
+//! against the keys of their contexts.
+
+pub use cortex_m_rtfm_macros::app_module as app;
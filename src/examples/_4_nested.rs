@@ -6,11 +6,16 @@
 //! ```
 //! #![deny(unsafe_code)]
 //! #![deny(warnings)]
-//! #![feature(proc_macro)]
 //! #![no_std]
+//! #![no_main]
 //! 
 //! extern crate cortex_m_rtfm as rtfm;
 //! extern crate stm32f103xx;
+//! // KLEE builds use the panic handler of the `klee` crate
+//! #[cfg(feature = "klee_mode")]
+//! extern crate klee;
+//! #[cfg(not(feature = "klee_mode"))]
+//! extern crate panic_halt;
 //! 
 //! use stm32f103xx::Interrupt;
 //! use rtfm::{app, Resource, Threshold};
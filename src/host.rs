@@ -0,0 +1,461 @@
+//! Host backend: a software model of the NVIC
+//!
+//! With the `host` Cargo feature the code generated by the `app!` macro runs
+//! on the host, e.g. under `cargo test`, instead of on a Cortex-M device. The
+//! tasks are registered with a software NVIC that preempts the running context
+//! whenever a pending task has a higher priority, so `set_pending`, `claim`
+//! and `atomic` behave as they do on the hardware.
+//!
+//! ``` ignore
+//! #[test]
+//! fn preemption() {
+//!     // runs `init` and unmasks the tasks; `idle` is not run
+//!     rtfm_init();
+//!
+//!     // runs `EXTI0`, and any task it pends, to completion
+//!     rtfm::set_pending(device::Interrupt::EXTI0);
+//! }
+//! ```
+//!
+//! The `device` crate is replaced by a module that provides the items the
+//! generated code uses: `NVIC_PRIO_BITS`, an `Interrupt` enum that implements
+//! `Nr`, and `Peripherals` and `CorePeripherals` structs with a `steal`
+//! constructor.
+//!
+//! # Limitations
+//!
+//! - There's no time on the host: applications that use `schedule` or
+//!   periodic tasks are rejected.
+//! - The NVIC model is per thread but the resources are `static` variables.
+//!   Test binaries should contain a single application and either a single
+//!   test or run with `--test-threads=1`.
+//! - The exceptions are pended by hand, with `pend_exception`.
+//!
+//! # Exploring preemptions
+//!
+//! Data races between tasks only show up under particular preemptions.
+//! `explore` runs a test once per interleaving: at the entry and at the exit
+//! of every `claim` it either lets the task continue or preempts it with one
+//! of the hardware tasks that have a higher priority, until every combination
+//! has been tried.
+//!
+//! ``` ignore
+//! #[test]
+//! fn invariants() {
+//!     let schedules = rtfm::host::explore(2, || {
+//!         unsafe { rtfm_reset() };
+//!         rtfm_init();
+//!
+//!         rtfm::set_pending(device::Interrupt::EXTI0);
+//!
+//!         let r = unsafe { rtfm_resources() };
+//!         assert!(*r.PRODUCED >= *r.CONSUMED);
+//!     });
+//! }
+//! ```
+//!
+//! `rtfm_reset` restores the resources to their initial values, and
+//! `rtfm_resources` hands out references to them; the `local` resources of the
+//! tasks are not reset. When the closure panics the failing schedule is
+//! printed to `stderr`.
+
+use core::cell::RefCell;
+use core::{cmp, mem};
+use std::thread;
+use std::vec::Vec;
+
+use cortex_m::interrupt::Nr;
+
+/// Priority of the thread mode: lower than any configurable priority
+const THREAD: i16 = 256;
+
+/// System exceptions plus the maximum number of device interrupts
+const VECTORS: usize = 16 + 256;
+
+#[derive(Clone, Copy)]
+struct Vector {
+    handler: Option<unsafe extern "C" fn()>,
+    // hardware encoding: the lower the value the higher the priority
+    priority: i16,
+    enabled: bool,
+    pending: bool,
+    // hardware task, as opposed to a dispatcher of software tasks
+    task: bool,
+}
+
+struct Nvic {
+    vectors: Vec<Vector>,
+    // priority of the active exception, or `THREAD`
+    running: i16,
+    basepri: u8,
+    primask: bool,
+}
+
+impl Nvic {
+    fn new() -> Self {
+        let vector = Vector {
+            handler: None,
+            priority: 0,
+            enabled: false,
+            pending: false,
+            task: false,
+        };
+
+        Nvic {
+            vectors: vec![vector; VECTORS],
+            running: THREAD,
+            basepri: 0,
+            primask: false,
+        }
+    }
+
+    /// Priority a pending exception must exceed to preempt the current context
+    fn threshold(&self) -> i16 {
+        let mut threshold = self.running;
+        if self.basepri != 0 {
+            threshold = cmp::min(threshold, i16::from(self.basepri));
+        }
+        if self.primask {
+            threshold = cmp::min(threshold, 0);
+        }
+        threshold
+    }
+
+    /// The pending exception with the highest priority, if it can preempt
+    fn next(&self) -> Option<usize> {
+        let threshold = self.threshold();
+        let mut next: Option<usize> = None;
+        for (nr, vector) in self.vectors.iter().enumerate() {
+            if !vector.pending || !vector.enabled || vector.handler.is_none()
+                || vector.priority >= threshold
+            {
+                continue;
+            }
+
+            // ties are broken by the exception number, as on the hardware
+            if next.map(|n| vector.priority < self.vectors[n].priority).unwrap_or(true) {
+                next = Some(nr);
+            }
+        }
+        next
+    }
+
+    /// The hardware tasks that would preempt the current context if pended
+    fn eligible(&self) -> Vec<usize> {
+        let threshold = self.threshold();
+        self.vectors
+            .iter()
+            .enumerate()
+            .filter(|&(_, vector)| {
+                vector.task && vector.enabled && !vector.pending && vector.priority < threshold
+            })
+            .map(|(nr, _)| nr)
+            .collect()
+    }
+}
+
+struct Explorer {
+    // choice made at each preemption point, out of how many: `0` continues,
+    // `n` preempts with the `n`-th eligible task
+    path: Vec<(usize, usize)>,
+    // index of the next preemption point
+    point: usize,
+    // (preemption point, exception number) of the injected preemptions
+    injected: Vec<(usize, usize)>,
+    bound: usize,
+}
+
+thread_local! {
+    static NVIC_MODEL: RefCell<Nvic> = RefCell::new(Nvic::new());
+    static EXPLORER: RefCell<Option<Explorer>> = RefCell::new(None);
+}
+
+/// Services the pending exceptions that can preempt the current context
+///
+/// Handlers are called with the running priority raised to theirs; no borrow
+/// of the model is held across the call so the handlers can pend, claim and
+/// mask in turn.
+fn dispatch() {
+    loop {
+        let next = NVIC_MODEL.with(|nvic| {
+            let mut nvic = nvic.borrow_mut();
+
+            nvic.next().map(|nr| {
+                let vector = &mut nvic.vectors[nr];
+                vector.pending = false;
+
+                let (handler, priority) = (vector.handler, vector.priority);
+                let old = mem::replace(&mut nvic.running, priority);
+                (handler, old)
+            })
+        });
+
+        match next {
+            Some((Some(handler), old)) => {
+                unsafe { handler() }
+
+                NVIC_MODEL.with(|nvic| nvic.borrow_mut().running = old);
+            }
+            _ => break,
+        }
+    }
+}
+
+fn with<R, F>(f: F) -> R
+where
+    F: FnOnce(&mut Nvic) -> R,
+{
+    let r = NVIC_MODEL.with(|nvic| f(&mut nvic.borrow_mut()));
+    dispatch();
+    r
+}
+
+/// Registers `handler` as the handler of the system exception number `nr`
+///
+/// `priority` is in the hardware encoding; it's ignored for `NMI` and
+/// `HARD_FAULT`, which have fixed priorities. Exceptions are always enabled.
+pub unsafe fn register_exception(nr: usize, priority: u8, handler: unsafe extern "C" fn()) {
+    with(|nvic| {
+        nvic.vectors[nr] = Vector {
+            handler: Some(handler),
+            priority: match nr {
+                2 => -2,
+                3 => -1,
+                _ => i16::from(priority),
+            },
+            enabled: true,
+            pending: false,
+            task: true,
+        };
+    })
+}
+
+/// Registers `handler` as the handler of `interrupt`
+///
+/// `priority` is in the hardware encoding. The interrupt starts disabled.
+pub unsafe fn register_interrupt<I>(interrupt: I, priority: u8, handler: unsafe extern "C" fn())
+where
+    I: Nr,
+{
+    register(interrupt, priority, handler, true)
+}
+
+/// Registers `handler`, which dispatches software tasks, as the handler of
+/// `interrupt`
+///
+/// Unlike tasks, dispatchers are not used to preempt the application in
+/// `explore`. The interrupt starts disabled.
+pub unsafe fn register_dispatcher<I>(interrupt: I, priority: u8, handler: unsafe extern "C" fn())
+where
+    I: Nr,
+{
+    register(interrupt, priority, handler, false)
+}
+
+fn register<I>(interrupt: I, priority: u8, handler: unsafe extern "C" fn(), task: bool)
+where
+    I: Nr,
+{
+    with(|nvic| {
+        nvic.vectors[16 + usize::from(interrupt.nr())] = Vector {
+            handler: Some(handler),
+            priority: i16::from(priority),
+            enabled: false,
+            pending: false,
+            task,
+        };
+    })
+}
+
+/// Enables `interrupt`
+pub fn enable<I>(interrupt: I)
+where
+    I: Nr,
+{
+    with(|nvic| nvic.vectors[16 + usize::from(interrupt.nr())].enabled = true)
+}
+
+/// Disables `interrupt`
+pub fn disable<I>(interrupt: I)
+where
+    I: Nr,
+{
+    with(|nvic| nvic.vectors[16 + usize::from(interrupt.nr())].enabled = false)
+}
+
+/// Sets the system exception number `nr` as pending
+pub fn pend_exception(nr: usize) {
+    with(|nvic| nvic.vectors[nr].pending = true)
+}
+
+/// Stand-in for `cortex_m::peripheral::NVIC`
+pub struct NVIC {
+    _0: (),
+}
+
+impl NVIC {
+    /// Sets `interrupt` as pending
+    pub fn pend<I>(interrupt: I)
+    where
+        I: Nr,
+    {
+        with(|nvic| nvic.vectors[16 + usize::from(interrupt.nr())].pending = true)
+    }
+}
+
+/// Stand-in for `cortex_m::interrupt`: the PRIMASK of the model
+pub mod interrupt {
+    /// Masks all the exceptions but `NMI` and `HARD_FAULT`
+    pub fn disable() {
+        super::with(|nvic| nvic.primask = true)
+    }
+
+    /// Unmasks the exceptions
+    pub unsafe fn enable() {
+        super::with(|nvic| nvic.primask = false)
+    }
+}
+
+/// Stand-in for `cortex_m::register::basepri`: the BASEPRI of the model
+pub mod basepri {
+    /// Reads the BASEPRI register
+    pub fn read() -> u8 {
+        super::NVIC_MODEL.with(|nvic| nvic.borrow().basepri)
+    }
+
+    /// Writes to the BASEPRI register; `0` turns the masking off
+    pub unsafe fn write(basepri: u8) {
+        super::with(|nvic| nvic.basepri = basepri)
+    }
+}
+
+/// Runs `schedule` once for every preemption interleaving, with up to
+/// `preemptions` injected preemptions per run; returns the number of runs
+///
+/// The interleavings are explored depth first. At each preemption point, the
+/// entry and the exit of a `claim`, a run either continues or pends one of the
+/// hardware tasks that would preempt the running context. The software NVIC is
+/// reset before each run, so `schedule` must start the application with
+/// `rtfm_init` and bring the resources back to a known state, e.g. with
+/// `rtfm_reset`. The application must be deterministic: a run must reach the
+/// same preemption points as the run it replays.
+pub fn explore<F>(preemptions: usize, mut schedule: F) -> usize
+where
+    F: FnMut(),
+{
+    let mut path = vec![];
+    let mut schedules = 0;
+    loop {
+        NVIC_MODEL.with(|nvic| *nvic.borrow_mut() = Nvic::new());
+        EXPLORER.with(|explorer| {
+            *explorer.borrow_mut() = Some(Explorer {
+                path,
+                point: 0,
+                injected: vec![],
+                bound: preemptions,
+            });
+        });
+
+        {
+            let _report = Report;
+            schedule();
+        }
+        schedules += 1;
+
+        path = EXPLORER
+            .with(|explorer| explorer.borrow_mut().take())
+            .map(|explorer| explorer.path)
+            .unwrap_or_default();
+
+        // backtrack to the last preemption point with untried alternatives
+        while let Some(&(choice, alternatives)) = path.last() {
+            if choice + 1 < alternatives {
+                break;
+            }
+            path.pop();
+        }
+
+        match path.last_mut() {
+            Some(last) => last.0 += 1,
+            None => return schedules,
+        }
+    }
+}
+
+/// Preemption point of `explore`; `claim` calls it when it takes and when it
+/// releases a resource
+#[doc(hidden)]
+pub fn point() {
+    let nr = EXPLORER.with(|explorer| {
+        let mut explorer = explorer.borrow_mut();
+        let explorer = match *explorer {
+            Some(ref mut explorer) => explorer,
+            None => return None,
+        };
+
+        let eligible = if explorer.injected.len() < explorer.bound {
+            NVIC_MODEL.with(|nvic| nvic.borrow().eligible())
+        } else {
+            vec![]
+        };
+
+        let point = explorer.point;
+        explorer.point += 1;
+
+        let choice = if point < explorer.path.len() {
+            let (choice, alternatives) = explorer.path[point];
+            assert_eq!(
+                alternatives,
+                1 + eligible.len(),
+                "rtfm::host::explore: the application is not deterministic"
+            );
+            choice
+        } else {
+            explorer.path.push((0, 1 + eligible.len()));
+            0
+        };
+
+        if choice == 0 {
+            None
+        } else {
+            let nr = eligible[choice - 1];
+            explorer.injected.push((point, nr));
+            Some(nr)
+        }
+    });
+
+    if let Some(nr) = nr {
+        with(|nvic| nvic.vectors[nr].pending = true)
+    }
+}
+
+/// Prints the schedule that was running when the `explore` closure panicked
+struct Report;
+
+impl Drop for Report {
+    fn drop(&mut self) {
+        if !thread::panicking() {
+            return;
+        }
+
+        EXPLORER.with(|explorer| {
+            if let Ok(explorer) = explorer.try_borrow() {
+                if let Some(ref explorer) = *explorer {
+                    eprintln!(
+                        "rtfm::host::explore: failing schedule, as (preemption point, \
+                         exception number) pairs: {:?}",
+                        explorer.injected
+                    );
+                }
+            }
+        });
+    }
+}
+
+/// Waits for an interrupt
+///
+/// Nothing pends the tasks of the application behind its back on the host, so
+/// this parks the current thread.
+pub fn wfi() {
+    thread::park();
+}
@@ -0,0 +1,164 @@
+//! Instrumentation of the scheduling events
+//!
+//! The code generated by the `app!` macro reports the start and the end of
+//! every task, and the entry to and the exit from the critical section of
+//! every `claim`, to an implementation of `Instrument` chosen at compile time
+//! with the `instrument` key:
+//!
+//! ``` ignore
+//! app! {
+//!     device: stm32f103xx,
+//!
+//!     instrument: rtfm::instrument::Breakpoints,
+//!
+//!     // ..
+//! }
+//! ```
+//!
+//! Without the key the events are discarded (`Noop`), unless the `wcet_bkpt`
+//! or the `wcet_nop` feature is enabled, which selects `Breakpoints` or `Nops`
+//! respectively.
+//!
+//! Tasks are identified by their index in `rtfm_meta::TASKS` and resources by
+//! their index in `rtfm_meta::RESOURCES`. Claims of the internal queues of the
+//! runtime report `None` as their resource. Tasks that declare a `threshold`
+//! raise the preemption threshold once they start; that's reported by
+//! `threshold_enter` and `threshold_exit` rather than as a claim.
+
+#[cfg(feature = "host")]
+use core::cell::RefCell;
+#[cfg(feature = "host")]
+use std::vec::Vec;
+
+use cortex_m::asm;
+
+/// Receives the scheduling events of the application
+///
+/// The methods run in the context that produced the event, with the priority
+/// of that context, so they must be short and must not block. All the events
+/// are ignored by default.
+pub trait Instrument {
+    /// The task `task` starts running
+    fn task_start(_task: usize) {}
+
+    /// The task `task` ran to completion
+    fn task_end(_task: usize) {}
+
+    /// The preemption threshold has been raised to `ceiling` to access
+    /// `resource`
+    fn claim_enter(_resource: Option<usize>, _ceiling: u8) {}
+
+    /// The preemption threshold is about to be lowered after accessing
+    /// `resource`
+    fn claim_exit(_resource: Option<usize>) {}
+
+    /// The task that just started raised the preemption threshold to its
+    /// `threshold`, `ceiling`
+    fn threshold_enter(_ceiling: u8) {}
+
+    /// The preemption threshold is about to be lowered back to the priority of
+    /// the task that's ending
+    fn threshold_exit() {}
+}
+
+/// A scheduling event
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Event {
+    /// See `Instrument::task_start`
+    TaskStart(usize),
+    /// See `Instrument::task_end`
+    TaskEnd(usize),
+    /// See `Instrument::claim_enter`
+    ClaimEnter(Option<usize>, u8),
+    /// See `Instrument::claim_exit`
+    ClaimExit(Option<usize>),
+    /// See `Instrument::threshold_enter`
+    ThresholdEnter(u8),
+    /// See `Instrument::threshold_exit`
+    ThresholdExit,
+}
+
+/// Discards all the events
+pub struct Noop;
+
+impl Instrument for Noop {}
+
+/// Breakpoints at the critical sections, for the WCET measurements
+///
+/// `bkpt 1` marks the entry to a critical section and `bkpt 2` its exit. Each
+/// breakpoint is a function call, whose cost is part of the measured critical
+/// section. Without the `wcet_bkpt` or the `wcet_nop` feature, or when not
+/// compiled for ARM, e.g. on the host, the breakpoints are no-ops.
+pub struct Breakpoints;
+
+impl Instrument for Breakpoints {
+    fn claim_enter(_resource: Option<usize>, _ceiling: u8) {
+        unsafe { ::bkpt_1() }
+    }
+
+    fn claim_exit(_resource: Option<usize>) {
+        unsafe { ::bkpt_2() }
+    }
+}
+
+/// A `nop` where `Breakpoints` places its breakpoints, so that production
+/// code keeps the memory layout of the measured code
+pub struct Nops;
+
+impl Instrument for Nops {
+    fn claim_enter(_resource: Option<usize>, _ceiling: u8) {
+        asm::nop()
+    }
+
+    fn claim_exit(_resource: Option<usize>) {
+        asm::nop()
+    }
+}
+
+/// Records the events of the current thread, for host tests
+#[cfg(feature = "host")]
+pub struct Recorder;
+
+#[cfg(feature = "host")]
+thread_local! {
+    static EVENTS: RefCell<Vec<Event>> = RefCell::new(vec![]);
+}
+
+#[cfg(feature = "host")]
+impl Recorder {
+    /// Takes the events recorded so far
+    pub fn take() -> Vec<Event> {
+        EVENTS.with(|events| events.borrow_mut().drain(..).collect())
+    }
+
+    fn record(event: Event) {
+        EVENTS.with(|events| events.borrow_mut().push(event))
+    }
+}
+
+#[cfg(feature = "host")]
+impl Instrument for Recorder {
+    fn task_start(task: usize) {
+        Recorder::record(Event::TaskStart(task))
+    }
+
+    fn task_end(task: usize) {
+        Recorder::record(Event::TaskEnd(task))
+    }
+
+    fn claim_enter(resource: Option<usize>, ceiling: u8) {
+        Recorder::record(Event::ClaimEnter(resource, ceiling))
+    }
+
+    fn claim_exit(resource: Option<usize>) {
+        Recorder::record(Event::ClaimExit(resource))
+    }
+
+    fn threshold_enter(ceiling: u8) {
+        Recorder::record(Event::ThresholdEnter(ceiling))
+    }
+
+    fn threshold_exit() {
+        Recorder::record(Event::ThresholdExit)
+    }
+}
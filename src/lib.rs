@@ -37,11 +37,18 @@
 //! # Dependencies
 //!
 //! The application crate must depend on a device crate generated using
-//! [`svd2rust`] v0.12.x and the "rt" feature of that crate must be enabled. The
+//! [`svd2rust`] v0.14.x and the "rt" feature of that crate must be enabled. The
 //! SVD file used to generate the device crate *must* contain [`<cpu>`]
 //! information.
 //!
-//! [`svd2rust`]: https://docs.rs/svd2rust/0.12.0/svd2rust/
+//! This crate builds on stable Rust, 1.36 or newer. The `app!` macro defines
+//! the `main` function the reset handler of [`cortex-m-rt`] calls, so the
+//! application must be `#![no_main]`; it must also link a panic handler, e.g.
+//! [`panic-halt`].
+//!
+//! [`svd2rust`]: https://docs.rs/svd2rust/0.14.0/svd2rust/
+//! [`cortex-m-rt`]: https://docs.rs/cortex-m-rt/0.6.0/cortex_m_rt/
+//! [`panic-halt`]: https://docs.rs/panic-halt/0.2.0/panic_halt/
 //! [`<cpu>`]: https://www.keil.com/pack/doc/CMSIS/SVD/html/elem_cpu.html
 //!
 //! # `app!`
@@ -50,6 +57,12 @@
 //!
 //! [here]: https://docs.rs/cortex-m-rtfm-macros/0.3.0/cortex_m_rtfm_macros/fn.app.html
 //!
+//! # Host backend
+//!
+//! With the `host` Cargo feature the application runs on the host, against a
+//! software model of the NVIC, so its tasks can be exercised with `cargo
+//! test`. See the [`host`](./host/index.html) module.
+//!
 //! # Important: Cortex-M7 devices
 //!
 //! If targeting a Cortex-M7 device with revision r0p1 then you MUST enable the `cm7-r0p1` Cargo
@@ -79,29 +92,81 @@
 //! [rtfm]: http://www.diva-portal.org/smash/get/diva2:1005680/FULLTEXT01.pdf
 #![deny(missing_docs)]
 #![deny(warnings)]
-#![feature(proc_macro)]
-#![feature(asm)]
 #![no_std]
 
 extern crate cortex_m;
 extern crate cortex_m_rtfm_macros;
 extern crate rtfm_core;
-extern crate untagged_option;
+#[cfg(feature = "host")]
+#[macro_use]
+extern crate std;
 
-use core::{mem, u8};
+use core::u8;
 
-pub use cortex_m::asm::{bkpt, nop, wfi};
+pub use cortex_m::asm::{bkpt, nop};
+#[cfg(not(feature = "host"))]
+pub use cortex_m::asm::wfi;
+#[cfg(feature = "host")]
+pub use host::wfi;
 pub use cortex_m_rtfm_macros::app;
+pub use instrument::Instrument;
 pub use rtfm_core::{Resource, Threshold};
+pub use tq::{Duration, Instant};
+pub use units::{UnitResource, Units};
+#[doc(hidden)]
+pub use units::claim_units;
 #[doc(hidden)]
-pub use untagged_option::UntaggedOption;
+pub use queue::Queue;
+#[doc(hidden)]
+pub use core::mem::MaybeUninit;
 
-use cortex_m::interrupt::{self, Nr};
+use cortex_m::interrupt::Nr;
+#[cfg(not(feature = "host"))]
+use cortex_m::interrupt;
+#[cfg(not(feature = "host"))]
 use cortex_m::peripheral::NVIC;
-#[cfg(not(armv6m))]
+#[cfg(all(not(armv6m), not(feature = "host")))]
 use cortex_m::register::basepri;
+#[cfg(feature = "host")]
+use host::{basepri, interrupt, NVIC};
 
+pub mod attr;
 pub mod examples;
+#[cfg(feature = "host")]
+pub mod host;
+pub mod instrument;
+pub mod meta;
+mod queue;
+#[cfg(feature = "trace")]
+pub mod trace;
+#[doc(hidden)]
+pub mod tq;
+mod units;
+
+/// Registers stacked (pushed onto the stack) by the hardware on exception entry
+///
+/// Same layout as `cortex_m_rt::ExceptionFrame`; the handler of a `HARD_FAULT`
+/// task receives it from `cortex-m-rt`.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct ExceptionFrame {
+    /// General purpose register 0
+    pub r0: u32,
+    /// General purpose register 1
+    pub r1: u32,
+    /// General purpose register 2
+    pub r2: u32,
+    /// General purpose register 3
+    pub r3: u32,
+    /// General purpose register 12
+    pub r12: u32,
+    /// Link register
+    pub lr: u32,
+    /// Program counter
+    pub pc: u32,
+    /// Program status register
+    pub xpsr: u32,
+}
 
 /// Executes the closure `f` in a preemption free context
 ///
@@ -124,84 +189,157 @@ where
     }
 }
 
+/// Runs `f` with `data` once the preemption threshold has been raised to
+/// `ceiling`
+///
+/// `resource` is the index of the resource in `rtfm_meta::RESOURCES`, or `None`
+/// for the queues of the runtime; it's only reported to the instrumentation
+/// `I`, along with the critical section.
 #[inline]
 #[doc(hidden)]
-pub unsafe fn claim<T, R, F>(
+pub unsafe fn claim<I, T, R, F>(
     data: T,
+    resource: Option<usize>,
     ceiling: u8,
-    _nvic_prio_bits: u8,
+    nvic_prio_bits: u8,
     t: &mut Threshold,
     f: F,
 ) -> R
 where
+    I: Instrument,
     F: FnOnce(T, &mut Threshold) -> R,
 {
-    if ceiling > t.value() {
-        match () {
-            #[cfg(armv6m)]
-            () => atomic(t, |t| f(data, t)),
-
-            #[cfg(not(armv6m))]
-            () => {
-                let max_priority = 1 << _nvic_prio_bits;
-
-                if ceiling == max_priority {
-                    atomic(t, |t| f(data, t))
-                } else {
-                    let mut old = 0;
-                    // klee mode code generation
-                    // the generated code should not access the hardware
-                    if !cfg!(feature = "klee_mode") {
-                        old = basepri::read();
-                    }
-                    let hw = (max_priority - ceiling) << (8 - _nvic_prio_bits);
-
-                    // klee mode code generation
-                    // the generated code should not access the hardware
-                    if !cfg!(feature = "klee_mode") {
-                        basepri::write(hw);
-                    }
-
-                    // wcet_bkpt mode
-                    // put breakpoint at raise ceiling, for tracing execution time
-                    if cfg!(feature = "wcet_bkpt") {
-                        bkpt_1();
-                    }
-
-                    // wcet_nop mode
-                    // leave nop in the production code, to keep memory layout the same
-                    if cfg!(feature = "wcet_nop") {
-                        nop();
-                    }
-
-                    let ret = f(data, &mut Threshold::new(ceiling));
-
-                    // wcet_bkpt mode
-                    // put breakpoint at lower ceiling, for tracing execution time
-                    if cfg!(feature = "wcet_bkpt") {
-                        bkpt_2();
-                    }
-
-                    // wcet_nop mode
-                    // leave nop in the production code, to keep memory layout the same
-                    if cfg!(feature = "wcet_nop") {
-                        nop();
-                    }
-
-                    // klee mode code generation
-                    // the generated code should not access the hardware
-                    if !cfg!(feature = "klee_mode") {
-                        basepri::write(old);
-                    }
-                    ret
+    // preemption points of `host::explore`
+    #[cfg(feature = "host")]
+    host::point();
+
+    let r = if ceiling > t.value() {
+        raise(ceiling, nvic_prio_bits, t, |t| {
+            critical::<I, _, _, _>(data, resource, ceiling, t, f)
+        })
+    } else {
+        f(data, t)
+    };
+
+    #[cfg(feature = "host")]
+    host::point();
+
+    r
+}
+
+/// Runs the body `f` of a task that declares a `threshold` with the
+/// preemption threshold raised to it
+///
+/// `t` is the threshold the task starts with, its priority. The raise is
+/// reported to the instrumentation `I` by `threshold_enter` and
+/// `threshold_exit`.
+#[inline]
+#[doc(hidden)]
+pub unsafe fn threshold<I, R, F>(
+    threshold: u8,
+    nvic_prio_bits: u8,
+    t: &mut Threshold,
+    f: F,
+) -> R
+where
+    I: Instrument,
+    F: FnOnce(&mut Threshold) -> R,
+{
+    // preemption points of `host::explore`
+    #[cfg(feature = "host")]
+    host::point();
+
+    let r = raise(threshold, nvic_prio_bits, t, |t| {
+        I::threshold_enter(threshold);
+        let r = f(t);
+        I::threshold_exit();
+        r
+    });
+
+    #[cfg(feature = "host")]
+    host::point();
+
+    r
+}
+
+/// Runs `f` with the preemption threshold raised to `ceiling`, which must be
+/// greater than the current threshold `t`
+#[inline(always)]
+unsafe fn raise<R, F>(ceiling: u8, _nvic_prio_bits: u8, t: &mut Threshold, f: F) -> R
+where
+    F: FnOnce(&mut Threshold) -> R,
+{
+    match () {
+        #[cfg(armv6m)]
+        () => atomic(t, f),
+
+        #[cfg(not(armv6m))]
+        () => {
+            let max_priority = 1 << _nvic_prio_bits;
+
+            if ceiling == max_priority {
+                atomic(t, f)
+            } else {
+                let mut old = 0;
+                // klee mode code generation
+                // the generated code should not access the hardware
+                if !cfg!(feature = "klee_mode") {
+                    old = basepri::read();
                 }
+                let hw = (max_priority - ceiling) << (8 - _nvic_prio_bits);
+
+                // klee mode code generation
+                // the generated code should not access the hardware
+                if !cfg!(feature = "klee_mode") {
+                    basepri::write(hw);
+                }
+
+                let ret = f(&mut Threshold::new(ceiling));
+
+                // klee mode code generation
+                // the generated code should not access the hardware
+                if !cfg!(feature = "klee_mode") {
+                    basepri::write(old);
+                }
+                ret
             }
         }
-    } else {
-        f(data, t)
     }
 }
 
+/// The critical section of a claim, delimited by the `claim_enter` and
+/// `claim_exit` events of `I`
+#[inline(always)]
+fn critical<I, T, R, F>(data: T, resource: Option<usize>, ceiling: u8, t: &mut Threshold, f: F) -> R
+where
+    I: Instrument,
+    F: FnOnce(T, &mut Threshold) -> R,
+{
+    I::claim_enter(resource, ceiling);
+    let r = f(data, t);
+    I::claim_exit(resource);
+    r
+}
+
+/// A resource the task has read-only access to (`resources: [&$RESOURCE]`)
+///
+/// Readers don't block each other: `claim` only raises the preemption threshold
+/// to the highest priority among the tasks that *write* to the resource. As
+/// tasks of different priorities may hold references to the data at the same
+/// time the data must be `Sync`.
+pub unsafe trait ReadOnlyResource {
+    /// The data protected by the resource
+    type Data: Sync;
+
+    /// Borrows the resource data for the duration of a critical section
+    fn borrow<'cs>(&'cs self, t: &'cs Threshold) -> &'cs Self::Data;
+
+    /// Claims the resource data for the span of the closure `f`
+    fn claim<R, F>(&self, t: &mut Threshold, f: F) -> R
+    where
+        F: FnOnce(&Self::Data, &mut Threshold) -> R;
+}
+
 /// Sets an interrupt, that is a task, as pending
 ///
 /// If the task priority is high enough the task will be serviced immediately,
@@ -211,39 +349,55 @@ where
     I: Nr,
 {
     // NOTE(safe) atomic write
-    let mut nvic: NVIC = unsafe { mem::transmute(()) };
-    nvic.set_pending(interrupt);
+    NVIC::pend(interrupt);
+}
+
+// `bkpt` with an immediate, defined in `asm.s`, as inline assembly is not available on stable;
+// the build script only assembles it in the `wcet_bkpt` and `wcet_nop` modes
+#[cfg(rtfm_bkpt)]
+extern "C" {
+    fn __rtfm_bkpt_1();
+    fn __rtfm_bkpt_2();
+    fn __rtfm_bkpt_3();
 }
 
 /// breakpoints with immediate field set, used for wcet analysis
+///
+/// The breakpoint is a call to a `bkpt #1; bx lr` function, so the measured
+/// times include the call and the return, a few cycles. A no-op unless the
+/// `wcet_bkpt` or the `wcet_nop` feature is enabled and compiled for ARM.
 #[inline]
 pub unsafe fn bkpt_1() {
     match () {
-        #[cfg(target_arch = "arm")]
-        () => asm!("bkpt #0x01" ::: "memory" : "volatile"),
-        #[cfg(not(target_arch = "arm"))]
-        () => unimplemented!(),
+        #[cfg(rtfm_bkpt)]
+        () => __rtfm_bkpt_1(),
+        #[cfg(not(rtfm_bkpt))]
+        () => {}
     }
 }
 
 /// breakpoints with immediate field set, used for wcet analysis
+///
+/// See `bkpt_1`.
 #[inline]
 pub unsafe fn bkpt_2() {
     match () {
-        #[cfg(target_arch = "arm")]
-        () => asm!("bkpt #0x02" ::: "memory" : "volatile"),
-        #[cfg(not(target_arch = "arm"))]
-        () => unimplemented!(),
+        #[cfg(rtfm_bkpt)]
+        () => __rtfm_bkpt_2(),
+        #[cfg(not(rtfm_bkpt))]
+        () => {}
     }
 }
 
 /// breakpoints with immediate field set, used for wcet analysis
+///
+/// See `bkpt_1`.
 #[inline]
 pub unsafe fn bkpt_3() {
     match () {
-        #[cfg(target_arch = "arm")]
-        () => asm!("bkpt #0x03" ::: "memory" : "volatile"),
-        #[cfg(not(target_arch = "arm"))]
-        () => unimplemented!(),
+        #[cfg(rtfm_bkpt)]
+        () => __rtfm_bkpt_3(),
+        #[cfg(not(rtfm_bkpt))]
+        () => {}
     }
 }
@@ -0,0 +1,56 @@
+//! Description of the application generated by the `app!` macro
+//!
+//! The `app!` macro generates a `rtfm_meta` module in the root of the crate
+//! that contains the following items:
+//!
+//! ``` ignore
+//! mod rtfm_meta {
+//!     /// All the tasks, sorted by name
+//!     pub const TASKS: &'static [rtfm::meta::Task] = &[..];
+//!
+//!     /// All the resources, sorted by name
+//!     pub const RESOURCES: &'static [rtfm::meta::Resource] = &[..];
+//!
+//!     /// Index of each task in `TASKS`
+//!     pub mod tasks {
+//!         pub const EXTI0: usize = 0;
+//!         // ..
+//!     }
+//!
+//!     /// Index of each resource in `RESOURCES`
+//!     pub mod resources {
+//!         pub const SHARED: usize = 0;
+//!         // ..
+//!     }
+//! }
+//! ```
+//!
+//! The tables can be used to introspect the application, e.g. from tests,
+//! loggers or on-target monitors, without duplicating the contents of `app!`.
+
+/// A task
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Task {
+    /// Name of the task; this is the name of the exception or interrupt
+    pub name: &'static str,
+    /// Priority of the task
+    pub priority: u8,
+    /// Minimum time between two consecutive releases of the task, in clock
+    /// cycles (`1` if not declared)
+    pub interarrival: u32,
+    /// Relative deadline of the task, in clock cycles
+    pub deadline: u32,
+    /// Worst case execution time of the task, in clock cycles, if declared
+    pub wcet: Option<u32>,
+    /// Resources the task has access to, as indices into `rtfm_meta::RESOURCES`
+    pub resources: &'static [usize],
+}
+
+/// A resource
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Resource {
+    /// Name of the resource
+    pub name: &'static str,
+    /// Ceiling of the resource; `None` if no task or `idle` uses it
+    pub ceiling: Option<u8>,
+}
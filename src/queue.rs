@@ -0,0 +1,55 @@
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::ptr;
+
+/// Fixed capacity FIFO used by the software task machinery
+///
+/// `A` is the backing storage, an array of `capacity` elements of type `T`.
+/// The queue doesn't synchronize itself; it must only be accessed from a
+/// critical section at (or above) its ceiling. The fields are public so that
+/// queues can be initialized in `static` variables.
+#[doc(hidden)]
+pub struct Queue<T, A> {
+    /// Backing storage
+    pub buffer: MaybeUninit<A>,
+    /// Index of the oldest element
+    pub head: usize,
+    /// Number of elements in the queue
+    pub len: usize,
+    /// Number of elements `A` can hold
+    pub capacity: usize,
+    /// The element type
+    pub _marker: PhantomData<T>,
+}
+
+impl<T, A> Queue<T, A> {
+    /// Adds `item` at the end of the queue; returns it back if the queue is full
+    pub fn enqueue(&mut self, item: T) -> Result<(), T> {
+        if self.len == self.capacity {
+            return Err(item);
+        }
+
+        let tail = (self.head + self.len) % self.capacity;
+        unsafe { ptr::write(self.slot(tail), item) }
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /// Removes the oldest element from the queue
+    pub fn dequeue(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let item = unsafe { ptr::read(self.slot(self.head)) };
+        self.head = (self.head + 1) % self.capacity;
+        self.len -= 1;
+
+        Some(item)
+    }
+
+    fn slot(&mut self, i: usize) -> *mut T {
+        unsafe { (&mut self.buffer as *mut MaybeUninit<A> as *mut T).offset(i as isize) }
+    }
+}
@@ -0,0 +1,212 @@
+//! Timer queue used by the `schedule` API
+//!
+//! Time is measured in clock cycles by the cycle counter (CYCCNT) of the DWT;
+//! the `SYS_TICK` exception wakes up the queue when its earliest entry is due.
+//! The cycle counter is not available on ARMv6-M devices.
+
+use core::cmp::{self, Ordering};
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+use core::{ops, ptr};
+
+use cortex_m::peripheral::{DCB, DWT, SCB, SYST};
+
+/// A reading of the cycle counter
+///
+/// The counter wraps around so two instants can only be compared if they are
+/// less than `2^31` clock cycles apart.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Instant(u32);
+
+impl Instant {
+    /// Reads the cycle counter
+    pub fn now() -> Self {
+        Instant(unsafe { (*DWT::ptr()).cyccnt.read() })
+    }
+
+    /// Returns the time elapsed since `earlier`; zero if `earlier` is actually
+    /// later than `self`
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        let diff = self.0.wrapping_sub(earlier.0) as i32;
+
+        Duration(if diff > 0 { diff as u32 } else { 0 })
+    }
+}
+
+impl Ord for Instant {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.0.wrapping_sub(other.0) as i32).cmp(&0)
+    }
+}
+
+impl PartialOrd for Instant {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl ops::Add<Duration> for Instant {
+    type Output = Self;
+
+    fn add(self, duration: Duration) -> Self {
+        Instant(self.0.wrapping_add(duration.0))
+    }
+}
+
+impl ops::Sub<Duration> for Instant {
+    type Output = Self;
+
+    fn sub(self, duration: Duration) -> Self {
+        Instant(self.0.wrapping_sub(duration.0))
+    }
+}
+
+/// A span of time, in clock cycles
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Duration(u32);
+
+impl Duration {
+    /// A span of `cycles` clock cycles
+    pub fn from_cycles(cycles: u32) -> Self {
+        Duration(cycles)
+    }
+
+    /// Returns the length of this span in clock cycles
+    pub fn as_cycles(&self) -> u32 {
+        self.0
+    }
+}
+
+impl ops::Add for Duration {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Duration(self.0 + other.0)
+    }
+}
+
+/// Entry of the timer queue: the message in slot `index` of the free queue of
+/// `task` is released at `instant`
+#[doc(hidden)]
+#[derive(Clone, Copy)]
+pub struct NotReady<T> {
+    pub instant: Instant,
+    pub task: T,
+    pub index: u8,
+}
+
+/// Fixed capacity queue of `NotReady` entries sorted by instant
+///
+/// `A` is the backing storage, an array of `capacity` `NotReady<T>` elements.
+/// Like `Queue` this doesn't synchronize itself and its fields are public so
+/// it can be initialized in a `static` variable.
+#[doc(hidden)]
+pub struct TimerQueue<T, A> {
+    /// Backing storage
+    pub buffer: MaybeUninit<A>,
+    /// Number of entries in the queue
+    pub len: usize,
+    /// Number of entries `A` can hold
+    pub capacity: usize,
+    /// The task type
+    pub _marker: PhantomData<T>,
+}
+
+impl<T, A> TimerQueue<T, A> {
+    /// Inserts `entry` in the queue; returns whether `entry` is now the
+    /// earliest entry or gives it back if the queue is full
+    ///
+    /// Entries released at the same instant are kept in insertion order.
+    pub fn enqueue(&mut self, entry: NotReady<T>) -> Result<bool, NotReady<T>> {
+        if self.len == self.capacity {
+            return Err(entry);
+        }
+
+        let mut i = self.len;
+        while i > 0 && self.get(i - 1).instant > entry.instant {
+            i -= 1;
+        }
+
+        unsafe {
+            let slot = self.slot(i);
+            ptr::copy(slot, slot.offset(1), self.len - i);
+            ptr::write(slot, entry);
+        }
+        self.len += 1;
+
+        Ok(i == 0)
+    }
+
+    /// Removes the earliest entry from the queue if it's due at `now`
+    pub fn dequeue(&mut self, now: Instant) -> Option<NotReady<T>> {
+        if self.len == 0 || self.get(0).instant > now {
+            return None;
+        }
+
+        self.len -= 1;
+        unsafe {
+            let slot = self.slot(0);
+            let entry = ptr::read(slot);
+            ptr::copy(slot.offset(1), slot, self.len);
+
+            Some(entry)
+        }
+    }
+
+    /// Returns the instant of the earliest entry
+    pub fn next(&self) -> Option<Instant> {
+        if self.len == 0 {
+            None
+        } else {
+            Some(self.get(0).instant)
+        }
+    }
+
+    fn get(&self, i: usize) -> &NotReady<T> {
+        unsafe {
+            &*(&self.buffer as *const MaybeUninit<A> as *const NotReady<T>).offset(i as isize)
+        }
+    }
+
+    fn slot(&mut self, i: usize) -> *mut NotReady<T> {
+        unsafe {
+            (&mut self.buffer as *mut MaybeUninit<A> as *mut NotReady<T>).offset(i as isize)
+        }
+    }
+}
+
+/// Starts the cycle counter
+#[doc(hidden)]
+pub unsafe fn enable() {
+    // TRCENA
+    (*DCB::ptr()).demcr.modify(|r| r | (1 << 24));
+    // CYCCNTENA
+    (*DWT::ptr()).ctrl.modify(|r| r | 1);
+}
+
+/// Programs `SYS_TICK` to fire at `instant`, or as close to it as its 24-bit
+/// counter allows; the handler re-arms the timer if it fires early
+#[doc(hidden)]
+pub unsafe fn arm(instant: Instant) {
+    let cycles = instant.duration_since(Instant::now()).as_cycles();
+
+    let syst = &*SYST::ptr();
+    syst.rvr.write(cmp::max(cmp::min(cycles, 0x00ff_ffff), 1));
+    syst.cvr.write(0);
+    // processor clock, interrupt and counter enabled
+    syst.csr.write(0b111);
+}
+
+/// Stops `SYS_TICK`
+#[doc(hidden)]
+pub unsafe fn disarm() {
+    (*SYST::ptr()).csr.write(0);
+}
+
+/// Sets `SYS_TICK` as pending so that the timer is re-armed for the earliest
+/// entry of the queue
+#[doc(hidden)]
+pub fn pend() {
+    // NOTE(safe) atomic write; PENDSTSET
+    unsafe { (*SCB::ptr()).icsr.write(1 << 26) }
+}
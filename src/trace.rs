@@ -0,0 +1,173 @@
+//! Event trace: a ring buffer of the scheduling events
+//!
+//! `Trace` is an implementation of `Instrument` that logs the start and the end
+//! of every task, and the critical sections of the claims, into the
+//! `RTFM_TRACE` ring buffer, timestamped with the cycle counter (CYCCNT) of
+//! the DWT. Select it with the `instrument` key and start the cycle counter
+//! from `init`:
+//!
+//! ``` ignore
+//! app! {
+//!     device: stm32f103xx,
+//!
+//!     instrument: rtfm::trace::Trace,
+//!
+//!     // ..
+//! }
+//!
+//! fn init(_p: init::Peripherals) {
+//!     unsafe { rtfm::trace::enable() }
+//! }
+//! ```
+//!
+//! The buffer keeps the last `CAPACITY` events. To inspect them halt the
+//! device, dump the buffer and decode it with the `rtfm-trace` tool of the
+//! `rtfm-tools` crate:
+//!
+//! ``` text
+//! (gdb) dump binary value trace.bin RTFM_TRACE
+//! $ rtfm-trace trace.bin
+//! ```
+//!
+//! The layout of the buffer, with all the integers in little endian, is:
+//!
+//! ``` text
+//! header:  MAGIC:u32 VERSION:u32 CAPACITY:u32 COUNT:u32
+//! entry:   TIMESTAMP:u32 KIND:u8 CEILING:u8 ID:u16       (CAPACITY times)
+//! ```
+//!
+//! `COUNT` is the number of events logged so far; event `n` is stored in entry
+//! `n % CAPACITY`. `KIND` is one of the `TASK_START`, `TASK_END`,
+//! `CLAIM_ENTER`, `CLAIM_EXIT`, `THRESHOLD_ENTER` and `THRESHOLD_EXIT`
+//! constants, `ID` the index of the task in `rtfm_meta::TASKS` or of the
+//! resource in `rtfm_meta::RESOURCES` (`NONE` for the queues of the runtime,
+//! and for the threshold events) and `CEILING` is only used by `CLAIM_ENTER`
+//! and `THRESHOLD_ENTER`.
+//!
+//! The cycle counter is not available on ARMv6-M devices; the timestamps are
+//! zero there.
+
+use cortex_m::interrupt;
+use cortex_m::peripheral::DWT;
+
+use instrument::Instrument;
+
+/// Identifies the buffer in a memory dump
+pub const MAGIC: u32 = 0x5452_5452;
+
+/// Version of the buffer layout
+pub const VERSION: u32 = 2;
+
+/// Number of events the buffer holds
+pub const CAPACITY: usize = 256;
+
+/// `KIND` of an `Instrument::task_start` event
+pub const TASK_START: u8 = 1;
+/// `KIND` of an `Instrument::task_end` event
+pub const TASK_END: u8 = 2;
+/// `KIND` of an `Instrument::claim_enter` event
+pub const CLAIM_ENTER: u8 = 3;
+/// `KIND` of an `Instrument::claim_exit` event
+pub const CLAIM_EXIT: u8 = 4;
+/// `KIND` of an `Instrument::threshold_enter` event
+pub const THRESHOLD_ENTER: u8 = 5;
+/// `KIND` of an `Instrument::threshold_exit` event
+pub const THRESHOLD_EXIT: u8 = 6;
+
+/// `ID` of the claims of the queues of the runtime and of the threshold events
+pub const NONE: u16 = 0xffff;
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Entry {
+    timestamp: u32,
+    kind: u8,
+    ceiling: u8,
+    id: u16,
+}
+
+#[repr(C)]
+struct Buffer {
+    magic: u32,
+    version: u32,
+    capacity: u32,
+    count: u32,
+    entries: [Entry; CAPACITY],
+}
+
+#[no_mangle]
+static mut RTFM_TRACE: Buffer = Buffer {
+    magic: MAGIC,
+    version: VERSION,
+    capacity: CAPACITY as u32,
+    count: 0,
+    entries: [Entry {
+        timestamp: 0,
+        kind: 0,
+        ceiling: 0,
+        id: 0,
+    }; CAPACITY],
+};
+
+/// Logs the scheduling events into the `RTFM_TRACE` buffer
+pub struct Trace;
+
+impl Instrument for Trace {
+    fn task_start(task: usize) {
+        log(TASK_START, task as u16, 0)
+    }
+
+    fn task_end(task: usize) {
+        log(TASK_END, task as u16, 0)
+    }
+
+    fn claim_enter(resource: Option<usize>, ceiling: u8) {
+        log(CLAIM_ENTER, resource.map(|r| r as u16).unwrap_or(NONE), ceiling)
+    }
+
+    fn claim_exit(resource: Option<usize>) {
+        log(CLAIM_EXIT, resource.map(|r| r as u16).unwrap_or(NONE), 0)
+    }
+
+    fn threshold_enter(ceiling: u8) {
+        log(THRESHOLD_ENTER, NONE, ceiling)
+    }
+
+    fn threshold_exit() {
+        log(THRESHOLD_EXIT, NONE, 0)
+    }
+}
+
+fn log(kind: u8, id: u16, ceiling: u8) {
+    // a task that preempts this one must not take the same entry
+    interrupt::free(|_| unsafe {
+        let timestamp = if cfg!(armv6m) {
+            0
+        } else {
+            (*DWT::ptr()).cyccnt.read()
+        };
+
+        let index = RTFM_TRACE.count as usize % CAPACITY;
+        RTFM_TRACE.entries[index] = Entry {
+            timestamp,
+            kind,
+            ceiling,
+            id,
+        };
+        RTFM_TRACE.count = RTFM_TRACE.count.wrapping_add(1);
+    })
+}
+
+/// Starts the cycle counter that timestamps the events
+///
+/// This is done by the runtime if the application uses the timer queue.
+pub unsafe fn enable() {
+    if !cfg!(armv6m) {
+        ::tq::enable();
+    }
+}
+
+/// Discards the events logged so far
+pub fn clear() {
+    interrupt::free(|_| unsafe { RTFM_TRACE.count = 0 })
+}
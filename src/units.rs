@@ -0,0 +1,209 @@
+use core::marker::PhantomData;
+use core::u8;
+
+#[cfg(not(feature = "host"))]
+use cortex_m::interrupt;
+#[cfg(all(not(armv6m), not(feature = "host")))]
+use cortex_m::register::basepri;
+use rtfm_core::Threshold;
+
+use critical;
+#[cfg(feature = "host")]
+use host::{self, basepri, interrupt};
+use instrument::Instrument;
+
+/// A multi-unit resource: a pool of interchangeable units (`units` key)
+///
+/// Under the Stack Resource Policy the ceiling of a multi-unit resource depends
+/// on how many of its units are available; claiming units raises the
+/// preemption threshold only enough to exclude the tasks that may need more
+/// units than the ones left.
+pub unsafe trait UnitResource {
+    /// The type of a single unit
+    type Unit;
+
+    /// Claims `n` units of the resource for the span of the closure `f`
+    ///
+    /// Panics if `n` is greater than the number of units the task declared it
+    /// holds at once.
+    fn claim_units<R, F>(&mut self, t: &mut Threshold, n: usize, f: F) -> R
+    where
+        F: FnOnce(&mut Units<Self::Unit>, &mut Threshold) -> R;
+}
+
+/// The units held by a `UnitResource::claim_units` closure
+pub struct Units<'a, T>
+where
+    T: 'a,
+{
+    base: *mut T,
+    taken: u32,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Units<'a, T> {
+    /// Number of units held
+    pub fn len(&self) -> usize {
+        self.taken.count_ones() as usize
+    }
+
+    /// Whether no unit is held
+    pub fn is_empty(&self) -> bool {
+        self.taken == 0
+    }
+
+    /// Returns the `i`-th unit held
+    pub fn get(&self, i: usize) -> Option<&T> {
+        self.index(i).map(|j| unsafe { &*self.base.offset(j as isize) })
+    }
+
+    /// Returns the `i`-th unit held
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        self.index(i).map(|j| unsafe { &mut *self.base.offset(j as isize) })
+    }
+
+    /// Index, in the pool, of the `i`-th unit held
+    fn index(&self, i: usize) -> Option<usize> {
+        (0..32).filter(|j| self.taken & (1 << j) != 0).nth(i)
+    }
+}
+
+/// Takes `n` units from the pool that starts at `base`
+///
+/// `free` is the bitmask of the available units and `ceilings[k]` is the
+/// ceiling of the resource while `k` units are available. `max` is the number
+/// of units the calling task declared it holds at once. `resource` is reported
+/// to the instrumentation `I`, see `claim`.
+#[doc(hidden)]
+pub unsafe fn claim_units<I, T, R, F>(
+    base: *mut T,
+    free: *mut u32,
+    resource: Option<usize>,
+    n: usize,
+    max: usize,
+    ceilings: &[u8],
+    _nvic_prio_bits: u8,
+    t: &mut Threshold,
+    f: F,
+) -> R
+where
+    I: Instrument,
+    F: FnOnce(&mut Units<T>, &mut Threshold) -> R,
+{
+    assert!(n <= max);
+
+    // preemption points of `host::explore`
+    #[cfg(feature = "host")]
+    host::point();
+
+    // other tasks may take units of the pool while we hold ours, and the
+    // ceiling depends on how many units are left: the units are taken, and
+    // the threshold raised to that ceiling, in a single global critical
+    // section; likewise the units are returned before the threshold drops
+    let nested = t.value() == u8::MAX;
+    disable(nested);
+
+    let mut taken = 0;
+    for _ in 0..n {
+        let left = *free & !taken;
+        // can't happen if the SRP ceilings are respected
+        assert!(left != 0);
+        taken |= left & left.wrapping_neg();
+    }
+    *free &= !taken;
+
+    let ceiling = ceilings[(*free).count_ones() as usize];
+    let mut units = Units {
+        base,
+        taken,
+        _marker: PhantomData,
+    };
+
+    let r = if ceiling <= t.value() {
+        enable(nested);
+        let r = f(&mut units, t);
+        disable(nested);
+        *free |= taken;
+        enable(nested);
+        r
+    } else {
+        match () {
+            #[cfg(armv6m)]
+            () => {
+                let r = critical::<I, _, _, _>(
+                    &mut units,
+                    resource,
+                    ceiling,
+                    &mut Threshold::max(),
+                    |units, t| f(units, t),
+                );
+                *free |= taken;
+                enable(nested);
+                r
+            }
+
+            #[cfg(not(armv6m))]
+            () => {
+                let max_priority = 1 << _nvic_prio_bits;
+
+                if ceiling == max_priority {
+                    let r = critical::<I, _, _, _>(
+                        &mut units,
+                        resource,
+                        ceiling,
+                        &mut Threshold::max(),
+                        |units, t| f(units, t),
+                    );
+                    *free |= taken;
+                    enable(nested);
+                    r
+                } else {
+                    let mut old = 0;
+                    // klee mode code generation
+                    // the generated code should not access the hardware
+                    if !cfg!(feature = "klee_mode") {
+                        old = basepri::read();
+                        basepri::write((max_priority - ceiling) << (8 - _nvic_prio_bits));
+                    }
+                    enable(nested);
+
+                    let r = critical::<I, _, _, _>(
+                        &mut units,
+                        resource,
+                        ceiling,
+                        &mut Threshold::new(ceiling),
+                        |units, t| f(units, t),
+                    );
+
+                    disable(nested);
+                    *free |= taken;
+                    if !cfg!(feature = "klee_mode") {
+                        basepri::write(old);
+                    }
+                    enable(nested);
+                    r
+                }
+            }
+        }
+    };
+
+    #[cfg(feature = "host")]
+    host::point();
+
+    r
+}
+
+/// Enters a global critical section, unless already `nested` in one
+unsafe fn disable(nested: bool) {
+    // the generated code should not access the hardware in klee mode
+    if !nested && !cfg!(feature = "klee_mode") {
+        interrupt::disable();
+    }
+}
+
+/// Leaves the global critical section entered by `disable`
+unsafe fn enable(nested: bool) {
+    if !nested && !cfg!(feature = "klee_mode") {
+        interrupt::enable();
+    }
+}
@@ -0,0 +1,217 @@
+//! Syntax checking pass
+
+use std::collections::{HashMap, HashSet};
+
+use syn::{Ident, Path, Ty};
+
+use error::*;
+use {CriticalSections, Interrupts, Overheads, Priority, Resources, Spawn, Statics, Units};
+
+/// `app!` with optional fields filled with their default values
+#[derive(Debug)]
+pub struct App {
+    /// The device crate
+    pub device: Path,
+    /// Interrupts used to dispatch software tasks
+    pub free_interrupts: Interrupts,
+    /// `idle` configuration
+    pub idle: Idle,
+    /// `init` configuration
+    pub init: Init,
+    /// Implementation of `rtfm::Instrument` that receives the scheduling events
+    pub instrument: Option<Path>,
+    /// Number of priority bits of the device
+    pub nvic_prio_bits: Option<u8>,
+    /// Execution times of the runtime; unknown if not declared
+    pub overheads: Overheads,
+    /// Resources
+    pub resources: Statics,
+    /// Tasks
+    pub tasks: Tasks,
+    _extensible: (),
+}
+
+/// `idle` configuration
+#[derive(Debug)]
+pub struct Idle {
+    /// Length of the longest critical section on each resource
+    pub critical_sections: CriticalSections,
+    /// Path to the `idle` function
+    pub path: Path,
+    /// Resources, among `resources`, that `idle` only reads
+    pub read_only: Resources,
+    /// Resources assigned to `idle`
+    pub resources: Resources,
+    /// Software tasks `idle` can schedule
+    pub schedule: Spawn,
+    /// Software tasks `idle` can spawn
+    pub spawn: Spawn,
+    /// Stack usage of `idle`, in bytes
+    pub stack: Option<u32>,
+    /// Maximum number of units of each multi-unit resource `idle` holds at once
+    pub units: Units,
+    _extensible: (),
+}
+
+/// `init` configuration
+#[derive(Debug)]
+pub struct Init {
+    /// Path to the `init` function
+    pub path: Path,
+    /// Resources assigned to `init`
+    pub resources: Resources,
+    /// Software tasks `init` can schedule
+    pub schedule: Spawn,
+    /// Software tasks `init` can spawn
+    pub spawn: Spawn,
+    /// Stack usage of `init`, in bytes
+    pub stack: Option<u32>,
+    _extensible: (),
+}
+
+/// A task
+#[derive(Debug)]
+pub struct Task {
+    /// Capacity of the queue of pending messages of a software task
+    pub capacity: Option<u8>,
+    /// Length of the longest critical section on each resource
+    pub critical_sections: CriticalSections,
+    /// Relative deadline of the task, in clock cycles
+    pub deadline: Option<u32>,
+    /// Whether the interrupt bound to this task is enabled after `init` returns
+    pub enabled: Option<bool>,
+    /// Type of the message (payload) of a software task
+    pub input: Option<Ty>,
+    /// Minimum time, in clock cycles, between two releases of the task
+    pub interarrival: Option<u32>,
+    /// Static variables that persist across invocations of the task
+    pub local: Statics,
+    /// Time, in clock cycles, of the first release of a periodic task
+    pub offset: Option<u32>,
+    /// Path to the task handler
+    pub path: Option<Path>,
+    /// Time, in clock cycles, between two releases of a periodic task
+    pub period: Option<u32>,
+    /// Priority of the task
+    pub priority: Option<Priority>,
+    /// Resources, among `resources`, that this task only reads
+    pub read_only: Resources,
+    /// Resources assigned to this task
+    pub resources: Resources,
+    /// Software tasks this task can schedule
+    pub schedule: Spawn,
+    /// Software tasks this task can spawn
+    pub spawn: Spawn,
+    /// Stack usage of the task, in bytes
+    pub stack: Option<u32>,
+    /// Preemption threshold of the task
+    pub threshold: Option<u8>,
+    /// Maximum number of units of each multi-unit resource this task holds at
+    /// once
+    pub units: Units,
+    /// Worst case execution time of the task, in clock cycles
+    pub wcet: Option<u32>,
+    _extensible: (),
+}
+
+/// A set of tasks
+pub type Tasks = HashMap<Ident, Task>;
+
+/// Checks the syntax of the parsed `app!` macro
+pub fn app(app: ::App) -> Result<App> {
+    Ok(App {
+        device: app.device,
+        free_interrupts: app.free_interrupts.unwrap_or_else(Vec::new),
+        idle: ::check::idle(app.idle).chain_err(|| "checking `idle`")?,
+        init: ::check::init(app.init).chain_err(|| "checking `init`")?,
+        instrument: app.instrument,
+        nvic_prio_bits: app.nvic_prio_bits,
+        overheads: app.overheads.unwrap_or_default(),
+        resources: app.resources.unwrap_or_else(HashMap::new),
+        tasks: app.tasks
+            .unwrap_or_else(HashMap::new)
+            .into_iter()
+            .map(|(k, v)| {
+                let v = ::check::task(v).chain_err(|| format!("checking task `{}`", k))?;
+
+                Ok((k, v))
+            })
+            .collect::<Result<_>>()?,
+        _extensible: (),
+    })
+}
+
+fn idle(idle: Option<::Idle>) -> Result<Idle> {
+    Ok(if let Some(idle) = idle {
+        Idle {
+            critical_sections: idle.critical_sections.unwrap_or_else(HashMap::new),
+            path: idle.path.unwrap_or_else(|| Path::from("idle")),
+            read_only: idle.read_only.unwrap_or_else(HashSet::new),
+            resources: idle.resources.unwrap_or_else(HashSet::new),
+            schedule: idle.schedule.unwrap_or_else(HashSet::new),
+            spawn: idle.spawn.unwrap_or_else(HashSet::new),
+            stack: idle.stack,
+            units: idle.units.unwrap_or_else(HashMap::new),
+            _extensible: (),
+        }
+    } else {
+        Idle {
+            critical_sections: HashMap::new(),
+            path: Path::from("idle"),
+            read_only: HashSet::new(),
+            resources: HashSet::new(),
+            schedule: HashSet::new(),
+            spawn: HashSet::new(),
+            stack: None,
+            units: HashMap::new(),
+            _extensible: (),
+        }
+    })
+}
+
+fn init(init: Option<::Init>) -> Result<Init> {
+    Ok(if let Some(init) = init {
+        Init {
+            path: init.path.unwrap_or_else(|| Path::from("init")),
+            resources: init.resources.unwrap_or_else(HashSet::new),
+            schedule: init.schedule.unwrap_or_else(HashSet::new),
+            spawn: init.spawn.unwrap_or_else(HashSet::new),
+            stack: init.stack,
+            _extensible: (),
+        }
+    } else {
+        Init {
+            path: Path::from("init"),
+            resources: HashSet::new(),
+            schedule: HashSet::new(),
+            spawn: HashSet::new(),
+            stack: None,
+            _extensible: (),
+        }
+    })
+}
+
+fn task(task: ::Task) -> Result<Task> {
+    Ok(Task {
+        capacity: task.capacity,
+        critical_sections: task.critical_sections.unwrap_or_else(HashMap::new),
+        deadline: task.deadline,
+        enabled: task.enabled,
+        input: task.input,
+        interarrival: task.interarrival,
+        local: task.local.unwrap_or_else(HashMap::new),
+        offset: task.offset,
+        path: task.path,
+        period: task.period,
+        priority: task.priority,
+        read_only: task.read_only.unwrap_or_else(HashSet::new),
+        resources: task.resources.unwrap_or_else(HashSet::new),
+        schedule: task.schedule.unwrap_or_else(HashSet::new),
+        spawn: task.spawn.unwrap_or_else(HashSet::new),
+        stack: task.stack,
+        threshold: task.threshold,
+        units: task.units.unwrap_or_else(HashMap::new),
+        wcet: task.wcet,
+        _extensible: (),
+    })
+}
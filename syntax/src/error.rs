@@ -0,0 +1,46 @@
+//! Errors
+
+error_chain! {
+    errors {
+        /// The value of the key `key` couldn't be parsed; the cause is the next
+        /// error of the chain
+        Key(key: String) {
+            description("error parsing the value of a key")
+            display("parsing `{}`", key)
+        }
+    }
+}
+
+impl Error {
+    /// Splits the error into the keys, outermost first, of the value that
+    /// couldn't be parsed, e.g. `["tasks", "EXTI0", "priority"]`, and the
+    /// description of the problem with that value
+    pub fn locate(&self) -> (Vec<String>, String) {
+        let mut keys = vec![];
+        let mut causes = vec![];
+
+        let mut error = self;
+        loop {
+            match error.0 {
+                ErrorKind::Key(ref key) => {
+                    keys.push(key.clone());
+                    causes.clear();
+                }
+                ref kind => causes.push(kind.to_string()),
+            }
+
+            match error.1.next_error {
+                Some(ref next) => match next.downcast_ref::<Error>() {
+                    Some(next) => error = next,
+                    None => {
+                        causes.push(next.to_string());
+                        break;
+                    }
+                },
+                None => break,
+            }
+        }
+
+        (keys, causes.join(": "))
+    }
+}
@@ -0,0 +1,187 @@
+//! Parser of the `app!` macro used by the Real Time For the Masses (RTFM) framework
+#![deny(missing_docs)]
+#![deny(warnings)]
+
+#[macro_use]
+extern crate error_chain;
+extern crate quote;
+extern crate syn;
+
+pub mod check;
+pub mod error;
+
+mod parse;
+
+use std::collections::{HashMap, HashSet};
+
+use syn::{Expr, Ident, Path, Ty};
+
+use error::*;
+
+/// The `app!` macro
+#[derive(Debug)]
+pub struct App {
+    /// The device crate
+    pub device: Path,
+    /// Interrupts used to dispatch software tasks
+    pub free_interrupts: Option<Interrupts>,
+    /// `idle` configuration
+    pub idle: Option<Idle>,
+    /// `init` configuration
+    pub init: Option<Init>,
+    /// Implementation of `rtfm::Instrument` that receives the scheduling events
+    pub instrument: Option<Path>,
+    /// Number of priority bits of the device
+    pub nvic_prio_bits: Option<u8>,
+    /// Execution times of the runtime
+    pub overheads: Option<Overheads>,
+    /// Resources
+    pub resources: Option<Statics>,
+    /// Tasks
+    pub tasks: Option<Tasks>,
+    _extensible: (),
+}
+
+impl App {
+    /// Parses the contents of the `app! { .. }` macro
+    pub fn parse(input: &str) -> Result<Self> {
+        parse::app(input)
+    }
+}
+
+/// `idle` configuration
+#[derive(Debug)]
+pub struct Idle {
+    /// Length of the longest critical section on each resource
+    pub critical_sections: Option<CriticalSections>,
+    /// Path to the `idle` function
+    pub path: Option<Path>,
+    /// Resources, among `resources`, that `idle` only reads
+    pub read_only: Option<Resources>,
+    /// Resources assigned to `idle`
+    pub resources: Option<Resources>,
+    /// Software tasks `idle` can schedule
+    pub schedule: Option<Spawn>,
+    /// Software tasks `idle` can spawn
+    pub spawn: Option<Spawn>,
+    /// Stack usage of `idle`, in bytes
+    pub stack: Option<u32>,
+    /// Maximum number of units of each multi-unit resource `idle` holds at once
+    pub units: Option<Units>,
+    _extensible: (),
+}
+
+/// `init` configuration
+#[derive(Debug)]
+pub struct Init {
+    /// Path to the `init` function
+    pub path: Option<Path>,
+    /// Resources assigned to `init`
+    pub resources: Option<Resources>,
+    /// Software tasks `init` can schedule
+    pub schedule: Option<Spawn>,
+    /// Software tasks `init` can spawn
+    pub spawn: Option<Spawn>,
+    /// Stack usage of `init`, in bytes
+    pub stack: Option<u32>,
+    _extensible: (),
+}
+
+/// A task
+#[derive(Debug)]
+pub struct Task {
+    /// Capacity of the queue of pending messages of a software task
+    pub capacity: Option<u8>,
+    /// Length of the longest critical section on each resource
+    pub critical_sections: Option<CriticalSections>,
+    /// Relative deadline of the task, in clock cycles
+    pub deadline: Option<u32>,
+    /// Whether the interrupt bound to this task is enabled after `init` returns
+    pub enabled: Option<bool>,
+    /// Type of the message (payload) of a software task
+    pub input: Option<Ty>,
+    /// Minimum time, in clock cycles, between two releases of the task
+    pub interarrival: Option<u32>,
+    /// Static variables that persist across invocations of the task
+    pub local: Option<Statics>,
+    /// Time, in clock cycles, of the first release of a periodic task
+    pub offset: Option<u32>,
+    /// Path to the task handler
+    pub path: Option<Path>,
+    /// Time, in clock cycles, between two releases of a periodic task
+    pub period: Option<u32>,
+    /// Priority of the task
+    pub priority: Option<Priority>,
+    /// Resources, among `resources`, that this task only reads
+    pub read_only: Option<Resources>,
+    /// Resources assigned to this task
+    pub resources: Option<Resources>,
+    /// Software tasks this task can schedule
+    pub schedule: Option<Spawn>,
+    /// Software tasks this task can spawn
+    pub spawn: Option<Spawn>,
+    /// Stack usage of the task, in bytes
+    pub stack: Option<u32>,
+    /// Preemption threshold of the task
+    pub threshold: Option<u8>,
+    /// Maximum number of units of each multi-unit resource this task holds at
+    /// once
+    pub units: Option<Units>,
+    /// Worst case execution time of the task, in clock cycles
+    pub wcet: Option<u32>,
+    _extensible: (),
+}
+
+/// Execution times, in clock cycles, of the code the runtime runs on behalf of
+/// the tasks
+#[derive(Clone, Debug, Default)]
+pub struct Overheads {
+    /// Execution time of a dispatcher, per software task it runs
+    pub dispatch: Option<u32>,
+    /// Length of the longest critical section on one of the queues of the
+    /// software tasks or on the timer queue
+    pub queue: Option<u32>,
+    /// Execution time of the `SYS_TICK` handler, per task it releases
+    pub release: Option<u32>,
+    _extensible: (),
+}
+
+/// Priority of a task
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Priority {
+    /// `priority: auto`, the priority is assigned by the framework
+    Auto,
+    /// An explicit priority
+    Value(u8),
+}
+
+/// A static variable
+#[derive(Debug)]
+pub struct Static {
+    /// Initial value of the static variable; `None` for late resources
+    pub expr: Option<Expr>,
+    /// Type of the static variable
+    pub ty: Ty,
+    _extensible: (),
+}
+
+/// Length, in clock cycles, of the longest critical section on each resource
+pub type CriticalSections = HashMap<Ident, u32>;
+
+/// An ordered list of interrupts
+pub type Interrupts = Vec<Ident>;
+
+/// A set of resources
+pub type Resources = HashSet<Ident>;
+
+/// A set of software tasks
+pub type Spawn = HashSet<Ident>;
+
+/// A set of `static` variables
+pub type Statics = HashMap<Ident, Static>;
+
+/// A set of tasks
+pub type Tasks = HashMap<Ident, Task>;
+
+/// Number of units of each multi-unit resource
+pub type Units = HashMap<Ident, u8>;
@@ -0,0 +1,539 @@
+use std::collections::{HashMap, HashSet};
+
+use quote::Tokens;
+use syn::{self, BinOpToken, DelimToken, Delimited, Ident, IntTy, Lit, Path, Token, TokenTree, Ty};
+
+use error::*;
+use {App, CriticalSections, Idle, Init, Interrupts, Overheads, Priority, Resources, Spawn, Static,
+     Statics, Task, Tasks, Units};
+
+pub fn app(input: &str) -> Result<App> {
+    let tts = syn::parse_token_trees(input)?;
+
+    let mut device = None;
+    let mut free_interrupts = None;
+    let mut idle = None;
+    let mut init = None;
+    let mut instrument = None;
+    let mut nvic_prio_bits = None;
+    let mut overheads = None;
+    let mut resources = None;
+    let mut tasks = None;
+
+    fields(&tts, |key, tts| {
+        match key.as_ref() {
+            "device" => device = Some(::parse::path(tts)?),
+            "free_interrupts" => free_interrupts = Some(::parse::interrupts(tts)?),
+            "idle" => idle = Some(::parse::idle(tts)?),
+            "init" => init = Some(::parse::init(tts)?),
+            "instrument" => instrument = Some(::parse::path(tts)?),
+            "nvic_prio_bits" => nvic_prio_bits = Some(::parse::u8(tts)?),
+            "overheads" => overheads = Some(::parse::overheads(tts)?),
+            "resources" => resources = Some(::parse::statics(tts)?),
+            "tasks" => tasks = Some(::parse::tasks(tts)?),
+            _ => bail!("unknown field: `{}`", key),
+        }
+
+        Ok(())
+    })?;
+
+    Ok(App {
+        device: device.ok_or("`device` field is missing")?,
+        free_interrupts,
+        idle,
+        init,
+        instrument,
+        nvic_prio_bits,
+        overheads,
+        resources,
+        tasks,
+        _extensible: (),
+    })
+}
+
+/// Parses a comma separated list of `key: value` pairs, calling `f` on each
+/// of them
+///
+/// Duplicated keys are rejected.
+fn fields<F>(tts: &[TokenTree], mut f: F) -> Result<()>
+where
+    F: FnMut(&Ident, &[TokenTree]) -> Result<()>,
+{
+    let mut keys = HashSet::new();
+
+    let mut i = 0;
+    while i < tts.len() {
+        let key = match tts[i] {
+            TokenTree::Token(Token::Ident(ref id)) => id,
+            _ => bail!("expected a key, found `{}`", string(&tts[i..i + 1])),
+        };
+        i += 1;
+
+        ensure!(keys.insert(key.clone()), "duplicated key `{}`", key);
+
+        ensure!(
+            tts.get(i) == Some(&TokenTree::Token(Token::Colon)),
+            "expected `:` after `{}`",
+            key
+        );
+        i += 1;
+
+        let start = i;
+        while i < tts.len() && tts[i] != TokenTree::Token(Token::Comma) {
+            i += 1;
+        }
+
+        ensure!(start != i, "`{}` has no value", key);
+
+        f(key, &tts[start..i]).chain_err(|| ErrorKind::Key(key.to_string()))?;
+
+        // skip the comma
+        i += 1;
+    }
+
+    Ok(())
+}
+
+/// Parses a `{ key: value, .. }` block
+fn block<F>(tts: &[TokenTree], f: F) -> Result<()>
+where
+    F: FnMut(&Ident, &[TokenTree]) -> Result<()>,
+{
+    fields(delimited(tts, DelimToken::Brace)?, f)
+}
+
+fn overheads(tts: &[TokenTree]) -> Result<Overheads> {
+    let mut dispatch = None;
+    let mut queue = None;
+    let mut release = None;
+
+    block(tts, |key, tts| {
+        match key.as_ref() {
+            "dispatch" => dispatch = Some(::parse::u32(tts)?),
+            "queue" => queue = Some(::parse::u32(tts)?),
+            "release" => release = Some(::parse::u32(tts)?),
+            _ => bail!("unknown field: `{}`", key),
+        }
+
+        Ok(())
+    })?;
+
+    Ok(Overheads {
+        dispatch,
+        queue,
+        release,
+        _extensible: (),
+    })
+}
+
+fn idle(tts: &[TokenTree]) -> Result<Idle> {
+    let mut critical_sections = None;
+    let mut path = None;
+    let mut read_only = None;
+    let mut resources = None;
+    let mut schedule = None;
+    let mut spawn = None;
+    let mut stack = None;
+    let mut units = None;
+
+    block(tts, |key, tts| {
+        match key.as_ref() {
+            "critical_sections" => critical_sections = Some(::parse::critical_sections(tts)?),
+            "path" => path = Some(::parse::path(tts)?),
+            "resources" => {
+                let (all, reads) = ::parse::resources(tts)?;
+                resources = Some(all);
+                read_only = Some(reads);
+            }
+            "schedule" => schedule = Some(::parse::spawn(tts)?),
+            "spawn" => spawn = Some(::parse::spawn(tts)?),
+            "stack" => stack = Some(::parse::u32(tts)?),
+            "units" => units = Some(::parse::units(tts)?),
+            _ => bail!("unknown field: `{}`", key),
+        }
+
+        Ok(())
+    })?;
+
+    Ok(Idle {
+        critical_sections,
+        path,
+        read_only,
+        resources,
+        schedule,
+        spawn,
+        stack,
+        units,
+        _extensible: (),
+    })
+}
+
+fn init(tts: &[TokenTree]) -> Result<Init> {
+    let mut path = None;
+    let mut resources = None;
+    let mut schedule = None;
+    let mut spawn = None;
+    let mut stack = None;
+
+    block(tts, |key, tts| {
+        match key.as_ref() {
+            "path" => path = Some(::parse::path(tts)?),
+            "resources" => {
+                let (all, reads) = ::parse::resources(tts)?;
+                ensure!(
+                    reads.is_empty(),
+                    "`init` owns its resources; read-only access (`&`) is not valid here"
+                );
+                resources = Some(all);
+            }
+            "schedule" => schedule = Some(::parse::spawn(tts)?),
+            "spawn" => spawn = Some(::parse::spawn(tts)?),
+            "stack" => stack = Some(::parse::u32(tts)?),
+            _ => bail!("unknown field: `{}`", key),
+        }
+
+        Ok(())
+    })?;
+
+    Ok(Init {
+        path,
+        resources,
+        schedule,
+        spawn,
+        stack,
+        _extensible: (),
+    })
+}
+
+fn tasks(tts: &[TokenTree]) -> Result<Tasks> {
+    let mut tasks = HashMap::new();
+
+    block(tts, |name, tts| {
+        tasks.insert(name.clone(), ::parse::task(tts)?);
+
+        Ok(())
+    })?;
+
+    Ok(tasks)
+}
+
+fn task(tts: &[TokenTree]) -> Result<Task> {
+    let mut capacity = None;
+    let mut critical_sections = None;
+    let mut deadline = None;
+    let mut enabled = None;
+    let mut input = None;
+    let mut interarrival = None;
+    let mut local = None;
+    let mut offset = None;
+    let mut path = None;
+    let mut period = None;
+    let mut priority = None;
+    let mut read_only = None;
+    let mut resources = None;
+    let mut schedule = None;
+    let mut spawn = None;
+    let mut stack = None;
+    let mut threshold = None;
+    let mut units = None;
+    let mut wcet = None;
+
+    block(tts, |key, tts| {
+        match key.as_ref() {
+            "capacity" => capacity = Some(::parse::u8(tts)?),
+            "critical_sections" => critical_sections = Some(::parse::critical_sections(tts)?),
+            "deadline" => deadline = Some(::parse::u32(tts)?),
+            "enabled" => enabled = Some(::parse::bool(tts)?),
+            "input" => input = Some(::parse::ty(tts)?),
+            "interarrival" => interarrival = Some(::parse::u32(tts)?),
+            "local" => local = Some(::parse::statics(tts)?),
+            "offset" => offset = Some(::parse::u32(tts)?),
+            "path" => path = Some(::parse::path(tts)?),
+            "period" => period = Some(::parse::u32(tts)?),
+            "priority" => priority = Some(::parse::priority(tts)?),
+            "resources" => {
+                let (all, reads) = ::parse::resources(tts)?;
+                resources = Some(all);
+                read_only = Some(reads);
+            }
+            "schedule" => schedule = Some(::parse::spawn(tts)?),
+            "spawn" => spawn = Some(::parse::spawn(tts)?),
+            "stack" => stack = Some(::parse::u32(tts)?),
+            "threshold" => threshold = Some(::parse::u8(tts)?),
+            "units" => units = Some(::parse::units(tts)?),
+            "wcet" => wcet = Some(::parse::u32(tts)?),
+            _ => bail!("unknown field: `{}`", key),
+        }
+
+        Ok(())
+    })?;
+
+    Ok(Task {
+        capacity,
+        critical_sections,
+        deadline,
+        enabled,
+        input,
+        interarrival,
+        local,
+        offset,
+        path,
+        period,
+        priority,
+        read_only,
+        resources,
+        schedule,
+        spawn,
+        stack,
+        threshold,
+        units,
+        wcet,
+        _extensible: (),
+    })
+}
+
+/// Parses `{ A: 10, B: 20 }`
+fn critical_sections(tts: &[TokenTree]) -> Result<CriticalSections> {
+    let mut sections = HashMap::new();
+
+    block(tts, |resource, tts| {
+        sections.insert(resource.clone(), ::parse::u32(tts)?);
+
+        Ok(())
+    })?;
+
+    Ok(sections)
+}
+
+/// Parses `{ POOL: 2, BUFFERS: 1 }`
+fn units(tts: &[TokenTree]) -> Result<Units> {
+    let mut units = HashMap::new();
+
+    block(tts, |resource, tts| {
+        units.insert(resource.clone(), ::parse::u8(tts)?);
+
+        Ok(())
+    })?;
+
+    Ok(units)
+}
+
+/// Parses `[A, &B, C]`; returns all the resources and the ones prefixed with
+/// `&` (read-only access)
+fn resources(tts: &[TokenTree]) -> Result<(Resources, Resources)> {
+    let tts = delimited(tts, DelimToken::Bracket)?;
+
+    let mut all = HashSet::new();
+    let mut read_only = HashSet::new();
+    for chunk in tts.split(|tt| *tt == TokenTree::Token(Token::Comma)) {
+        // trailing comma
+        if chunk.is_empty() {
+            continue;
+        }
+
+        let (name, shared) = match (chunk.len(), chunk.get(0), chunk.get(1)) {
+            (1, Some(&TokenTree::Token(Token::Ident(ref id))), None) => (id, false),
+            (
+                2,
+                Some(&TokenTree::Token(Token::BinOp(BinOpToken::And))),
+                Some(&TokenTree::Token(Token::Ident(ref id))),
+            ) => (id, true),
+            _ => bail!("expected a resource name, found `{}`", string(chunk)),
+        };
+
+        ensure!(
+            all.insert(name.clone()),
+            "resource `{}` listed more than once",
+            name
+        );
+
+        if shared {
+            read_only.insert(name.clone());
+        }
+    }
+
+    Ok((all, read_only))
+}
+
+/// Parses `[foo, bar, baz]`
+fn spawn(tts: &[TokenTree]) -> Result<Spawn> {
+    Ok(list(tts, "task")?.into_iter().collect())
+}
+
+/// Parses `[EXTI0, EXTI1]`
+fn interrupts(tts: &[TokenTree]) -> Result<Interrupts> {
+    list(tts, "interrupt")
+}
+
+/// Parses a `[A, B, C]` list of names; names can't be repeated
+fn list(tts: &[TokenTree], what: &str) -> Result<Vec<Ident>> {
+    let tts = delimited(tts, DelimToken::Bracket)?;
+
+    let mut names: Vec<Ident> = vec![];
+    for chunk in tts.split(|tt| *tt == TokenTree::Token(Token::Comma)) {
+        // trailing comma
+        if chunk.is_empty() {
+            continue;
+        }
+
+        match single(chunk) {
+            Some(&TokenTree::Token(Token::Ident(ref id))) => {
+                ensure!(
+                    !names.contains(id),
+                    "{} `{}` listed more than once",
+                    what,
+                    id
+                );
+
+                names.push(id.clone());
+            }
+            _ => bail!("expected a {} name, found `{}`", what, string(chunk)),
+        }
+    }
+
+    Ok(names)
+}
+
+/// Parses `{ static A: Ty = expr; static B: Ty; .. }`
+fn statics(tts: &[TokenTree]) -> Result<Statics> {
+    let tts = delimited(tts, DelimToken::Brace)?;
+
+    let mut statics = HashMap::new();
+    for item in tts.split(|tt| *tt == TokenTree::Token(Token::Semi)) {
+        if item.is_empty() {
+            continue;
+        }
+
+        let name = match (item.get(0), item.get(1), item.get(2)) {
+            (
+                Some(&TokenTree::Token(Token::Ident(ref kw))),
+                Some(&TokenTree::Token(Token::Ident(ref name))),
+                Some(&TokenTree::Token(Token::Colon)),
+            ) if kw == "static" =>
+            {
+                name
+            }
+            _ => bail!(
+                "expected `static $NAME: $TY [= $EXPR];`, found `{}`",
+                string(item)
+            ),
+        };
+        let item = &item[3..];
+
+        let mut parts = item.splitn(2, |tt| *tt == TokenTree::Token(Token::Eq));
+        let ty = parts.next().unwrap_or(&[]);
+        let expr = parts.next();
+
+        ensure!(!ty.is_empty(), "static `{}` has no type", name);
+
+        let ty = syn::parse_type(&string(ty))
+            .map_err(Error::from)
+            .chain_err(|| format!("parsing the type of `{}`", name))?;
+        let expr = match expr {
+            Some(expr) => Some(
+                syn::parse_expr(&string(expr))
+                    .map_err(Error::from)
+                    .chain_err(|| format!("parsing the initial value of `{}`", name))?,
+            ),
+            None => None,
+        };
+
+        ensure!(
+            !statics.contains_key(name),
+            "resource `{}` is declared more than once",
+            name
+        );
+
+        statics.insert(
+            name.clone(),
+            Static {
+                expr,
+                ty,
+                _extensible: (),
+            },
+        );
+    }
+
+    Ok(statics)
+}
+
+fn path(tts: &[TokenTree]) -> Result<Path> {
+    Ok(syn::parse_path(&string(tts))?)
+}
+
+fn ty(tts: &[TokenTree]) -> Result<Ty> {
+    Ok(syn::parse_type(&string(tts))?)
+}
+
+fn bool(tts: &[TokenTree]) -> Result<bool> {
+    match single(tts) {
+        Some(&TokenTree::Token(Token::Literal(Lit::Bool(b)))) => Ok(b),
+        Some(&TokenTree::Token(Token::Ident(ref id))) if id == "true" => Ok(true),
+        Some(&TokenTree::Token(Token::Ident(ref id))) if id == "false" => Ok(false),
+        _ => bail!("expected a boolean, found `{}`", string(tts)),
+    }
+}
+
+/// Parses `auto` or an `u8` integer
+fn priority(tts: &[TokenTree]) -> Result<Priority> {
+    match single(tts) {
+        Some(&TokenTree::Token(Token::Ident(ref id))) if id == "auto" => Ok(Priority::Auto),
+        _ => Ok(Priority::Value(::parse::u8(tts)?)),
+    }
+}
+
+fn u8(tts: &[TokenTree]) -> Result<u8> {
+    let n = int(tts)?;
+
+    ensure!(n <= u64::from(::std::u8::MAX), "{} is out of the range of `u8`", n);
+
+    Ok(n as u8)
+}
+
+fn u32(tts: &[TokenTree]) -> Result<u32> {
+    let n = int(tts)?;
+
+    ensure!(n <= u64::from(::std::u32::MAX), "{} is out of the range of `u32`", n);
+
+    Ok(n as u32)
+}
+
+fn int(tts: &[TokenTree]) -> Result<u64> {
+    match single(tts) {
+        Some(&TokenTree::Token(Token::Literal(Lit::Int(n, IntTy::Unsuffixed)))) => Ok(n),
+        _ => bail!("expected an unsuffixed integer, found `{}`", string(tts)),
+    }
+}
+
+/// Returns the contents of a single delimited token tree
+fn delimited(tts: &[TokenTree], delim: DelimToken) -> Result<&[TokenTree]> {
+    match single(tts) {
+        Some(&TokenTree::Delimited(Delimited {
+            delim: ref d,
+            ref tts,
+        })) if *d == delim =>
+        {
+            Ok(tts)
+        }
+        _ => bail!(
+            "expected a {:?} delimited block, found `{}`",
+            delim,
+            string(tts)
+        ),
+    }
+}
+
+/// Returns the token tree if `tts` contains exactly one
+fn single(tts: &[TokenTree]) -> Option<&TokenTree> {
+    if tts.len() == 1 {
+        tts.first()
+    } else {
+        None
+    }
+}
+
+fn string(tts: &[TokenTree]) -> String {
+    let mut tokens = Tokens::new();
+    tokens.append_all(tts);
+    tokens.as_str().to_owned()
+}
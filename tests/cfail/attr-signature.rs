@@ -0,0 +1,29 @@
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f413;
+extern crate panic_halt;
+
+use rtfm::attr::app;
+
+#[app(device = stm32f413)]
+mod app {
+    use rtfm::Threshold;
+
+    static mut ON: bool = false;
+
+    #[init]
+    fn init(_p: init::Peripherals, _r: init::Resources) {}
+
+    #[idle]
+    fn idle() -> ! {
+        loop {}
+    }
+
+    // ERROR the task has resources so its handler must take `SYS_TICK::Resources`
+    #[task(binds = SYS_TICK, resources = [ON])]
+    fn sys_tick(_t: &mut Threshold) {} //~ error must have signature
+}
@@ -1,20 +1,21 @@
 #![deny(unsafe_code)]
 #![deny(warnings)]
-#![feature(proc_macro)]
 #![no_std]
+#![no_main]
 
 extern crate cortex_m_rtfm as rtfm;
-extern crate stm32f103xx;
+extern crate stm32f413;
+extern crate panic_halt;
 
 use rtfm::app;
 
-app! { //~ error proc macro panicked
-    device: stm32f103xx,
+app! {
+    device: stm32f413,
 
     tasks: {
         // ERROR exceptions can't be enabled / disabled here
         SYS_TICK: {
-            enabled: true,
+            enabled: true, //~ error `enabled` field is not valid for exceptions
             priority: 1,
         },
     },
@@ -1,14 +1,15 @@
 #![deny(warnings)]
-#![feature(proc_macro)]
 #![no_std]
+#![no_main]
 
 extern crate cortex_m_rtfm as rtfm;
-extern crate stm32f103xx;
+extern crate stm32f413;
+extern crate panic_halt;
 
 use rtfm::app;
 
-app! { //~ proc macro panicked
-    device: stm32f103xx,
+app! {
+    device: stm32f413,
 
     resources: {
         static BUFFER: [u8; 16] = [0; 16];
@@ -20,7 +21,7 @@ app! { //~ proc macro panicked
 
     idle: {
         // ERROR resources assigned to `init` can't be shared with `idle`
-        resources: [BUFFER],
+        resources: [BUFFER], //~ error can't be shared with `idle`
     },
 }
 
@@ -1,15 +1,16 @@
 #![deny(unsafe_code)]
 #![deny(warnings)]
-#![feature(proc_macro)]
 #![no_std]
+#![no_main]
 
 extern crate cortex_m_rtfm as rtfm;
-extern crate stm32f103xx;
+extern crate stm32f413;
+extern crate panic_halt;
 
 use rtfm::{app, Threshold};
 
 app! {
-    device: stm32f103xx,
+    device: stm32f413,
 
     resources: {
         static A: u8 = 0;
@@ -0,0 +1,32 @@
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f413;
+extern crate panic_halt;
+
+use rtfm::app;
+
+app! {
+    device: stm32f413,
+
+    tasks: {
+        EXTI0: {
+            path: exti0,
+            local: {
+                // ERROR task-local statics must have an initial value
+                static STATE: u32; //~ error must have an initial value
+            },
+        },
+    },
+}
+
+fn init(_p: init::Peripherals) {}
+
+fn idle() -> ! {
+    loop {}
+}
+
+fn exti0(_r: EXTI0::Resources) {}
@@ -0,0 +1,41 @@
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f413;
+extern crate panic_halt;
+
+use rtfm::{app, Threshold};
+
+app! {
+    device: stm32f413,
+
+    resources: {
+        static STATE: u32 = 0;
+    },
+
+    tasks: {
+        // ERROR no critical section can mask `NMI`
+        NMI: {
+            path: nmi,
+            resources: [STATE], //~ error non-maskable exceptions can't be masked
+        },
+
+        EXTI0: {
+            path: exti0,
+            resources: [STATE],
+        },
+    },
+}
+
+fn init(_p: init::Peripherals, _r: init::Resources) {}
+
+fn idle() -> ! {
+    loop {}
+}
+
+fn nmi(_t: &mut Threshold, _r: NMI::Resources) {}
+
+fn exti0(_t: &mut Threshold, _r: EXTI0::Resources) {}
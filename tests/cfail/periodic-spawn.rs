@@ -0,0 +1,39 @@
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f413;
+extern crate panic_halt;
+
+use rtfm::{app, Threshold};
+
+app! {
+    device: stm32f413,
+
+    free_interrupts: [EXTI1],
+
+    tasks: {
+        // ERROR periodic tasks are only released by the timer queue
+        EXTI0: {
+            path: exti0,
+            spawn: [foo], //~ error because it's a periodic task
+        },
+
+        foo: {
+            path: foo,
+            period: 1000,
+        },
+    },
+}
+
+fn init(_p: init::Peripherals) {}
+
+fn idle() -> ! {
+    loop {}
+}
+
+fn exti0(_t: &mut Threshold, _s: EXTI0::Spawn) {}
+
+fn foo() {}
@@ -1,22 +1,23 @@
 #![deny(unsafe_code)]
 #![deny(warnings)]
-#![feature(proc_macro)]
 #![no_std]
+#![no_main]
 
 extern crate cortex_m_rtfm as rtfm;
-extern crate stm32f103xx;
+extern crate stm32f413;
+extern crate panic_halt;
 
 use rtfm::app;
 
-app! { //~ error proc macro panicked
-    device: stm32f103xx,
+app! {
+    device: stm32f413,
 
     tasks: {
         EXTI0: {
             enabled: true,
             priority: 1,
             // ERROR peripheral appears twice in this list
-            resources: [GPIOA, GPIOA],
+            resources: [GPIOA, GPIOA], //~ error resource `GPIOA` listed more than once
         },
     },
 }
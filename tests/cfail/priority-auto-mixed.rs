@@ -0,0 +1,40 @@
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f413;
+extern crate panic_halt;
+
+use rtfm::{app, Threshold};
+
+app! {
+    device: stm32f413,
+
+    nvic_prio_bits: 4,
+
+    tasks: {
+        EXTI0: {
+            path: exti0,
+            priority: auto,
+            interarrival: 100,
+        },
+
+        // ERROR either all the tasks or none of them must use `priority: auto`
+        EXTI1: {
+            path: exti1,
+            priority: 2, //~ error must be used by either all the tasks or none of them
+        },
+    },
+}
+
+fn init(_p: init::Peripherals) {}
+
+fn idle() -> ! {
+    loop {}
+}
+
+fn exti0(_t: &mut Threshold, _r: EXTI0::Resources) {}
+
+fn exti1(_t: &mut Threshold, _r: EXTI1::Resources) {}
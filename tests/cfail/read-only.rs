@@ -0,0 +1,49 @@
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f413;
+extern crate panic_halt;
+
+use rtfm::{app, ReadOnlyResource, Threshold};
+
+app! {
+    device: stm32f413,
+
+    resources: {
+        static STATE: u32 = 0;
+    },
+
+    tasks: {
+        EXTI0: {
+            path: exti0,
+            priority: 1,
+            resources: [STATE],
+        },
+
+        EXTI1: {
+            path: exti1,
+            priority: 2,
+            resources: [&STATE],
+        },
+    },
+}
+
+fn init(_p: init::Peripherals, _r: init::Resources) {}
+
+fn idle() -> ! {
+    loop {}
+}
+
+fn exti0(_t: &mut Threshold, _r: EXTI0::Resources) {}
+
+fn exti1(t: &mut Threshold, mut r: EXTI1::Resources) {
+    // OK reading through a read-only proxy
+    let _state: u32 = r.STATE.claim(t, |state, _| *state);
+
+    // ERROR read-only proxies can't be written to
+    r.STATE.claim_mut(t, |state, _| *state += 1);
+    //~^ error no method named `claim_mut` found
+}
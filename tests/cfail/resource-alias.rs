@@ -1,17 +1,18 @@
 #![deny(unsafe_code)]
 #![deny(warnings)]
-#![feature(proc_macro)]
 #![no_std]
+#![no_main]
 
 extern crate cortex_m_rtfm as rtfm;
-extern crate stm32f103xx;
+extern crate stm32f413;
+extern crate panic_halt;
 
 use rtfm::app;
 
-app! { //~ error proc macro panicked
-    device: stm32f103xx,
+app! {
+    device: stm32f413,
 
-    resources: {
+    resources: { //~ error expected `static $NAME: $TY [= $EXPR];`
         // resource `MAX` listed twice
         MAX: u8 = 0;
         MAX: u16 = 0;
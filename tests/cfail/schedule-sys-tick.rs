@@ -0,0 +1,45 @@
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f413;
+extern crate panic_halt;
+
+use rtfm::{app, Threshold};
+
+app! {
+    device: stm32f413,
+
+    free_interrupts: [EXTI1],
+
+    tasks: {
+        // ERROR `SYS_TICK` drives the timer queue
+        SYS_TICK: { //~ error `SYS_TICK` drives the timer queue
+            path: sys_tick,
+        },
+
+        EXTI0: {
+            path: exti0,
+            schedule: [foo],
+        },
+
+        foo: {
+            path: foo,
+            capacity: 1,
+        },
+    },
+}
+
+fn init(_p: init::Peripherals) {}
+
+fn idle() -> ! {
+    loop {}
+}
+
+fn sys_tick() {}
+
+fn exti0(_t: &mut Threshold, _s: EXTI0::Schedule) {}
+
+fn foo() {}
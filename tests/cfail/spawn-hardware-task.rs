@@ -0,0 +1,36 @@
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f413;
+extern crate panic_halt;
+
+use rtfm::{app, Threshold};
+
+app! {
+    device: stm32f413,
+
+    tasks: {
+        // ERROR only software tasks can be spawned
+        EXTI0: {
+            path: exti0,
+            spawn: [EXTI1], //~ error because it's not a software task
+        },
+
+        EXTI1: {
+            path: exti1,
+        },
+    },
+}
+
+fn init(_p: init::Peripherals) {}
+
+fn idle() -> ! {
+    loop {}
+}
+
+fn exti0(_t: &mut Threshold, _s: EXTI0::Spawn) {}
+
+fn exti1() {}
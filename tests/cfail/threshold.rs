@@ -0,0 +1,31 @@
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f413;
+extern crate panic_halt;
+
+use rtfm::app;
+
+app! {
+    device: stm32f413,
+
+    tasks: {
+        EXTI0: {
+            path: exti0,
+            priority: 2,
+            // ERROR the threshold can't be lower than the priority
+            threshold: 1, //~ error can't be lower than `priority`
+        },
+    },
+}
+
+fn init(_p: init::Peripherals) {}
+
+fn idle() -> ! {
+    loop {}
+}
+
+fn exti0() {}
@@ -0,0 +1,35 @@
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f413;
+extern crate panic_halt;
+
+use rtfm::{app, Threshold};
+
+app! {
+    device: stm32f413,
+
+    resources: {
+        // ERROR multi-unit resources must be arrays
+        static COUNTER: u32 = 0; //~ error must have an array type
+    },
+
+    tasks: {
+        EXTI0: {
+            path: exti0,
+            resources: [COUNTER],
+            units: { COUNTER: 2 },
+        },
+    },
+}
+
+fn init(_p: init::Peripherals, _r: init::Resources) {}
+
+fn idle() -> ! {
+    loop {}
+}
+
+fn exti0(_t: &mut Threshold, _r: EXTI0::Resources) {}
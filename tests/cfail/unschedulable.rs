@@ -0,0 +1,49 @@
+#![deny(unsafe_code)]
+#![deny(warnings)]
+#![no_std]
+#![no_main]
+
+extern crate cortex_m_rtfm as rtfm;
+extern crate stm32f413;
+extern crate panic_halt;
+
+use rtfm::{app, Threshold};
+
+app! {
+    device: stm32f413,
+
+    resources: {
+        static SHARED: u32 = 0;
+    },
+
+    tasks: {
+        EXTI0: {
+            path: exti0,
+            priority: 1,
+            interarrival: 100,
+            wcet: 40,
+            resources: [SHARED],
+        },
+
+        // ERROR R = 30 + 40 + 0 = 70 > D = 50; `EXTI0` may hold `SHARED` for
+        // its whole execution time
+        EXTI1: { //~ error task `EXTI1` misses its deadline
+            path: exti1,
+            priority: 2,
+            interarrival: 100,
+            deadline: 50,
+            wcet: 30,
+            resources: [SHARED],
+        },
+    },
+}
+
+fn init(_p: init::Peripherals, _r: init::Resources) {}
+
+fn idle() -> ! {
+    loop {}
+}
+
+fn exti0(_t: &mut Threshold, _r: EXTI0::Resources) {}
+
+fn exti1(_t: &mut Threshold, _r: EXTI1::Resources) {}
@@ -0,0 +1,243 @@
+//! Symbols the handlers of the tasks are exported under
+//!
+//! `cortex-m-rt` 0.6 fills the vector table with these symbols so a handler
+//! exported under any other name is never called.
+//!
+//! Run with `RUSTFLAGS="" cargo test --features host --test exceptions`
+#![cfg(feature = "host")]
+#![deny(warnings)]
+
+// the generated code refers to `core`
+extern crate core;
+extern crate cortex_m;
+extern crate cortex_m_rtfm as rtfm;
+
+use std::cell::RefCell;
+
+use rtfm::{app, Threshold};
+
+// stands in for the device crate
+mod device {
+    use cortex_m::interrupt::Nr;
+
+    pub const NVIC_PRIO_BITS: u8 = 4;
+
+    #[derive(Clone, Copy)]
+    pub enum Interrupt {
+        EXTI0,
+    }
+
+    unsafe impl Nr for Interrupt {
+        fn nr(&self) -> u8 {
+            *self as u8
+        }
+    }
+
+    pub struct Peripherals {}
+
+    impl Peripherals {
+        pub unsafe fn steal() -> Self {
+            Peripherals {}
+        }
+    }
+
+    pub struct CorePeripherals {}
+
+    impl CorePeripherals {
+        pub unsafe fn steal() -> Self {
+            CorePeripherals {}
+        }
+    }
+}
+
+app! {
+    device: device,
+
+    tasks: {
+        BUS_FAULT: {
+            path: bus_fault,
+            priority: 2,
+        },
+
+        DEBUG_MONITOR: {
+            path: debug_monitor,
+            priority: 1,
+        },
+
+        EXTI0: {
+            path: exti0,
+            priority: 1,
+        },
+
+        HARD_FAULT: {
+            path: hard_fault,
+        },
+
+        MEM_MANAGE: {
+            path: mem_manage,
+            priority: 3,
+        },
+
+        NMI: {
+            path: nmi,
+        },
+
+        PENDSV: {
+            path: pendsv,
+            priority: 1,
+        },
+
+        SVCALL: {
+            path: svcall,
+            priority: 2,
+        },
+
+        SYS_TICK: {
+            path: sys_tick,
+            priority: 3,
+        },
+
+        USAGE_FAULT: {
+            path: usage_fault,
+            priority: 1,
+        },
+    },
+}
+
+// the handlers, as `cortex-m-rt` declares them
+extern "C" {
+    fn BusFault();
+    fn DebugMonitor();
+    fn EXTI0();
+    fn MemoryManagement();
+    fn NonMaskableInt();
+    fn PendSV();
+    fn SVCall();
+    fn SysTick();
+    fn UsageFault();
+    fn UserHardFault(frame: &rtfm::ExceptionFrame) -> !;
+}
+
+thread_local! {
+    static LOG: RefCell<Vec<&'static str>> = RefCell::new(vec![]);
+}
+
+fn log(event: &'static str) {
+    LOG.with(|log| log.borrow_mut().push(event));
+}
+
+fn take() -> Vec<&'static str> {
+    LOG.with(|log| log.borrow_mut().drain(..).collect())
+}
+
+fn init(_p: init::Peripherals) {}
+
+fn idle() -> ! {
+    loop {
+        rtfm::wfi();
+    }
+}
+
+fn bus_fault(_t: &mut Threshold, _r: BUS_FAULT::Resources) {
+    log("bus_fault");
+}
+
+fn debug_monitor(_t: &mut Threshold, _r: DEBUG_MONITOR::Resources) {
+    log("debug_monitor");
+}
+
+fn exti0(_t: &mut Threshold, _r: EXTI0::Resources) {
+    log("exti0");
+}
+
+fn hard_fault(_r: HARD_FAULT::Resources) {
+    log("hard_fault");
+}
+
+fn mem_manage(_t: &mut Threshold, _r: MEM_MANAGE::Resources) {
+    log("mem_manage");
+}
+
+fn nmi(_r: NMI::Resources) {
+    log("nmi");
+}
+
+fn pendsv(_t: &mut Threshold, _r: PENDSV::Resources) {
+    log("pendsv");
+}
+
+fn svcall(_t: &mut Threshold, _r: SVCALL::Resources) {
+    log("svcall");
+}
+
+fn sys_tick(_t: &mut Threshold, _r: SYS_TICK::Resources) {
+    log("sys_tick");
+}
+
+fn usage_fault(_t: &mut Threshold, _r: USAGE_FAULT::Resources) {
+    log("usage_fault");
+}
+
+#[test]
+fn bus_fault_symbol() {
+    unsafe { BusFault() }
+    assert_eq!(take(), ["bus_fault"]);
+}
+
+#[test]
+fn debug_monitor_symbol() {
+    unsafe { DebugMonitor() }
+    assert_eq!(take(), ["debug_monitor"]);
+}
+
+#[test]
+fn exti0_symbol() {
+    unsafe { EXTI0() }
+    assert_eq!(take(), ["exti0"]);
+}
+
+#[test]
+fn hard_fault_symbol() {
+    // `UserHardFault` never returns; the task runs from the handler registered with the NVIC
+    let _handler: unsafe extern "C" fn(&rtfm::ExceptionFrame) -> ! = UserHardFault;
+
+    rtfm_init();
+    rtfm::host::pend_exception(3);
+    assert_eq!(take(), ["hard_fault"]);
+}
+
+#[test]
+fn mem_manage_symbol() {
+    unsafe { MemoryManagement() }
+    assert_eq!(take(), ["mem_manage"]);
+}
+
+#[test]
+fn nmi_symbol() {
+    unsafe { NonMaskableInt() }
+    assert_eq!(take(), ["nmi"]);
+}
+
+#[test]
+fn pendsv_symbol() {
+    unsafe { PendSV() }
+    assert_eq!(take(), ["pendsv"]);
+}
+
+#[test]
+fn svcall_symbol() {
+    unsafe { SVCall() }
+    assert_eq!(take(), ["svcall"]);
+}
+
+#[test]
+fn sys_tick_symbol() {
+    unsafe { SysTick() }
+    assert_eq!(take(), ["sys_tick"]);
+}
+
+#[test]
+fn usage_fault_symbol() {
+    unsafe { UsageFault() }
+    assert_eq!(take(), ["usage_fault"]);
+}
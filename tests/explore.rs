@@ -0,0 +1,118 @@
+//! Exploring the preemptions of a task that splits a read-modify-write in two
+//! critical sections
+//!
+//! Run with `RUSTFLAGS="" cargo test --features host --test explore`
+#![cfg(feature = "host")]
+#![deny(warnings)]
+
+// the generated code refers to `core`
+extern crate core;
+extern crate cortex_m;
+extern crate cortex_m_rtfm as rtfm;
+
+use std::cell::Cell;
+
+use rtfm::{app, Resource, Threshold};
+
+// stands in for the device crate
+mod device {
+    use cortex_m::interrupt::Nr;
+
+    pub const NVIC_PRIO_BITS: u8 = 4;
+
+    #[derive(Clone, Copy)]
+    pub enum Interrupt {
+        EXTI0,
+        EXTI1,
+    }
+
+    unsafe impl Nr for Interrupt {
+        fn nr(&self) -> u8 {
+            *self as u8
+        }
+    }
+
+    pub struct Peripherals {}
+
+    impl Peripherals {
+        pub unsafe fn steal() -> Self {
+            Peripherals {}
+        }
+    }
+
+    pub struct CorePeripherals {}
+
+    impl CorePeripherals {
+        pub unsafe fn steal() -> Self {
+            CorePeripherals {}
+        }
+    }
+}
+
+app! {
+    device: device,
+
+    resources: {
+        static COUNTER: u32 = 0;
+    },
+
+    tasks: {
+        EXTI0: {
+            path: low,
+            priority: 1,
+            resources: [COUNTER],
+        },
+
+        EXTI1: {
+            path: high,
+            priority: 2,
+            resources: [COUNTER],
+        },
+    },
+}
+
+thread_local! {
+    static HIGH: Cell<u32> = Cell::new(0);
+}
+
+fn init(_p: init::Peripherals, _r: init::Resources) {}
+
+fn idle() -> ! {
+    loop {
+        rtfm::wfi();
+    }
+}
+
+fn low(t: &mut Threshold, mut r: EXTI0::Resources) {
+    // BUG an update of `high` between the two critical sections is lost
+    let value = r.COUNTER.claim(t, |counter, _t| *counter);
+    r.COUNTER.claim_mut(t, |counter, _t| *counter = value + 1);
+}
+
+fn high(_t: &mut Threshold, mut r: EXTI1::Resources) {
+    *r.COUNTER += 1;
+    HIGH.with(|high| high.set(high.get() + 1));
+}
+
+#[test]
+fn lost_update() {
+    let mut lost = 0;
+    let schedules = rtfm::host::explore(1, || {
+        unsafe { rtfm_reset() };
+        HIGH.with(|high| high.set(0));
+        rtfm_init();
+
+        rtfm::set_pending(device::Interrupt::EXTI0);
+
+        let counter = unsafe { *rtfm_resources().COUNTER };
+        if counter != 1 + HIGH.with(|high| high.get()) {
+            lost += 1;
+        }
+    });
+
+    // no preemption, or `high` preempting `low` at the entry or at the exit of
+    // one of its two claims
+    assert_eq!(schedules, 5);
+    // `high` runs between the two claims
+    assert_eq!(lost, 2);
+}
@@ -0,0 +1,166 @@
+//! Preemption, claims and critical sections on the host backend
+//!
+//! Run with `RUSTFLAGS="" cargo test --features host --test host`; the empty
+//! `RUSTFLAGS` overrides the KLEE flags of the `x86_64` target in
+//! `.cargo/config`.
+#![cfg(feature = "host")]
+#![deny(warnings)]
+
+// the generated code refers to `core`
+extern crate core;
+extern crate cortex_m;
+extern crate cortex_m_rtfm as rtfm;
+
+use std::cell::RefCell;
+
+use rtfm::instrument::{Event, Recorder};
+use rtfm::{app, Resource, Threshold};
+
+// stands in for the device crate
+mod device {
+    use cortex_m::interrupt::Nr;
+
+    pub const NVIC_PRIO_BITS: u8 = 4;
+
+    #[derive(Clone, Copy)]
+    pub enum Interrupt {
+        EXTI0,
+        EXTI1,
+        EXTI2,
+    }
+
+    unsafe impl Nr for Interrupt {
+        fn nr(&self) -> u8 {
+            *self as u8
+        }
+    }
+
+    pub struct Peripherals {}
+
+    impl Peripherals {
+        pub unsafe fn steal() -> Self {
+            Peripherals {}
+        }
+    }
+
+    pub struct CorePeripherals {}
+
+    impl CorePeripherals {
+        pub unsafe fn steal() -> Self {
+            CorePeripherals {}
+        }
+    }
+}
+
+app! {
+    device: device,
+
+    free_interrupts: [EXTI2],
+
+    instrument: rtfm::instrument::Recorder,
+
+    resources: {
+        static SHARED: u32 = 0;
+    },
+
+    init: {
+        spawn: [soft],
+    },
+
+    tasks: {
+        EXTI0: {
+            path: low,
+            priority: 1,
+            resources: [SHARED],
+            spawn: [soft],
+        },
+
+        EXTI1: {
+            path: high,
+            priority: 2,
+            resources: [SHARED],
+        },
+
+        soft: {
+            path: soft,
+            priority: 3,
+        },
+    },
+}
+
+thread_local! {
+    static LOG: RefCell<Vec<&'static str>> = RefCell::new(vec![]);
+}
+
+fn log(event: &'static str) {
+    LOG.with(|log| log.borrow_mut().push(event));
+}
+
+fn take() -> Vec<&'static str> {
+    LOG.with(|log| log.borrow_mut().drain(..).collect())
+}
+
+fn init(_p: init::Peripherals, _r: init::Resources, mut s: init::Spawn) {
+    // `init` runs with the tasks masked: `soft` runs once it returns
+    s.soft().unwrap();
+    log("init");
+}
+
+fn idle() -> ! {
+    loop {
+        rtfm::wfi();
+    }
+}
+
+fn low(t: &mut Threshold, mut r: EXTI0::Resources, mut s: EXTI0::Spawn) {
+    log("low");
+
+    // `high` preempts `low` as soon as it's pended ..
+    rtfm::set_pending(device::Interrupt::EXTI1);
+
+    // .. but not while `low` holds the resource they share ..
+    r.SHARED.claim_mut(t, |shared, _t| {
+        rtfm::set_pending(device::Interrupt::EXTI1);
+        *shared += 1;
+        log("claim");
+    });
+
+    // .. nor within a critical section
+    rtfm::atomic(t, |_t| {
+        rtfm::set_pending(device::Interrupt::EXTI1);
+        log("atomic");
+    });
+
+    // the software tasks run at their own priority
+    s.soft(t).unwrap();
+
+    log("end");
+}
+
+fn high(_t: &mut Threshold, mut r: EXTI1::Resources) {
+    *r.SHARED += 1;
+    log("high");
+}
+
+fn soft() {
+    log("soft");
+}
+
+#[test]
+fn preemption() {
+    rtfm_init();
+    assert_eq!(take(), ["init", "soft"]);
+    Recorder::take();
+
+    rtfm::set_pending(device::Interrupt::EXTI0);
+    assert_eq!(
+        take(),
+        ["low", "high", "claim", "high", "atomic", "high", "soft", "end"]
+    );
+
+    let events = Recorder::take();
+    let exti0 = rtfm_meta::tasks::EXTI0;
+    assert_eq!(events.first(), Some(&Event::TaskStart(exti0)));
+    assert_eq!(events.last(), Some(&Event::TaskEnd(exti0)));
+    assert!(events.contains(&Event::ClaimEnter(Some(rtfm_meta::resources::SHARED), 2)));
+}
@@ -0,0 +1,123 @@
+//! Exploring the preemptions of tasks of two priority levels that take units
+//! of the same multi-unit resource
+//!
+//! Run with `RUSTFLAGS="" cargo test --features host --test units`
+#![cfg(feature = "host")]
+#![deny(warnings)]
+
+// the generated code refers to `core`
+extern crate core;
+extern crate cortex_m;
+extern crate cortex_m_rtfm as rtfm;
+
+use std::cell::Cell;
+
+use rtfm::{app, Threshold, UnitResource};
+
+// stands in for the device crate
+mod device {
+    use cortex_m::interrupt::Nr;
+
+    pub const NVIC_PRIO_BITS: u8 = 4;
+
+    #[derive(Clone, Copy)]
+    pub enum Interrupt {
+        EXTI0,
+        EXTI1,
+    }
+
+    unsafe impl Nr for Interrupt {
+        fn nr(&self) -> u8 {
+            *self as u8
+        }
+    }
+
+    pub struct Peripherals {}
+
+    impl Peripherals {
+        pub unsafe fn steal() -> Self {
+            Peripherals {}
+        }
+    }
+
+    pub struct CorePeripherals {}
+
+    impl CorePeripherals {
+        pub unsafe fn steal() -> Self {
+            CorePeripherals {}
+        }
+    }
+}
+
+app! {
+    device: device,
+
+    resources: {
+        static BUFFERS: [u32; 2] = [0; 2];
+    },
+
+    tasks: {
+        // holds one unit at a time; while it does `high` can't get both
+        EXTI0: {
+            path: low,
+            priority: 1,
+            resources: [BUFFERS],
+        },
+
+        EXTI1: {
+            path: high,
+            priority: 2,
+            resources: [BUFFERS],
+            units: { BUFFERS: 2 },
+        },
+    },
+}
+
+thread_local! {
+    static HIGH: Cell<u32> = Cell::new(0);
+}
+
+fn init(_p: init::Peripherals, _r: init::Resources) {}
+
+fn idle() -> ! {
+    loop {
+        rtfm::wfi();
+    }
+}
+
+fn low(t: &mut Threshold, mut r: EXTI0::Resources) {
+    r.BUFFERS.claim_units(t, 1, |buffers, _t| {
+        assert_eq!(buffers.len(), 1);
+        *buffers.get_mut(0).unwrap() += 1;
+    });
+}
+
+fn high(t: &mut Threshold, mut r: EXTI1::Resources) {
+    r.BUFFERS.claim_units(t, 2, |buffers, _t| {
+        assert_eq!(buffers.len(), 2);
+        *buffers.get_mut(0).unwrap() += 1;
+        *buffers.get_mut(1).unwrap() += 1;
+    });
+    HIGH.with(|high| high.set(high.get() + 1));
+}
+
+#[test]
+fn two_levels() {
+    let schedules = rtfm::host::explore(1, || {
+        unsafe { rtfm_reset() };
+        HIGH.with(|high| high.set(0));
+        rtfm_init();
+
+        rtfm::set_pending(device::Interrupt::EXTI0);
+        // the units of `low` are back in the pool
+        rtfm::set_pending(device::Interrupt::EXTI1);
+
+        let buffers = unsafe { *rtfm_resources().BUFFERS };
+        let high = HIGH.with(|high| high.get());
+        assert_eq!(buffers[0] + buffers[1], 1 + 2 * high);
+    });
+
+    // no preemption, or `high` preempting `low` before it takes its unit or
+    // after it has returned it; never while it holds it
+    assert_eq!(schedules, 3);
+}
@@ -0,0 +1,79 @@
+//! Prints the task table embedded in the `.rtfm_meta` section of an RTFM application
+//!
+//! ``` text
+//! rtfm-meta <elf>
+//! ```
+
+extern crate rtfm_tools;
+extern crate xmas_elf;
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::process;
+
+use rtfm_tools::errors::*;
+use rtfm_tools::meta::{self, Source};
+use xmas_elf::ElfFile;
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        for e in e.iter().skip(1) {
+            eprintln!("caused by: {}", e);
+        }
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let mut args = env::args().skip(1);
+    let path = match (args.next(), args.next()) {
+        (Some(path), None) => path,
+        _ => bail!("usage: rtfm-meta <elf>"),
+    };
+
+    let mut bytes = vec![];
+    File::open(&path)
+        .and_then(|mut f| f.read_to_end(&mut bytes))
+        .chain_err(|| format!("reading {}", path))?;
+    let elf = ElfFile::new(&bytes)?;
+
+    let table = meta::read(&elf)?;
+
+    println!(
+        "{:<24} {:>8} {:>12} {:>12}  {}",
+        "task", "priority", "source", "interarrival", "resources (ceiling)"
+    );
+    for task in &table.tasks {
+        let source = match task.source {
+            Source::Exception(nr) => format!("exception {}", nr),
+            Source::Interrupt(Some(nr)) => format!("irq {}", nr),
+            Source::Interrupt(None) => "irq ?".to_owned(),
+            Source::Software(Some(nr)) => format!("sw irq {}", nr),
+            Source::Software(None) => "sw irq ?".to_owned(),
+        };
+
+        let resources = task.resources
+            .iter()
+            .map(|&i| {
+                let resource = &table.resources[i];
+                match resource.ceiling {
+                    Some(ceiling) => format!("{} ({})", resource.name, ceiling),
+                    None => resource.name.clone(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        println!(
+            "{:<24} {:>8} {:>12} {:>12}  {}",
+            task.name,
+            task.priority,
+            source,
+            task.interarrival,
+            resources.join(", ")
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! Bounds the worst case usage of the shared stack of an RTFM application
+//!
+//! ``` text
+//! rtfm-stack [--app target/rtfm/app.json] [--elf <elf>] [--memory memory.x] [--fpu]
+//! ```
+//!
+//! Exits with a non-zero code if the bound exceeds the RAM left for the stack.
+
+extern crate rtfm_tools;
+extern crate xmas_elf;
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::process;
+
+use rtfm_tools::errors::*;
+use rtfm_tools::model::App;
+use rtfm_tools::{elf, memory, stack};
+use xmas_elf::ElfFile;
+
+fn main() {
+    match run() {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            for e in e.iter().skip(1) {
+                eprintln!("caused by: {}", e);
+            }
+            process::exit(2);
+        }
+    }
+}
+
+fn run() -> Result<bool> {
+    let mut app = "target/rtfm/app.json".to_owned();
+    let mut elf = None;
+    let mut memory = "memory.x".to_owned();
+    let mut frame = stack::FRAME;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--app" => app = args.next().ok_or("`--app` expects a path")?,
+            "--elf" => elf = Some(args.next().ok_or("`--elf` expects a path")?),
+            "--memory" => memory = args.next().ok_or("`--memory` expects a path")?,
+            "--fpu" => frame = stack::FRAME_FPU,
+            _ => bail!(
+                "unknown argument `{}`\n\
+                 usage: rtfm-stack [--app <app.json>] [--elf <elf>] [--memory <memory.x>] [--fpu]",
+                arg
+            ),
+        }
+    }
+
+    let app = App::load(&app)?;
+
+    let mut script = String::new();
+    File::open(&memory)
+        .and_then(|mut f| f.read_to_string(&mut script))
+        .chain_err(|| format!("reading {}", memory))?;
+    let ram = memory::ram(&script).chain_err(|| format!("parsing {}", memory))?;
+
+    let mut bytes = vec![];
+    let (program, statics) = if let Some(ref path) = elf {
+        File::open(path)
+            .and_then(|mut f| f.read_to_end(&mut bytes))
+            .chain_err(|| format!("reading {}", path))?;
+        let elf = ElfFile::new(&bytes)?;
+
+        let program = stack::Program {
+            frames: elf::stack_sizes(&elf)?,
+            calls: elf::calls(&elf)?,
+        };
+
+        (Some(program), elf::static_ram(&elf))
+    } else {
+        (None, 0)
+    };
+
+    let bound = stack::bound(&app, program.as_ref(), frame)?;
+
+    println!("{:>5}  {:<24} {:>8}", "level", "task", "stack");
+    if let Some(ref init) = bound.init {
+        println!("{:>5}  {:<24} {:>8}", "-", init.name, init.stack);
+    }
+    println!("{:>5}  {:<24} {:>8}", 0, bound.idle.name, bound.idle.stack);
+    for &(priority, ref usage) in &bound.levels {
+        println!(
+            "{:>5}  {:<24} {:>8} (+{} frame)",
+            priority, usage.name, usage.stack, bound.frame
+        );
+    }
+
+    let total = u64::from(bound.total());
+    let available = ram.length.saturating_sub(statics);
+
+    println!();
+    println!("worst case stack usage: {} bytes", total);
+    println!(
+        "RAM: {} bytes, {} used by static variables, {} left for the stack ({:.1}% used)",
+        ram.length,
+        statics,
+        available,
+        100. * total as f64 / available as f64
+    );
+
+    if total > available {
+        eprintln!("error: the stack may overflow into the static variables");
+        Ok(false)
+    } else {
+        Ok(true)
+    }
+}
@@ -0,0 +1,155 @@
+//! Prints, or exports, the event trace logged by `rtfm::trace`
+//!
+//! ``` text
+//! rtfm-trace <dump> [--app target/rtfm/app.json] [--clock <hz>]
+//!            [--chrome <trace.json>] [--ctf <dir>]
+//! ```
+//!
+//! `<dump>` is a memory dump of the `RTFM_TRACE` buffer, e.g. from GDB:
+//! `dump binary value trace.bin RTFM_TRACE`. The tasks and resources are named
+//! after the application description, if available.
+//!
+//! `--chrome` writes the trace as Chrome trace JSON, for Perfetto and
+//! `chrome://tracing`, and `--ctf` as a Common Trace Format trace, for Trace
+//! Compass and Babeltrace, instead of printing it. `--clock` is the frequency
+//! of the cycle counter, 1 MHz by default.
+
+extern crate rtfm_tools;
+
+use std::env;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process;
+
+use rtfm_tools::errors::*;
+use rtfm_tools::model::App;
+use rtfm_tools::export::{self, resource_name, task_name};
+use rtfm_tools::trace::{self, Kind};
+
+const USAGE: &str = "usage: rtfm-trace <dump> [--app <app.json>] [--clock <hz>] \
+                     [--chrome <trace.json>] [--ctf <dir>]";
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        for e in e.iter().skip(1) {
+            eprintln!("caused by: {}", e);
+        }
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let mut dump = None;
+    let mut app = None;
+    let mut clock = 1_000_000;
+    let mut chrome = None;
+    let mut ctf = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--app" => app = Some(args.next().ok_or("`--app` expects a path")?),
+            "--clock" => {
+                let hz = args.next().ok_or("`--clock` expects a frequency")?;
+                clock = hz
+                    .parse()
+                    .chain_err(|| format!("invalid clock frequency `{}`", hz))?;
+            }
+            "--chrome" => chrome = Some(args.next().ok_or("`--chrome` expects a path")?),
+            "--ctf" => ctf = Some(args.next().ok_or("`--ctf` expects a directory")?),
+            _ if dump.is_none() && !arg.starts_with("--") => dump = Some(arg),
+            _ => bail!("unknown argument `{}`\n{}", arg, USAGE),
+        }
+    }
+    let dump = dump.ok_or(USAGE)?;
+
+    let app = match app {
+        Some(path) => Some(App::load(&path)?),
+        None if Path::new("target/rtfm/app.json").exists() => {
+            Some(App::load("target/rtfm/app.json")?)
+        }
+        None => None,
+    };
+
+    let mut bytes = vec![];
+    File::open(&dump)
+        .and_then(|mut f| f.read_to_end(&mut bytes))
+        .chain_err(|| format!("reading {}", dump))?;
+
+    let trace = trace::decode(&bytes)?;
+    let app = app.as_ref();
+
+    if trace.lost != 0 {
+        println!("({} older events were overwritten)", trace.lost);
+    }
+
+    if chrome.is_some() || ctf.is_some() {
+        if let Some(path) = chrome {
+            let json = export::chrome(&trace.events, app, clock)?;
+            File::create(&path)
+                .and_then(|mut f| f.write_all(json.as_bytes()))
+                .chain_err(|| format!("writing {}", path))?;
+        }
+
+        if let Some(dir) = ctf {
+            export::ctf(&trace.events, app, clock)?.write(&dir)?;
+        }
+
+        return Ok(());
+    }
+
+    let task = |i: usize| task_name(app, i);
+    let resource = |i: Option<usize>| resource_name(app, i);
+
+    println!("{:>10}  {}", "cycles", "event");
+    let start = trace.events.first().map(|e| e.timestamp).unwrap_or(0);
+    // the tasks that are running, innermost last
+    let mut running: Vec<usize> = vec![];
+    for event in &trace.events {
+        let cycles = event.timestamp.wrapping_sub(start);
+        let context = running
+            .last()
+            .map(|&t| task(t))
+            .unwrap_or_else(|| "idle".to_owned());
+
+        let (depth, line) = match event.kind {
+            Kind::TaskStart(t) => {
+                let line = if running.is_empty() {
+                    format!("{} starts", task(t))
+                } else {
+                    format!("{} starts, preempting {}", task(t), context)
+                };
+                running.push(t);
+                (running.len() - 1, line)
+            }
+            Kind::TaskEnd(t) => {
+                if running.last() == Some(&t) {
+                    running.pop();
+                }
+                (running.len(), format!("{} ends", task(t)))
+            }
+            Kind::ClaimEnter(r, ceiling) => (
+                running.len(),
+                format!("{} claims {} (ceiling {})", context, resource(r), ceiling),
+            ),
+            Kind::ClaimExit(r) => (
+                running.len(),
+                format!("{} releases {}", context, resource(r)),
+            ),
+            Kind::ThresholdEnter(ceiling) => (
+                running.len(),
+                format!("{} raises its threshold to {}", context, ceiling),
+            ),
+            Kind::ThresholdExit => (
+                running.len(),
+                format!("{} lowers its threshold", context),
+            ),
+        };
+
+        println!("{:>10}  {:width$}{}", cycles, "", line, width = 2 * depth);
+    }
+
+    Ok(())
+}
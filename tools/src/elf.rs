@@ -0,0 +1,279 @@
+//! Information extracted from ELF files
+
+use std::collections::HashMap;
+
+use xmas_elf::ElfFile;
+use xmas_elf::header::Class;
+use xmas_elf::sections::{SectionData, SHF_ALLOC, SHF_EXECINSTR, SHF_WRITE};
+use xmas_elf::symbol_table::{Entry, Type};
+
+use errors::*;
+
+/// Functions called by each function
+///
+/// `None` if some of the callees of the function are unknown: it calls through
+/// a function pointer or branches to an address that's not a function.
+pub type CallGraph = HashMap<String, Option<Vec<String>>>;
+
+/// Returns the address of each function symbol, with the Thumb bit cleared
+pub fn functions(elf: &ElfFile) -> Result<HashMap<String, u64>> {
+    Ok(symbols(elf)?
+        .into_iter()
+        .map(|(name, address, _)| (name, address))
+        .collect())
+}
+
+/// Returns the name, address (with the Thumb bit cleared) and size of each
+/// function symbol
+fn symbols(elf: &ElfFile) -> Result<Vec<(String, u64, u64)>> {
+    fn collect<E>(elf: &ElfFile, entries: &[E], symbols: &mut Vec<(String, u64, u64)>) -> Result<()>
+    where
+        E: Entry,
+    {
+        for entry in entries {
+            if entry.get_type()? == Type::Func {
+                symbols.push((
+                    entry.get_name(elf)?.to_owned(),
+                    entry.value() & !1,
+                    entry.size(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    let symtab = elf.find_section_by_name(".symtab")
+        .ok_or("`.symtab` section not found; was the ELF stripped?")?;
+
+    let mut symbols = vec![];
+    match symtab.get_data(elf)? {
+        SectionData::SymbolTable32(entries) => collect(elf, entries, &mut symbols)?,
+        SectionData::SymbolTable64(entries) => collect(elf, entries, &mut symbols)?,
+        _ => bail!("malformed `.symtab` section"),
+    }
+
+    Ok(symbols)
+}
+
+/// Returns the call graph of the program
+///
+/// The graph is built by decoding the Thumb branch instructions of each
+/// function: `BL` is a call and a `B` whose target lies outside the function
+/// is a tail call. `BLX Rm`, and `BX Rm` with a register other than `LR`, make
+/// the callees of the function unknown, as does a branch to an address that's
+/// not the start of a function or a function whose code is not in the ELF.
+pub fn calls(elf: &ElfFile) -> Result<CallGraph> {
+    let symbols = symbols(elf)?;
+    let names = symbols
+        .iter()
+        .map(|&(ref name, address, _)| (address, name))
+        .collect::<HashMap<_, _>>();
+
+    let sections = elf.section_iter()
+        .filter(|section| section.flags() & SHF_EXECINSTR != 0)
+        .collect::<Vec<_>>();
+
+    let mut graph = HashMap::new();
+    for &(ref name, address, size) in &symbols {
+        let code = sections
+            .iter()
+            .find(|section| {
+                section.address() <= address
+                    && address + size <= section.address() + section.size()
+            })
+            .map(|section| {
+                let start = (address - section.address()) as usize;
+                &section.raw_data(elf)[start..start + size as usize]
+            });
+
+        graph.insert(
+            name.clone(),
+            code.and_then(|code| callees(code, address, &names)),
+        );
+    }
+
+    Ok(graph)
+}
+
+/// Decodes the callees of the function at `address` whose machine code is
+/// `code`; `None` if some of them are unknown
+fn callees(code: &[u8], address: u64, names: &HashMap<u64, &String>) -> Option<Vec<String>> {
+    let end = address + code.len() as u64;
+    let halfword = |i: usize| u32::from(code[i]) | u32::from(code[i + 1]) << 8;
+
+    let mut callees = vec![];
+    let mut i = 0;
+    while i + 1 < code.len() {
+        let pc = address + i as u64;
+        let hw = halfword(i);
+
+        let (offset, call) = if hw >> 11 >= 0b11101 {
+            // 32-bit instruction
+            if i + 3 >= code.len() {
+                break;
+            }
+
+            let hw2 = halfword(i + 2);
+            i += 4;
+
+            if hw & 0xf800 != 0xf000 {
+                continue;
+            }
+
+            let s = (hw >> 10) & 1;
+            let j1 = (hw2 >> 13) & 1;
+            let j2 = (hw2 >> 11) & 1;
+            let imm11 = hw2 & 0x7ff;
+            match hw2 & 0xd000 {
+                // BL and B.W (encoding T4)
+                0xd000 | 0x9000 => {
+                    let i1 = !(j1 ^ s) & 1;
+                    let i2 = !(j2 ^ s) & 1;
+                    let imm10 = hw & 0x3ff;
+                    let imm = s << 24 | i1 << 23 | i2 << 22 | imm10 << 12 | imm11 << 1;
+
+                    (sign_extend(imm, 25), hw2 & 0x4000 != 0)
+                }
+                // conditional B.W (encoding T3)
+                0x8000 if (hw >> 6) & 0xe != 0xe => {
+                    let imm6 = hw & 0x3f;
+                    let imm = s << 20 | j2 << 19 | j1 << 18 | imm6 << 12 | imm11 << 1;
+
+                    (sign_extend(imm, 21), false)
+                }
+                _ => continue,
+            }
+        } else {
+            i += 2;
+
+            if hw & 0xff00 == 0x4700 {
+                // BX / BLX register
+                let link = hw & 0x80 != 0;
+                let rm = (hw >> 3) & 0xf;
+                if link || rm != 14 {
+                    return None;
+                }
+
+                continue;
+            } else if hw & 0xf000 == 0xd000 && (hw >> 8) & 0xe != 0xe {
+                // conditional B (encoding T1)
+                (sign_extend((hw & 0xff) << 1, 9), false)
+            } else if hw & 0xf800 == 0xe000 {
+                // B (encoding T2)
+                (sign_extend((hw & 0x7ff) << 1, 12), false)
+            } else {
+                continue;
+            }
+        };
+
+        let target = (pc as i64 + 4 + offset) as u64;
+
+        // branches within the function are not calls
+        if !call && address <= target && target < end {
+            continue;
+        }
+
+        callees.push((*names.get(&target)?).clone());
+    }
+
+    callees.sort();
+    callees.dedup();
+    Some(callees)
+}
+
+/// Sign extends the `bits` wide number `value`
+fn sign_extend(value: u32, bits: u32) -> i64 {
+    let shift = 32 - bits;
+    i64::from(((value << shift) as i32) >> shift)
+}
+
+/// Returns the stack usage, in bytes, of each function as reported in the
+/// `.stack_sizes` section
+///
+/// The section is emitted by `rustc` when the `-Z emit-stack-sizes` flag is
+/// used. Note that these are the sizes of the *frames* of the functions; the
+/// stack usage of the functions they call is not included (see `calls` and
+/// `stack::usage`).
+pub fn stack_sizes(elf: &ElfFile) -> Result<HashMap<String, u64>> {
+    let section = elf.find_section_by_name(".stack_sizes")
+        .ok_or("`.stack_sizes` section not found; build with `-Z emit-stack-sizes`")?;
+
+    let address_size = match elf.header.pt1.class() {
+        Class::ThirtyTwo => 4,
+        Class::SixtyFour => 8,
+        _ => bail!("unknown ELF class"),
+    };
+
+    // the section is a list of (address, ULEB128 encoded size) pairs
+    let mut sizes_by_address = HashMap::new();
+    let mut data = section.raw_data(elf);
+    while !data.is_empty() {
+        ensure!(data.len() > address_size, "truncated `.stack_sizes` section");
+
+        let address = data[..address_size]
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &byte| (acc << 8) | u64::from(byte));
+        data = &data[address_size..];
+
+        let (size, read) = uleb128(data).ok_or("truncated `.stack_sizes` section")?;
+        data = &data[read..];
+
+        sizes_by_address.insert(address & !1, size);
+    }
+
+    Ok(functions(elf)?
+        .into_iter()
+        .filter_map(|(name, address)| {
+            sizes_by_address
+                .get(&address)
+                .map(|&size| (name, size))
+        })
+        .collect())
+}
+
+/// Returns the entries of the vector table (the `.vector_table` section), with
+/// the Thumb bit cleared
+///
+/// Entry `0` is the initial value of the stack pointer; entry `n` is the
+/// handler of exception `n`; the handler of interrupt `i` is entry `16 + i`.
+pub fn vectors(elf: &ElfFile) -> Result<Vec<u64>> {
+    let section = elf.find_section_by_name(".vector_table")
+        .ok_or("`.vector_table` section not found")?;
+
+    Ok(section
+        .raw_data(elf)
+        .chunks(4)
+        .map(|word| {
+            word.iter()
+                .rev()
+                .fold(0u64, |acc, &byte| (acc << 8) | u64::from(byte)) & !1
+        })
+        .collect())
+}
+
+/// Returns the RAM, in bytes, used by static variables (`.data`, `.bss`, etc.)
+pub fn static_ram(elf: &ElfFile) -> u64 {
+    elf.section_iter()
+        .filter(|section| {
+            let flags = section.flags();
+            flags & SHF_ALLOC != 0 && flags & SHF_WRITE != 0
+        })
+        .map(|section| section.size())
+        .sum()
+}
+
+/// Decodes an unsigned LEB128 number; returns the number and the bytes read
+fn uleb128(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0;
+    for (i, &byte) in bytes.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+
+    None
+}
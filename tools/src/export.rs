@@ -0,0 +1,482 @@
+//! Exporters of event traces to the formats of standard trace viewers
+//!
+//! The input is a list of `trace::Event`s, as decoded from a memory dump of
+//! the target buffer or built from another source, e.g. the events recorded by
+//! the host backend with consecutive indices as timestamps. The tasks and
+//! resources are named after the application description, if available.
+//!
+//! - `chrome` writes the Trace Event JSON format that Perfetto and
+//!   `chrome://tracing` open. Each task is a track (a thread of the `rtfm`
+//!   process) where its runs are slices, and its claims are slices nested in
+//!   them, named after the resource and with the ceiling as argument. The
+//!   span a task runs at its `threshold` is a `threshold` slice.
+//! - `ctf` writes a Common Trace Format (CTF 1.8) trace, a `metadata` file and
+//!   a binary stream, that Trace Compass and Babeltrace open. The task and
+//!   resource indices are enumerations that map to their names.
+//!
+//! The cycle counter wraps around every 2^32 cycles, so consecutive events are
+//! assumed to be less than that apart.
+
+use std::fmt::Write;
+use std::fs::{self, File};
+use std::io::Write as IoWrite;
+use std::path::Path;
+
+use serde_json;
+
+use errors::*;
+use model::App;
+use trace::{Event, Kind};
+
+/// Name of the task `task`
+pub fn task_name(app: Option<&App>, task: usize) -> String {
+    match app.and_then(|app| app.tasks.get(task)) {
+        Some(task) => task.name.clone(),
+        None => format!("task #{}", task),
+    }
+}
+
+/// Name of the resource `resource`; `None` are the queues of the runtime
+pub fn resource_name(app: Option<&App>, resource: Option<usize>) -> String {
+    match resource {
+        Some(i) => match app.and_then(|app| app.resources.get(i)) {
+            Some(resource) => resource.name.clone(),
+            None => format!("resource #{}", i),
+        },
+        None => "queue".to_owned(),
+    }
+}
+
+/// Cycles elapsed since the first event, for each event
+pub fn timeline(events: &[Event]) -> Vec<u64> {
+    let mut cycles = 0;
+    let mut last = events.first().map(|e| e.timestamp).unwrap_or(0);
+    events
+        .iter()
+        .map(|e| {
+            cycles += u64::from(e.timestamp.wrapping_sub(last));
+            last = e.timestamp;
+            cycles
+        })
+        .collect()
+}
+
+/// Runs `f` on each event along with the task that was running when it
+/// happened (`None` is `idle`)
+///
+/// The task of a `TaskStart` event is the task it preempts.
+fn walk<F>(events: &[Event], mut f: F)
+where
+    F: FnMut(&Event, Option<usize>),
+{
+    // the tasks that are running, innermost last
+    let mut running = vec![];
+    for event in events {
+        f(event, running.last().cloned());
+
+        match event.kind {
+            Kind::TaskStart(t) => running.push(t),
+            Kind::TaskEnd(t) => {
+                if running.last() == Some(&t) {
+                    running.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ChromeEvent {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cat: Option<&'static str>,
+    ph: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ts: Option<f64>,
+    pid: u32,
+    tid: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    args: Option<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct ChromeTrace {
+    #[serde(rename = "traceEvents")]
+    trace_events: Vec<ChromeEvent>,
+    #[serde(rename = "displayTimeUnit")]
+    display_time_unit: &'static str,
+}
+
+/// Exports `events` as Chrome / Perfetto trace JSON; `clock` is the frequency
+/// of the cycle counter, in Hz
+pub fn chrome(events: &[Event], app: Option<&App>, clock: u32) -> Result<String> {
+    ensure!(clock != 0, "the clock frequency must be non-zero");
+
+    // `idle` is thread 0, task `i` is thread `i + 1`
+    let tid = |task: Option<usize>| task.map(|t| t + 1).unwrap_or(0);
+    let us = |cycles: u64| cycles as f64 * 1e6 / f64::from(clock);
+
+    let mut trace = vec![];
+    let mut tracks = vec![(0, "idle".to_owned(), 0)];
+    match app {
+        Some(app) => {
+            for (i, task) in app.tasks.iter().enumerate() {
+                tracks.push((i + 1, task.name.clone(), task.priority));
+            }
+        }
+        None => {
+            let mut tasks = events
+                .iter()
+                .filter_map(|e| match e.kind {
+                    Kind::TaskStart(t) | Kind::TaskEnd(t) => Some(t),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            tasks.sort();
+            tasks.dedup();
+
+            for t in tasks {
+                tracks.push((t + 1, task_name(app, t), 0));
+            }
+        }
+    }
+
+    for (track, name, priority) in tracks {
+        trace.push(ChromeEvent {
+            name: "thread_name".to_owned(),
+            cat: None,
+            ph: "M",
+            ts: None,
+            pid: 1,
+            tid: track,
+            args: Some(json(&[("name", name.into())])),
+        });
+        // higher priorities first
+        trace.push(ChromeEvent {
+            name: "thread_sort_index".to_owned(),
+            cat: None,
+            ph: "M",
+            ts: None,
+            pid: 1,
+            tid: track,
+            args: Some(json(&[("sort_index", (-i64::from(priority)).into())])),
+        });
+    }
+
+    let cycles = timeline(events);
+    // claims (and threshold raises) that are open on each track; unmatched
+    // ends of a truncated trace are dropped
+    let mut claims = vec![0; tracks_len(app, events) + 1];
+    let mut index = 0;
+    walk(events, |event, running| {
+        let ts = Some(us(cycles[index]));
+        index += 1;
+
+        match event.kind {
+            Kind::TaskStart(t) => trace.push(ChromeEvent {
+                name: task_name(app, t),
+                cat: Some("task"),
+                ph: "B",
+                ts,
+                pid: 1,
+                tid: tid(Some(t)),
+                args: running.map(|r| json(&[("preempts", task_name(app, r).into())])),
+            }),
+            Kind::TaskEnd(t) => {
+                if running == Some(t) {
+                    trace.push(ChromeEvent {
+                        name: task_name(app, t),
+                        cat: Some("task"),
+                        ph: "E",
+                        ts,
+                        pid: 1,
+                        tid: tid(Some(t)),
+                        args: None,
+                    })
+                }
+            }
+            Kind::ClaimEnter(r, ceiling) => {
+                claims[tid(running)] += 1;
+                trace.push(ChromeEvent {
+                    name: resource_name(app, r),
+                    cat: Some("claim"),
+                    ph: "B",
+                    ts,
+                    pid: 1,
+                    tid: tid(running),
+                    args: Some(json(&[
+                        ("resource", resource_name(app, r).into()),
+                        ("ceiling", ceiling.into()),
+                    ])),
+                })
+            }
+            Kind::ClaimExit(r) => {
+                if claims[tid(running)] != 0 {
+                    claims[tid(running)] -= 1;
+                    trace.push(ChromeEvent {
+                        name: resource_name(app, r),
+                        cat: Some("claim"),
+                        ph: "E",
+                        ts,
+                        pid: 1,
+                        tid: tid(running),
+                        args: None,
+                    })
+                }
+            }
+            Kind::ThresholdEnter(ceiling) => {
+                claims[tid(running)] += 1;
+                trace.push(ChromeEvent {
+                    name: "threshold".to_owned(),
+                    cat: Some("threshold"),
+                    ph: "B",
+                    ts,
+                    pid: 1,
+                    tid: tid(running),
+                    args: Some(json(&[("ceiling", ceiling.into())])),
+                })
+            }
+            Kind::ThresholdExit => {
+                if claims[tid(running)] != 0 {
+                    claims[tid(running)] -= 1;
+                    trace.push(ChromeEvent {
+                        name: "threshold".to_owned(),
+                        cat: Some("threshold"),
+                        ph: "E",
+                        ts,
+                        pid: 1,
+                        tid: tid(running),
+                        args: None,
+                    })
+                }
+            }
+        }
+    });
+
+    Ok(serde_json::to_string_pretty(&ChromeTrace {
+        trace_events: trace,
+        display_time_unit: "ns",
+    })?)
+}
+
+/// Number of task tracks: one per task of the application, or up to the
+/// highest task index in `events`
+fn tracks_len(app: Option<&App>, events: &[Event]) -> usize {
+    let max = events
+        .iter()
+        .filter_map(|e| match e.kind {
+            Kind::TaskStart(t) | Kind::TaskEnd(t) => Some(t + 1),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+
+    app.map(|app| app.tasks.len()).unwrap_or(0).max(max)
+}
+
+fn json(fields: &[(&str, serde_json::Value)]) -> serde_json::Value {
+    serde_json::Value::Object(
+        fields
+            .iter()
+            .map(|&(k, ref v)| (k.to_owned(), v.clone()))
+            .collect(),
+    )
+}
+
+/// A Common Trace Format trace
+pub struct Ctf {
+    /// The TSDL description of the trace, the `metadata` file
+    pub metadata: String,
+    /// The single event stream of the trace
+    pub stream: Vec<u8>,
+}
+
+impl Ctf {
+    /// Writes the trace into `dir`, as the `metadata` and `stream` files
+    pub fn write<P>(&self, dir: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).chain_err(|| format!("creating {}", dir.display()))?;
+
+        for &(name, bytes) in &[
+            ("metadata", self.metadata.as_bytes()),
+            ("stream", &self.stream[..]),
+        ] {
+            let path = dir.join(name);
+            File::create(&path)
+                .and_then(|mut f| f.write_all(bytes))
+                .chain_err(|| format!("writing {}", path.display()))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Magic number of the CTF packet header
+const CTF_MAGIC: u32 = 0xc1fc_1fc1;
+
+/// The `task` and `resource` fields of the events that have none: `idle` and
+/// the queues of the runtime, respectively
+const CTF_NONE: u16 = 0xffff;
+
+/// Exports `events` as a Common Trace Format trace; `clock` is the frequency
+/// of the cycle counter, in Hz
+pub fn ctf(events: &[Event], app: Option<&App>, clock: u32) -> Result<Ctf> {
+    ensure!(clock != 0, "the clock frequency must be non-zero");
+
+    let mut tasks = String::new();
+    for t in 0..tracks_len(app, events) {
+        writeln!(tasks, "    \"{}\" = {},", task_name(app, t), t).unwrap();
+    }
+    writeln!(tasks, "    \"idle\" = {},", CTF_NONE).unwrap();
+
+    let mut resources = String::new();
+    let count = app.map(|app| app.resources.len()).unwrap_or(0).max(
+        events
+            .iter()
+            .filter_map(|e| match e.kind {
+                Kind::ClaimEnter(Some(r), _) | Kind::ClaimExit(Some(r)) => Some(r + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0),
+    );
+    for r in 0..count {
+        writeln!(resources, "    \"{}\" = {},", resource_name(app, Some(r)), r).unwrap();
+    }
+    writeln!(resources, "    \"queue\" = {},", CTF_NONE).unwrap();
+
+    let metadata = format!(
+        "/* CTF 1.8 */
+
+typealias integer {{ size = 8; align = 8; signed = false; }} := uint8_t;
+typealias integer {{ size = 16; align = 8; signed = false; }} := uint16_t;
+typealias integer {{ size = 32; align = 8; signed = false; }} := uint32_t;
+
+trace {{
+    major = 1;
+    minor = 8;
+    byte_order = le;
+    packet.header := struct {{
+        uint32_t magic;
+    }};
+}};
+
+clock {{
+    name = cycles;
+    freq = {clock};
+}};
+
+typealias integer {{
+    size = 64; align = 8; signed = false;
+    map = clock.cycles.value;
+}} := cycles_t;
+
+enum task_t : uint16_t {{
+{tasks}}};
+
+enum resource_t : uint16_t {{
+{resources}}};
+
+stream {{
+    event.header := struct {{
+        uint8_t id;
+        cycles_t timestamp;
+    }};
+}};
+
+event {{
+    name = \"task_start\";
+    id = 0;
+    fields := struct {{
+        task_t task;
+        task_t preempted;
+    }};
+}};
+
+event {{
+    name = \"task_end\";
+    id = 1;
+    fields := struct {{
+        task_t task;
+    }};
+}};
+
+event {{
+    name = \"claim_enter\";
+    id = 2;
+    fields := struct {{
+        task_t task;
+        resource_t resource;
+        uint8_t ceiling;
+    }};
+}};
+
+event {{
+    name = \"claim_exit\";
+    id = 3;
+    fields := struct {{
+        task_t task;
+        resource_t resource;
+    }};
+}};
+
+event {{
+    name = \"threshold_enter\";
+    id = 4;
+    fields := struct {{
+        task_t task;
+        uint8_t ceiling;
+    }};
+}};
+
+event {{
+    name = \"threshold_exit\";
+    id = 5;
+    fields := struct {{
+        task_t task;
+    }};
+}};
+",
+        clock = clock,
+        tasks = tasks,
+        resources = resources,
+    );
+
+    // `task_t` and `resource_t` fields
+    let id = |index: Option<usize>| {
+        le(index.map(|i| i as u64).unwrap_or(u64::from(CTF_NONE)), 2)
+    };
+    let cycles = timeline(events);
+
+    let mut stream = vec![];
+    stream.extend_from_slice(&le(u64::from(CTF_MAGIC), 4));
+    let mut index = 0;
+    walk(events, |event, running| {
+        let (kind, fields) = match event.kind {
+            Kind::TaskStart(t) => (0, vec![id(Some(t)), id(running)]),
+            Kind::TaskEnd(t) => (1, vec![id(Some(t))]),
+            Kind::ClaimEnter(r, ceiling) => (2, vec![id(running), id(r), vec![ceiling]]),
+            Kind::ClaimExit(r) => (3, vec![id(running), id(r)]),
+            Kind::ThresholdEnter(ceiling) => (4, vec![id(running), vec![ceiling]]),
+            Kind::ThresholdExit => (5, vec![id(running)]),
+        };
+
+        stream.push(kind);
+        stream.extend_from_slice(&le(cycles[index], 8));
+        for field in fields {
+            stream.extend_from_slice(&field);
+        }
+        index += 1;
+    });
+
+    Ok(Ctf { metadata, stream })
+}
+
+/// The `n` low bytes of `value`, little endian first
+fn le(value: u64, n: usize) -> Vec<u8> {
+    (0..n).map(|i| (value >> (8 * i)) as u8).collect()
+}
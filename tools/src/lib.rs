@@ -0,0 +1,26 @@
+//! Host side analysis tools for Real Time For the Masses (RTFM) applications
+//!
+//! These tools work on the description of the application that the `app!` macro writes to
+//! `target/rtfm/app.json` and, optionally, on the ELF file of the application. The task table the
+//! `app!` macro embeds in the `.rtfm_meta` section of the ELF can be read with the `meta` module,
+//! and memory dumps of the event trace logged by `rtfm::trace` are decoded by the `trace` module.
+//! The `export` module converts event traces to the formats of Perfetto and Trace Compass.
+#![deny(missing_docs)]
+#![deny(warnings)]
+
+#[macro_use]
+extern crate error_chain;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate xmas_elf;
+
+pub mod elf;
+pub mod errors;
+pub mod export;
+pub mod memory;
+pub mod meta;
+pub mod model;
+pub mod stack;
+pub mod trace;
@@ -0,0 +1,105 @@
+//! Memory regions declared in `memory.x` linker scripts
+
+use errors::*;
+
+/// A memory region
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Region {
+    /// Start address
+    pub origin: u64,
+    /// Size in bytes
+    pub length: u64,
+}
+
+/// Returns the `RAM` region declared in the `MEMORY` command of a `memory.x`
+/// linker script
+///
+/// ``` text
+/// MEMORY
+/// {
+///   FLASH : ORIGIN = 0x08000000, LENGTH = 64K
+///   RAM : ORIGIN = 0x20000000, LENGTH = 20K
+/// }
+/// ```
+pub fn ram(script: &str) -> Result<Region> {
+    region(script, "RAM")
+}
+
+/// Returns the region called `name` declared in the `MEMORY` command of a
+/// linker script
+pub fn region(script: &str, name: &str) -> Result<Region> {
+    let script = strip_comments(script);
+
+    for line in script.lines() {
+        let mut parts = line.splitn(2, ':');
+        let (region, attrs) = match (parts.next(), parts.next()) {
+            (Some(region), Some(attrs)) => (region.trim(), attrs),
+            _ => continue,
+        };
+
+        // ignore the optional attributes, e.g. `RAM (rwx) : ..`
+        if region.split_whitespace().next() != Some(name) {
+            continue;
+        }
+
+        let mut origin = None;
+        let mut length = None;
+        for attr in attrs.split(',') {
+            let mut kv = attr.splitn(2, '=');
+            let (key, value) = match (kv.next(), kv.next()) {
+                (Some(key), Some(value)) => (key.trim(), value.trim()),
+                _ => bail!("malformed `{}` region: `{}`", name, line.trim()),
+            };
+
+            match key {
+                "ORIGIN" | "org" | "o" => origin = Some(number(value)?),
+                "LENGTH" | "len" | "l" => length = Some(number(value)?),
+                _ => bail!("unknown attribute `{}` in the `{}` region", key, name),
+            }
+        }
+
+        return Ok(Region {
+            origin: origin.ok_or_else(|| format!("`{}` region has no ORIGIN", name))?,
+            length: length.ok_or_else(|| format!("`{}` region has no LENGTH", name))?,
+        });
+    }
+
+    bail!("no `{}` region found", name)
+}
+
+/// Parses numbers like `0x20000000`, `1024`, `20K` or `1M`
+fn number(s: &str) -> Result<u64> {
+    let (digits, multiplier) = if s.ends_with('K') {
+        (&s[..s.len() - 1], 1024)
+    } else if s.ends_with('M') {
+        (&s[..s.len() - 1], 1024 * 1024)
+    } else {
+        (s, 1)
+    };
+
+    let n = if digits.starts_with("0x") || digits.starts_with("0X") {
+        u64::from_str_radix(&digits[2..], 16)
+    } else {
+        digits.parse()
+    };
+
+    n.map(|n| n * multiplier)
+        .map_err(|_| format!("`{}` is not a valid number", s).into())
+}
+
+fn strip_comments(script: &str) -> String {
+    let mut out = String::with_capacity(script.len());
+
+    let mut rest = script;
+    while let Some(start) = rest.find("/*") {
+        out.push_str(&rest[..start]);
+
+        rest = match rest[start..].find("*/") {
+            Some(end) => &rest[start + end + 2..],
+            None => "",
+        };
+    }
+    out.push_str(rest);
+
+    out
+}
@@ -0,0 +1,212 @@
+//! The task table the `app!` macro places in the `.rtfm_meta` section
+//!
+//! Unlike `target/rtfm/app.json`, which describes the *last* application the
+//! `app!` macro expanded, this table is part of the binary so it always
+//! matches the ELF it's read from. All integers are little endian and strings
+//! are prefixed by their length as a `u8`:
+//!
+//! ``` text
+//! header:    b"RTFM" VERSION:u8 TASKS:u8 RESOURCES:u8 0:u8
+//! resource:  NAME:str CEILING:u8                      (0xff = unused)
+//! task:      NAME:str SYMBOL:str PRIORITY:u8 EXCEPTION:u8 (0xff = interrupt,
+//!            0xfe = software task) INTERARRIVAL:u32 N:u8 RESOURCE:u8 * N
+//!            (indices into the resources)
+//! ```
+//!
+//! The symbol of a software task is the handler of the interrupt that
+//! dispatches it. Interrupt numbers are not part of the table; `read` finds
+//! them in the vector table of the ELF.
+
+use xmas_elf::ElfFile;
+
+use elf;
+use errors::*;
+
+/// Version of the table layout this crate understands
+pub const VERSION: u8 = 2;
+
+/// Marks unused resources and interrupts
+const NONE: u8 = 0xff;
+
+/// Marks software tasks
+const SOFTWARE: u8 = 0xfe;
+
+/// The task table
+#[derive(Clone, Debug)]
+pub struct Table {
+    /// The tasks, sorted by name
+    pub tasks: Vec<Task>,
+    /// The resources, sorted by name
+    pub resources: Vec<Resource>,
+}
+
+/// A task
+#[derive(Clone, Debug)]
+pub struct Task {
+    /// Name of the task
+    pub name: String,
+    /// Symbol of the task handler; for software tasks, the handler of the
+    /// interrupt that dispatches them
+    pub symbol: String,
+    /// Priority of the task
+    pub priority: u8,
+    /// The exception or interrupt that triggers the task
+    pub source: Source,
+    /// Minimum time between two releases of the task, in clock cycles
+    pub interarrival: u32,
+    /// Resources the task has access to, as indices into `Table.resources`
+    pub resources: Vec<usize>,
+}
+
+/// The exception or interrupt that triggers a task
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Source {
+    /// Exception number
+    Exception(u8),
+    /// Interrupt number; `None` if it couldn't be found in the vector table
+    Interrupt(Option<u16>),
+    /// Software task, run by one of the interrupts reserved for dispatching;
+    /// number of that interrupt, `None` if it couldn't be found in the vector
+    /// table
+    Software(Option<u16>),
+}
+
+/// A resource
+#[derive(Clone, Debug)]
+pub struct Resource {
+    /// Name of the resource
+    pub name: String,
+    /// Ceiling of the resource; `None` if only `init` uses it
+    pub ceiling: Option<u8>,
+}
+
+/// Reads the task table from the `.rtfm_meta` section of `elf`
+///
+/// The interrupt numbers are looked up in the vector table using the address
+/// of the handler of each task.
+pub fn read(elf: &ElfFile) -> Result<Table> {
+    let section = elf.find_section_by_name(".rtfm_meta").ok_or(
+        "`.rtfm_meta` section not found; is `.rtfm_meta` declared in the linker script?",
+    )?;
+
+    let mut table = parse(section.raw_data(elf)).chain_err(|| "parsing `.rtfm_meta`")?;
+
+    // the vector table is only available when targeting Cortex-M devices
+    if let (Ok(functions), Ok(vectors)) = (elf::functions(elf), elf::vectors(elf)) {
+        for task in &mut table.tasks {
+            match task.source {
+                Source::Interrupt(ref mut nr) | Source::Software(ref mut nr) => {
+                    *nr = functions.get(&task.symbol).and_then(|address| {
+                        vectors
+                            .iter()
+                            .skip(16)
+                            .position(|vector| vector == address)
+                            .map(|i| i as u16)
+                    });
+                }
+                Source::Exception(_) => {}
+            }
+        }
+    }
+
+    Ok(table)
+}
+
+/// Parses the contents of the `.rtfm_meta` section
+///
+/// Interrupt numbers are left unresolved; see `read`.
+pub fn parse(bytes: &[u8]) -> Result<Table> {
+    let mut cursor = Cursor { bytes };
+
+    ensure!(cursor.take(4)? == b"RTFM", "not an RTFM task table");
+
+    let version = cursor.u8()?;
+    ensure!(
+        version == VERSION,
+        "unsupported table version {} (expected {})",
+        version,
+        VERSION
+    );
+
+    let ntasks = cursor.u8()?;
+    let nresources = cursor.u8()?;
+    cursor.u8()?;
+
+    let mut resources = vec![];
+    for _ in 0..nresources {
+        let name = cursor.string()?;
+        let ceiling = cursor.u8()?;
+
+        resources.push(Resource {
+            name,
+            ceiling: if ceiling == NONE { None } else { Some(ceiling) },
+        });
+    }
+
+    let mut tasks = vec![];
+    for _ in 0..ntasks {
+        let name = cursor.string()?;
+        let symbol = cursor.string()?;
+        let priority = cursor.u8()?;
+        let exception = cursor.u8()?;
+        let interarrival = cursor.u32()?;
+
+        let mut task_resources = vec![];
+        for _ in 0..cursor.u8()? {
+            let i = usize::from(cursor.u8()?);
+            ensure!(
+                i < resources.len(),
+                "task `{}` uses an unknown resource",
+                name
+            );
+            task_resources.push(i);
+        }
+
+        tasks.push(Task {
+            name,
+            symbol,
+            priority,
+            source: match exception {
+                NONE => Source::Interrupt(None),
+                SOFTWARE => Source::Software(None),
+                _ => Source::Exception(exception),
+            },
+            interarrival,
+            resources: task_resources,
+        });
+    }
+
+    Ok(Table { tasks, resources })
+}
+
+struct Cursor<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        ensure!(self.bytes.len() >= n, "truncated task table");
+
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(self.take(4)?
+            .iter()
+            .rev()
+            .fold(0, |acc, &byte| (acc << 8) | u32::from(byte)))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let n = usize::from(self.u8()?);
+        let bytes = self.take(n)?;
+
+        String::from_utf8(bytes.to_owned()).chain_err(|| "malformed string")
+    }
+}
@@ -0,0 +1,165 @@
+//! The application description written by the `app!` macro
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use serde_json;
+
+use errors::*;
+
+/// Version of the application description this crate understands
+pub const VERSION: u32 = 2;
+
+/// An RTFM application
+#[derive(Debug, Deserialize)]
+pub struct App {
+    /// Version of the description format
+    pub version: u32,
+    /// Path to the device crate
+    pub device: String,
+    /// `init` configuration
+    pub init: Init,
+    /// `idle` configuration
+    pub idle: Idle,
+    /// The tasks
+    pub tasks: Vec<Task>,
+    /// The interrupts that dispatch the software tasks, one per priority level
+    pub dispatchers: Vec<Handler>,
+    /// The `SysTick` handler that drives the timer queue, if any
+    pub timer_queue: Option<Handler>,
+    /// The resources, sorted by name
+    pub resources: Vec<Resource>,
+}
+
+impl App {
+    /// Loads the description from a JSON file
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        let mut json = String::new();
+        File::open(path)
+            .and_then(|mut f| f.read_to_string(&mut json))
+            .chain_err(|| format!("reading {}", path.display()))?;
+
+        App::parse(&json).chain_err(|| format!("parsing {}", path.display()))
+    }
+
+    /// Parses the JSON description
+    pub fn parse(json: &str) -> Result<Self> {
+        let app: App = serde_json::from_str(json)?;
+
+        ensure!(
+            app.version == VERSION,
+            "unsupported description version {} (expected {})",
+            app.version,
+            VERSION
+        );
+
+        Ok(app)
+    }
+
+    /// Looks up a task by name
+    pub fn task(&self, name: &str) -> Option<&Task> {
+        self.tasks.iter().find(|t| t.name == name)
+    }
+
+    /// Looks up a resource by name
+    pub fn resource(&self, name: &str) -> Option<&Resource> {
+        self.resources.iter().find(|r| r.name == name)
+    }
+}
+
+/// `init` configuration
+#[derive(Debug, Deserialize)]
+pub struct Init {
+    /// Path to the `init` function
+    pub path: String,
+    /// Resources owned by `init`
+    pub resources: Vec<String>,
+    /// Declared stack usage, in bytes
+    #[serde(default)]
+    pub stack: Option<u32>,
+}
+
+/// `idle` configuration
+#[derive(Debug, Deserialize)]
+pub struct Idle {
+    /// Path to the `idle` function
+    pub path: String,
+    /// Resources assigned to `idle`
+    pub resources: Vec<String>,
+    /// Length of the longest critical section on each resource
+    #[serde(default)]
+    pub critical_sections: BTreeMap<String, u32>,
+    /// Declared stack usage, in bytes
+    #[serde(default)]
+    pub stack: Option<u32>,
+}
+
+/// A task
+#[derive(Debug, Deserialize)]
+pub struct Task {
+    /// Name of the task
+    pub name: String,
+    /// `"exception"`, `"interrupt"` or `"software"`
+    pub kind: String,
+    /// Symbol of the handler the task runs from; the dispatcher for software
+    /// tasks
+    pub symbol: String,
+    /// Path to the task handler
+    pub path: String,
+    /// Priority of the task
+    pub priority: u8,
+    /// Preemption threshold of the task; the priority it runs at once it has
+    /// started
+    #[serde(default)]
+    pub threshold: Option<u8>,
+    /// Minimum time between two releases of the task, in clock cycles
+    pub interarrival: u32,
+    /// Relative deadline, in clock cycles
+    #[serde(default)]
+    pub deadline: Option<u32>,
+    /// Declared worst case execution time, in clock cycles
+    #[serde(default)]
+    pub wcet: Option<u32>,
+    /// Declared stack usage, in bytes
+    #[serde(default)]
+    pub stack: Option<u32>,
+    /// Whether the task is enabled after `init` returns
+    pub enabled: bool,
+    /// Resources assigned to this task
+    pub resources: Vec<String>,
+    /// Length of the longest critical section on each resource
+    #[serde(default)]
+    pub critical_sections: BTreeMap<String, u32>,
+}
+
+/// An interrupt or exception handler of the runtime
+#[derive(Debug, Deserialize)]
+pub struct Handler {
+    /// Priority the handler runs at
+    pub priority: u8,
+    /// Symbol of the handler
+    pub symbol: String,
+}
+
+/// A resource
+#[derive(Debug, Deserialize)]
+pub struct Resource {
+    /// Name of the resource
+    pub name: String,
+    /// Type of the resource
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// Whether the resource is initialized at runtime by `init`
+    pub late: bool,
+    /// Whether the resource is owned by `init`
+    pub init: bool,
+    /// Ceiling of the resource; `None` for resources owned by `init`
+    pub ceiling: Option<u8>,
+}
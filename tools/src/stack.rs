@@ -0,0 +1,237 @@
+//! Worst case usage of the shared stack
+//!
+//! All tasks run on a single stack. Once started, a task can only be preempted
+//! by tasks whose priority is *strictly* higher than its preemption threshold
+//! (its priority, unless it declares a `threshold`), so at any point in time
+//! the stack holds a chain of tasks of increasing priority on top of `idle`.
+//! The worst case is thus:
+//!
+//! ``` text
+//! max(init, idle + max_{chains} sum_{t in chain} (stack(t) + frame))
+//! ```
+//!
+//! where `frame` is the exception frame the hardware pushes on each
+//! preemption: 32 bytes, or 104 bytes if the FPU context is stacked. Without
+//! thresholds the worst chain takes the largest task of each priority level.
+//!
+//! Software tasks don't run from handlers of their own: the dispatcher of
+//! their priority level runs them, and the `SysTick` handler releases the
+//! scheduled ones. Both take part in the chains in their place, at the priority
+//! they run at.
+//!
+//! The stack usage of a handler is its frame plus the largest stack usage
+//! among the functions it calls, found by walking the call graph of the
+//! program. No bound is given if that walk can't complete: calls through
+//! function pointers, recursion or functions without a known frame.
+
+use std::cmp;
+use std::collections::HashMap;
+
+use elf::CallGraph;
+use model::App;
+
+use errors::*;
+
+/// Size of the exception frame without FPU context
+pub const FRAME: u32 = 32;
+
+/// Size of the exception frame with FPU context
+pub const FRAME_FPU: u32 = 104;
+
+/// The functions of a program, as found in its ELF
+pub struct Program {
+    /// Size of the stack frame of each function (see `elf::stack_sizes`)
+    pub frames: HashMap<String, u64>,
+    /// Functions called by each function (see `elf::calls`)
+    pub calls: CallGraph,
+}
+
+/// Stack usage of a single task (or of `init` / `idle`)
+#[derive(Clone, Debug)]
+pub struct Usage {
+    /// Name of the task, or symbol of the handler of the runtime
+    pub name: String,
+    /// Stack usage in bytes
+    pub stack: u32,
+}
+
+/// The worst case usage of the shared stack
+#[derive(Debug)]
+pub struct Bound {
+    /// Stack usage of `init`, if declared
+    pub init: Option<Usage>,
+    /// Stack usage of `idle`; if not declared, the stack usage of `main`,
+    /// which calls both `init` and `idle`
+    pub idle: Usage,
+    /// The tasks (and handlers of the runtime) of the preemption chain with
+    /// the largest stack usage, sorted by priority
+    pub levels: Vec<(u8, Usage)>,
+    /// Size of the exception frame pushed on each preemption
+    pub frame: u32,
+}
+
+impl Bound {
+    /// Worst case stack usage in bytes
+    pub fn total(&self) -> u32 {
+        let nested = self.levels
+            .iter()
+            .fold(self.idle.stack, |acc, &(_, ref usage)| {
+                acc + usage.stack + self.frame
+            });
+
+        // `init` runs with interrupts disabled so it can't be preempted
+        cmp::max(self.init.as_ref().map(|u| u.stack).unwrap_or(0), nested)
+    }
+}
+
+/// Worst case stack usage of `function`: its frame plus the largest stack
+/// usage among the functions it calls, directly or not
+pub fn usage(function: &str, program: &Program) -> Result<u64> {
+    fn walk<'a>(
+        function: &'a str,
+        program: &'a Program,
+        path: &mut Vec<&'a str>,
+        memo: &mut HashMap<&'a str, u64>,
+    ) -> Result<u64> {
+        if let Some(&usage) = memo.get(function) {
+            return Ok(usage);
+        }
+
+        ensure!(
+            !path.contains(&function),
+            "`{}` is recursive; its stack usage can't be bounded",
+            function
+        );
+
+        let frame = *program
+            .frames
+            .get(function)
+            .ok_or_else(|| format!("the stack frame of `{}` is unknown", function))?;
+        let callees = program
+            .calls
+            .get(function)
+            .and_then(|callees| callees.as_ref())
+            .ok_or_else(|| {
+                format!(
+                    "the callees of `{}` are unknown; it calls through a function pointer or \
+                     its code couldn't be decoded",
+                    function
+                )
+            })?;
+
+        path.push(function);
+        let mut deepest = 0;
+        for callee in callees {
+            deepest = cmp::max(deepest, walk(callee, program, path, memo)?);
+        }
+        path.pop();
+
+        memo.insert(function, frame + deepest);
+        Ok(frame + deepest)
+    }
+
+    walk(function, program, &mut vec![], &mut HashMap::new())
+        .chain_err(|| format!("bounding the stack usage of `{}`", function))
+}
+
+/// Computes the worst case usage of the shared stack
+///
+/// The stack usage declared in `app!` takes precedence; otherwise it's computed
+/// from the `program`, starting at the handler of the task. The dispatchers
+/// and the `SysTick` handler have no declared stack usage; they need the
+/// `program`.
+pub fn bound(app: &App, program: Option<&Program>, frame: u32) -> Result<Bound> {
+    let lookup = |symbol: &str, declared: Option<u32>| -> Result<Option<u32>> {
+        Ok(match (declared, program) {
+            (Some(stack), _) => Some(stack),
+            (None, Some(program)) => Some(usage(symbol, program)? as u32),
+            (None, None) => None,
+        })
+    };
+
+    let init = app.init.stack.map(|stack| Usage {
+        name: "init".to_owned(),
+        stack,
+    });
+
+    let idle = match app.idle.stack {
+        Some(stack) => Usage {
+            name: "idle".to_owned(),
+            stack,
+        },
+        None => Usage {
+            name: "main".to_owned(),
+            stack: lookup("main", None)?.ok_or(
+                "unknown stack usage of `idle`; declare `idle.stack` or provide the ELF",
+            )?,
+        },
+    };
+
+    // (priority, threshold, usage) sorted by decreasing priority
+    let mut tasks = vec![];
+    for task in app.tasks.iter().filter(|task| task.kind != "software") {
+        let stack = lookup(&task.symbol, task.stack)?.ok_or_else(|| {
+            format!(
+                "unknown stack usage of task `{}`; declare its `stack` or provide the ELF",
+                task.name
+            )
+        })?;
+
+        tasks.push((
+            task.priority,
+            task.threshold.unwrap_or(task.priority),
+            Usage {
+                name: task.name.clone(),
+                stack,
+            },
+        ));
+    }
+
+    // a dispatcher runs software tasks of any threshold; take the lowest one,
+    // its priority
+    for handler in app.dispatchers.iter().chain(&app.timer_queue) {
+        let stack = lookup(&handler.symbol, None)?.ok_or_else(|| {
+            format!(
+                "unknown stack usage of the `{}` handler of the runtime; provide the ELF",
+                handler.symbol
+            )
+        })?;
+
+        tasks.push((
+            handler.priority,
+            handler.priority,
+            Usage {
+                name: handler.symbol.clone(),
+                stack,
+            },
+        ));
+    }
+    tasks.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.2.name.cmp(&b.2.name)));
+
+    // `chains[i]`: stack usage of the worst chain that starts with `tasks[i]`
+    // and the index of the task that preempts it in that chain
+    let mut chains: Vec<(u32, Option<usize>)> = Vec::with_capacity(tasks.len());
+    for &(_, threshold, ref usage) in &tasks {
+        let next = (0..chains.len())
+            .filter(|&j| tasks[j].0 > threshold)
+            .max_by_key(|&j| (chains[j].0, cmp::Reverse(j)));
+
+        let above = next.map(|j| chains[j].0).unwrap_or(0);
+        chains.push((usage.stack + frame + above, next));
+    }
+
+    let mut levels = vec![];
+    let mut head = (0..chains.len()).max_by_key(|&i| (chains[i].0, cmp::Reverse(i)));
+    while let Some(i) = head {
+        let (priority, _, ref usage) = tasks[i];
+        levels.push((priority, usage.clone()));
+        head = chains[i].1;
+    }
+
+    Ok(Bound {
+        init,
+        idle,
+        levels,
+        frame,
+    })
+}
@@ -0,0 +1,131 @@
+//! Decoder of the event trace that `rtfm::trace` logs on the target
+//!
+//! The input is a memory dump of the `RTFM_TRACE` buffer. All integers are
+//! little endian:
+//!
+//! ``` text
+//! header:  MAGIC:u32 VERSION:u32 CAPACITY:u32 COUNT:u32
+//! entry:   TIMESTAMP:u32 KIND:u8 CEILING:u8 ID:u16       (CAPACITY times)
+//! ```
+//!
+//! Event `n` is stored in entry `n % CAPACITY`; only the last `CAPACITY`
+//! events survive.
+
+use errors::*;
+
+/// Identifies the buffer in a memory dump
+pub const MAGIC: u32 = 0x5452_5452;
+
+/// Version of the buffer layout this crate understands
+pub const VERSION: u32 = 2;
+
+const HEADER: usize = 16;
+const ENTRY: usize = 8;
+
+/// Marks the claims of the queues of the runtime
+const NONE: u16 = 0xffff;
+
+/// The decoded trace
+#[derive(Clone, Debug)]
+pub struct Trace {
+    /// The events, oldest first
+    pub events: Vec<Event>,
+    /// Number of older events that were overwritten
+    pub lost: u32,
+}
+
+/// A scheduling event
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Event {
+    /// Value of the cycle counter when the event was logged
+    pub timestamp: u32,
+    /// What happened
+    pub kind: Kind,
+}
+
+/// What happened
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    /// A task started; index into the tasks of the application
+    TaskStart(usize),
+    /// A task ran to completion
+    TaskEnd(usize),
+    /// The preemption threshold was raised to the given ceiling to access a
+    /// resource; `None` for the queues of the runtime
+    ClaimEnter(Option<usize>, u8),
+    /// The preemption threshold is being lowered after accessing a resource
+    ClaimExit(Option<usize>),
+    /// The task that just started raised the preemption threshold to its
+    /// `threshold`, the given ceiling
+    ThresholdEnter(u8),
+    /// The preemption threshold is being lowered back to the priority of the
+    /// task that's ending
+    ThresholdExit,
+}
+
+/// Decodes a memory dump of the `RTFM_TRACE` buffer
+pub fn decode(dump: &[u8]) -> Result<Trace> {
+    ensure!(dump.len() >= HEADER, "the dump is smaller than the trace header");
+
+    let magic = le32(dump, 0);
+    ensure!(magic == MAGIC, "not a trace buffer (magic {:#010x})", magic);
+
+    let version = le32(dump, 4);
+    ensure!(
+        version == VERSION,
+        "unsupported trace version {} (expected {})",
+        version,
+        VERSION
+    );
+
+    let capacity = le32(dump, 8) as usize;
+    let count = le32(dump, 12);
+    ensure!(capacity != 0, "the trace buffer has no entries");
+    ensure!(
+        dump.len() >= HEADER + capacity * ENTRY,
+        "the dump is truncated: {} bytes, expected {}",
+        dump.len(),
+        HEADER + capacity * ENTRY
+    );
+
+    let (first, len) = if (count as usize) <= capacity {
+        (0, count as usize)
+    } else {
+        (count as usize % capacity, capacity)
+    };
+
+    let mut events = Vec::with_capacity(len);
+    for i in 0..len {
+        let offset = HEADER + (first + i) % capacity * ENTRY;
+        let id = le16(dump, offset + 6);
+        let resource = if id == NONE { None } else { Some(usize::from(id)) };
+
+        let kind = match dump[offset + 4] {
+            1 => Kind::TaskStart(usize::from(id)),
+            2 => Kind::TaskEnd(usize::from(id)),
+            3 => Kind::ClaimEnter(resource, dump[offset + 5]),
+            4 => Kind::ClaimExit(resource),
+            5 => Kind::ThresholdEnter(dump[offset + 5]),
+            6 => Kind::ThresholdExit,
+            kind => bail!("unknown event kind {} in entry {}", kind, (first + i) % capacity),
+        };
+
+        events.push(Event {
+            timestamp: le32(dump, offset),
+            kind,
+        });
+    }
+
+    Ok(Trace {
+        events,
+        lost: count.saturating_sub(capacity as u32),
+    })
+}
+
+fn le16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from(bytes[offset]) | u16::from(bytes[offset + 1]) << 8
+}
+
+fn le32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from(le16(bytes, offset)) | u32::from(le16(bytes, offset + 2)) << 16
+}
@@ -0,0 +1,129 @@
+//! Golden outputs of the trace exporters
+extern crate rtfm_tools;
+
+use rtfm_tools::export;
+use rtfm_tools::model::App;
+use rtfm_tools::trace::{Event, Kind};
+
+/// `low` runs at its threshold, claims `SHARED` and a queue of the runtime,
+/// and is preempted by `high`
+const APP: &str = r#"{
+  "version": 2,
+  "device": "stm32f413",
+  "init": { "path": "init", "resources": [], "stack": null },
+  "idle": {
+    "path": "idle", "resources": [], "read_only": [], "critical_sections": {}, "stack": null
+  },
+  "tasks": [
+    {
+      "name": "EXTI0", "kind": "interrupt", "symbol": "EXTI0", "path": "low", "priority": 1,
+      "threshold": 2, "interarrival": 1000, "period": null, "offset": 0, "deadline": 1000,
+      "wcet": null, "stack": null, "enabled": true, "resources": ["SHARED"], "read_only": [],
+      "critical_sections": {}
+    },
+    {
+      "name": "EXTI1", "kind": "interrupt", "symbol": "EXTI1", "path": "high", "priority": 3,
+      "threshold": 3, "interarrival": 1000, "period": null, "offset": 0, "deadline": 1000,
+      "wcet": null, "stack": null, "enabled": true, "resources": ["SHARED"], "read_only": [],
+      "critical_sections": {}
+    }
+  ],
+  "dispatchers": [],
+  "timer_queue": null,
+  "resources": [
+    {
+      "name": "SHARED", "type": "u32", "late": false, "init": false, "ceiling": 3,
+      "read_ceiling": 3
+    }
+  ]
+}"#;
+
+fn events() -> Vec<Event> {
+    [
+        (100, Kind::TaskStart(0)),
+        (110, Kind::ThresholdEnter(2)),
+        (120, Kind::ClaimEnter(Some(0), 3)),
+        (130, Kind::ClaimExit(Some(0))),
+        (140, Kind::ClaimEnter(None, 3)),
+        (150, Kind::ClaimExit(None)),
+        (160, Kind::TaskStart(1)),
+        (170, Kind::TaskEnd(1)),
+        (180, Kind::ThresholdExit),
+        (190, Kind::TaskEnd(0)),
+    ]
+        .iter()
+        .map(|&(timestamp, kind)| Event { timestamp, kind })
+        .collect()
+}
+
+/// Cycle counter frequency at which a cycle lasts a microsecond
+const CLOCK: u32 = 1_000_000;
+
+#[test]
+fn chrome() {
+    let app = App::parse(APP).unwrap();
+
+    let json = export::chrome(&events(), Some(&app), CLOCK).unwrap();
+
+    assert_eq!(json, include_str!("export/chrome.json"));
+}
+
+#[test]
+fn ctf() {
+    let app = App::parse(APP).unwrap();
+
+    let ctf = export::ctf(&events(), Some(&app), CLOCK).unwrap();
+
+    assert_eq!(ctf.metadata, include_str!("export/metadata"));
+    // event header: ID:u8 TIMESTAMP:u64, then the fields of the event
+    assert_eq!(
+        &ctf.stream[..],
+        &[
+            // magic
+            0xc1, 0x1f, 0xfc, 0xc1,
+            // EXTI0 starts, preempting idle
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0xff, 0xff,
+            // threshold raised to 2
+            0x04, 0x0a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x02,
+            // SHARED claimed at ceiling 3
+            0x02, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x03,
+            // SHARED released
+            0x03, 0x1e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+            // queue claimed at ceiling 3
+            0x02, 0x28, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0xff, 0xff, 0x03,
+            // queue released
+            0x03, 0x32, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0xff, 0xff,
+            // EXTI1 starts, preempting EXTI0
+            0x00, 0x3c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x01, 0x00, 0x00, 0x00,
+            // EXTI1 ends
+            0x01, 0x46, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x01, 0x00,
+            // threshold lowered
+            0x05, 0x50, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+            // EXTI0 ends
+            0x01, 0x5a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ][..]
+    );
+}
+
+#[test]
+fn queue_and_threshold() {
+    let events = events();
+
+    assert_eq!(export::resource_name(None, Some(0)), "resource #0");
+    assert_eq!(export::resource_name(None, None), "queue");
+
+    // the threshold raise is not a claim of a queue
+    let json = export::chrome(&events, None, CLOCK).unwrap();
+    assert_eq!(json.matches("\"queue\"").count(), 3);
+    assert_eq!(json.matches("\"threshold\"").count(), 4);
+}
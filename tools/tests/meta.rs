@@ -0,0 +1,115 @@
+//! Decoding of the `.rtfm_meta` task table
+extern crate rtfm_tools;
+
+use rtfm_tools::meta::{self, Source};
+
+fn string(bytes: &mut Vec<u8>, s: &str) {
+    bytes.push(s.len() as u8);
+    bytes.extend_from_slice(s.as_bytes());
+}
+
+fn task(
+    bytes: &mut Vec<u8>,
+    name: &str,
+    symbol: &str,
+    priority: u8,
+    exception: u8,
+    interarrival: u32,
+    resources: &[u8],
+) {
+    string(bytes, name);
+    string(bytes, symbol);
+    bytes.push(priority);
+    bytes.push(exception);
+    for i in 0..4 {
+        bytes.push((interarrival >> (8 * i)) as u8);
+    }
+    bytes.push(resources.len() as u8);
+    bytes.extend_from_slice(resources);
+}
+
+/// The table of an application with an interrupt, an exception and a software
+/// task, in the layout the `app!` macro emits it
+fn table() -> Vec<u8> {
+    let mut bytes = vec![];
+    bytes.extend_from_slice(b"RTFM");
+    bytes.extend_from_slice(&[meta::VERSION, 3, 2, 0]);
+
+    string(&mut bytes, "A");
+    bytes.push(2);
+    string(&mut bytes, "B");
+    bytes.push(0xff);
+
+    task(&mut bytes, "EXTI0", "EXTI0", 1, 0xff, 1000, &[0]);
+    task(&mut bytes, "SYS_TICK", "SysTick", 2, 15, 0x0102_0304, &[0]);
+    task(&mut bytes, "foo", "EXTI1", 1, 0xfe, 500, &[]);
+
+    bytes
+}
+
+#[test]
+fn parse() {
+    let table = meta::parse(&table()).unwrap();
+
+    assert_eq!(table.resources.len(), 2);
+    assert_eq!(table.resources[0].name, "A");
+    assert_eq!(table.resources[0].ceiling, Some(2));
+    assert_eq!(table.resources[1].name, "B");
+    assert_eq!(table.resources[1].ceiling, None);
+
+    assert_eq!(table.tasks.len(), 3);
+
+    let exti0 = &table.tasks[0];
+    assert_eq!(exti0.name, "EXTI0");
+    assert_eq!(exti0.symbol, "EXTI0");
+    assert_eq!(exti0.priority, 1);
+    assert_eq!(exti0.source, Source::Interrupt(None));
+    assert_eq!(exti0.interarrival, 1000);
+    assert_eq!(exti0.resources, [0]);
+
+    let sys_tick = &table.tasks[1];
+    assert_eq!(sys_tick.symbol, "SysTick");
+    assert_eq!(sys_tick.source, Source::Exception(15));
+    // little endian
+    assert_eq!(sys_tick.interarrival, 0x0102_0304);
+
+    let foo = &table.tasks[2];
+    assert_eq!(foo.symbol, "EXTI1");
+    assert_eq!(foo.source, Source::Software(None));
+    assert!(foo.resources.is_empty());
+}
+
+#[test]
+fn magic() {
+    let mut bytes = table();
+    bytes[0] = b'X';
+
+    assert!(meta::parse(&bytes).is_err());
+}
+
+#[test]
+fn version() {
+    let mut bytes = table();
+    bytes[4] = meta::VERSION + 1;
+
+    assert!(meta::parse(&bytes).is_err());
+}
+
+#[test]
+fn truncated() {
+    let bytes = table();
+
+    for len in 0..bytes.len() {
+        assert!(meta::parse(&bytes[..len]).is_err(), "{} bytes", len);
+    }
+}
+
+#[test]
+fn unknown_resource() {
+    let mut bytes = vec![];
+    bytes.extend_from_slice(b"RTFM");
+    bytes.extend_from_slice(&[meta::VERSION, 1, 0, 0]);
+    task(&mut bytes, "EXTI0", "EXTI0", 1, 0xff, 1000, &[0]);
+
+    assert!(meta::parse(&bytes).is_err());
+}
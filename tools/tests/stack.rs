@@ -0,0 +1,127 @@
+//! Worst case usage of the shared stack
+extern crate rtfm_tools;
+
+use std::collections::HashMap;
+
+use rtfm_tools::model::App;
+use rtfm_tools::stack::{self, Program};
+
+/// `EXTI0`, an interrupt, preempted by the dispatcher of the software task
+/// `foo` and by the `SysTick` handler of the timer queue
+const APP: &str = r#"{
+  "version": 2,
+  "device": "stm32f413",
+  "init": { "path": "init", "resources": [], "stack": null },
+  "idle": { "path": "idle", "resources": [], "critical_sections": {}, "stack": null },
+  "tasks": [
+    {
+      "name": "EXTI0", "kind": "interrupt", "symbol": "EXTI0", "path": "exti0", "priority": 1,
+      "interarrival": 100, "deadline": 100, "wcet": null, "stack": 64, "enabled": true,
+      "resources": [], "critical_sections": {}
+    },
+    {
+      "name": "foo", "kind": "software", "symbol": "EXTI1", "path": "foo", "priority": 2,
+      "interarrival": 100, "deadline": 100, "wcet": null, "stack": null, "enabled": true,
+      "resources": [], "critical_sections": {}
+    }
+  ],
+  "dispatchers": [{ "priority": 2, "symbol": "EXTI1" }],
+  "timer_queue": { "priority": 3, "symbol": "SysTick" },
+  "resources": []
+}"#;
+
+fn program(frames: &[(&str, u64)], calls: &[(&str, Option<&[&str]>)]) -> Program {
+    Program {
+        frames: frames
+            .iter()
+            .map(|&(name, frame)| (name.to_owned(), frame))
+            .collect(),
+        calls: calls
+            .iter()
+            .map(|&(name, callees)| {
+                (
+                    name.to_owned(),
+                    callees.map(|c| c.iter().map(|c| (*c).to_owned()).collect()),
+                )
+            })
+            .collect::<HashMap<_, _>>(),
+    }
+}
+
+#[test]
+fn usage() {
+    let program = program(
+        &[("main", 8), ("a", 16), ("b", 32), ("c", 4)],
+        &[
+            ("main", Some(&["a", "c"])),
+            ("a", Some(&["b", "c"])),
+            ("b", Some(&[])),
+            ("c", Some(&[])),
+        ],
+    );
+
+    assert_eq!(stack::usage("b", &program).unwrap(), 32);
+    assert_eq!(stack::usage("a", &program).unwrap(), 16 + 32);
+    assert_eq!(stack::usage("main", &program).unwrap(), 8 + 16 + 32);
+}
+
+#[test]
+fn function_pointers() {
+    let program = program(
+        &[("main", 8), ("a", 16)],
+        &[("main", Some(&["a"])), ("a", None)],
+    );
+
+    assert!(stack::usage("main", &program).is_err());
+}
+
+#[test]
+fn recursion() {
+    let program = program(
+        &[("main", 8), ("a", 16), ("b", 16)],
+        &[("main", Some(&["a"])), ("a", Some(&["b"])), ("b", Some(&["a"]))],
+    );
+
+    assert!(stack::usage("main", &program).is_err());
+}
+
+#[test]
+fn unknown_frame() {
+    let program = program(&[("main", 8)], &[("main", Some(&["a"])), ("a", Some(&[]))]);
+
+    assert!(stack::usage("main", &program).is_err());
+}
+
+#[test]
+fn handlers() {
+    let app = App::parse(APP).unwrap();
+    let program = program(
+        &[("main", 16), ("EXTI1", 24), ("foo", 40), ("SysTick", 48)],
+        &[
+            ("main", Some(&[])),
+            ("EXTI1", Some(&["foo"])),
+            ("foo", Some(&[])),
+            ("SysTick", Some(&[])),
+        ],
+    );
+
+    let bound = stack::bound(&app, Some(&program), stack::FRAME).unwrap();
+
+    let levels = bound
+        .levels
+        .iter()
+        .map(|&(priority, ref usage)| (priority, &*usage.name, usage.stack))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        levels,
+        [(1, "EXTI0", 64), (2, "EXTI1", 24 + 40), (3, "SysTick", 48)]
+    );
+    assert_eq!(bound.total(), 16 + 64 + (24 + 40) + 48 + 3 * stack::FRAME);
+}
+
+#[test]
+fn handlers_need_the_elf() {
+    let app = App::parse(APP).unwrap();
+
+    assert!(stack::bound(&app, None, stack::FRAME).is_err());
+}
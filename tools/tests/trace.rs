@@ -0,0 +1,140 @@
+//! Decoding of the `RTFM_TRACE` buffer
+extern crate rtfm_tools;
+
+use rtfm_tools::trace::{self, Event, Kind};
+
+fn le32(bytes: &mut Vec<u8>, value: u32) {
+    for i in 0..4 {
+        bytes.push((value >> (8 * i)) as u8);
+    }
+}
+
+/// A dump of a buffer of `capacity` entries after logging `count` events;
+/// `entries` are `(timestamp, kind, ceiling, id)` in buffer order
+fn dump(capacity: u32, count: u32, entries: &[(u32, u8, u8, u16)]) -> Vec<u8> {
+    let mut bytes = vec![];
+    le32(&mut bytes, trace::MAGIC);
+    le32(&mut bytes, trace::VERSION);
+    le32(&mut bytes, capacity);
+    le32(&mut bytes, count);
+
+    for i in 0..capacity as usize {
+        let (timestamp, kind, ceiling, id) = entries.get(i).cloned().unwrap_or((0, 0, 0, 0));
+        le32(&mut bytes, timestamp);
+        bytes.push(kind);
+        bytes.push(ceiling);
+        bytes.push(id as u8);
+        bytes.push((id >> 8) as u8);
+    }
+
+    bytes
+}
+
+#[test]
+fn decode() {
+    let bytes = dump(
+        8,
+        7,
+        &[
+            (10, 1, 0, 0),
+            (20, 3, 2, 1),
+            (30, 4, 0, 1),
+            (40, 3, 3, 0xffff),
+            (50, 5, 2, 0xffff),
+            (60, 6, 0, 0xffff),
+            (0x0102_0304, 2, 0, 0x0100),
+        ],
+    );
+
+    let trace = trace::decode(&bytes).unwrap();
+
+    assert_eq!(trace.lost, 0);
+    assert_eq!(
+        trace.events,
+        [
+            Event { timestamp: 10, kind: Kind::TaskStart(0) },
+            Event { timestamp: 20, kind: Kind::ClaimEnter(Some(1), 2) },
+            Event { timestamp: 30, kind: Kind::ClaimExit(Some(1)) },
+            Event { timestamp: 40, kind: Kind::ClaimEnter(None, 3) },
+            Event { timestamp: 50, kind: Kind::ThresholdEnter(2) },
+            Event { timestamp: 60, kind: Kind::ThresholdExit },
+            Event { timestamp: 0x0102_0304, kind: Kind::TaskEnd(0x0100) },
+        ]
+    );
+}
+
+#[test]
+fn full() {
+    let bytes = dump(2, 2, &[(1, 1, 0, 0), (2, 2, 0, 0)]);
+
+    let trace = trace::decode(&bytes).unwrap();
+
+    assert_eq!(trace.lost, 0);
+    assert_eq!(
+        trace.events.iter().map(|e| e.timestamp).collect::<Vec<_>>(),
+        [1, 2]
+    );
+}
+
+#[test]
+fn wraparound() {
+    // events 4 and 5 overwrote events 0 and 1
+    let bytes = dump(
+        4,
+        6,
+        &[(4, 1, 0, 1), (5, 2, 0, 1), (2, 1, 0, 0), (3, 2, 0, 0)],
+    );
+
+    let trace = trace::decode(&bytes).unwrap();
+
+    assert_eq!(trace.lost, 2);
+    assert_eq!(
+        trace.events,
+        [
+            Event { timestamp: 2, kind: Kind::TaskStart(0) },
+            Event { timestamp: 3, kind: Kind::TaskEnd(0) },
+            Event { timestamp: 4, kind: Kind::TaskStart(1) },
+            Event { timestamp: 5, kind: Kind::TaskEnd(1) },
+        ]
+    );
+}
+
+#[test]
+fn empty() {
+    let trace = trace::decode(&dump(4, 0, &[])).unwrap();
+
+    assert!(trace.events.is_empty());
+    assert_eq!(trace.lost, 0);
+}
+
+#[test]
+fn magic() {
+    let mut bytes = dump(4, 0, &[]);
+    bytes[0] = 0;
+
+    assert!(trace::decode(&bytes).is_err());
+}
+
+#[test]
+fn version() {
+    let mut bytes = dump(4, 0, &[]);
+    bytes[4] = trace::VERSION as u8 + 1;
+
+    assert!(trace::decode(&bytes).is_err());
+}
+
+#[test]
+fn truncated() {
+    let bytes = dump(4, 1, &[(1, 1, 0, 0)]);
+
+    for len in 0..bytes.len() {
+        assert!(trace::decode(&bytes[..len]).is_err(), "{} bytes", len);
+    }
+}
+
+#[test]
+fn unknown_kind() {
+    let bytes = dump(4, 1, &[(1, 9, 0, 0)]);
+
+    assert!(trace::decode(&bytes).is_err());
+}